@@ -0,0 +1,763 @@
+use std::collections::HashMap;
+use std::fs::read_to_string;
+use std::path::PathBuf;
+use std::process::ExitCode;
+use std::time::Duration;
+use std::time::Instant;
+
+use x11rb::connection::Connection;
+use x11rb::protocol::Event;
+use x11rb::protocol::xproto::ConnectionExt as _;
+use x11rb::protocol::xproto::EventMask;
+use x11rb::protocol::xproto::KEY_PRESS_EVENT;
+use x11rb::protocol::xproto::KeyButMask;
+use x11rb::protocol::xproto::KeyPressEvent;
+use x11rb::protocol::xproto::PropMode;
+use x11rb::protocol::xproto::Screen;
+use x11rb::protocol::xproto::Visualid;
+use x11rb::protocol::xproto::Window;
+use x11rb::wrapper::ConnectionExt as _;
+
+use crate::APP_NAME;
+use crate::Atoms;
+use crate::Conn;
+use crate::EXIT_NO_CANDIDATES;
+use crate::Result;
+use crate::config::Config;
+use crate::config::Overflow;
+use crate::config::Stacking;
+use crate::config::State;
+use crate::icons::IconCache;
+use crate::icons::desktop_entries;
+use crate::icons::match_desktop_entry;
+use crate::render::Area;
+use crate::render::CellCache;
+use crate::render::Frame;
+use crate::render::TextRenderer;
+use crate::render::compute_layout;
+use crate::render::compute_page_size;
+use crate::render::compute_window_geometry;
+use crate::render::draw_list;
+use crate::tasks::Task;
+use crate::tasks::TaskList;
+use crate::tasks::class_matches;
+use crate::tasks::strip_field_codes;
+use crate::tasks::virtual_task;
+use crate::x11::create_graphic_context;
+use crate::x11::create_window;
+use crate::x11::get_suspended;
+use crate::x11::get_window_class;
+use crate::x11::request_pointer_warp;
+use crate::x11::request_suspended_publish;
+use crate::x11::request_window_activation;
+use crate::x11::send_frame;
+use crate::x11::wait_for_activation;
+
+pub(crate) const BENCH_RUNS: usize = 20;
+pub(crate) const BENCH_TASKS: [usize; 3] = [10, 50, 200];
+pub(crate) const USAGE: &str = "\
+usage: goto [options]
+
+options:
+  --events    print task events (added, removed, retitled, focused) as json lines on stdout
+  --oneshot   show the list, focus the selected window and exit
+  --dmenu     show the list and exit without focusing the selected window
+  --focus-class <class>
+              focus a window matching the class (or instance) and exit
+  --focus-wid <wid>
+              focus the window with the given id (e.g. `0x3c00005`) and exit
+  --or-exec <command>
+              run the command with `sh -c` when --focus-class or --focus-wid find no window
+  --run-or-raise <class> <command>
+              focus a window matching the class, cycling between them on repeated invocations,
+              or run the command when there is none
+  --set <key> <value>
+              set a key in the config file, keeping the rest of the file as is, and exit
+  --alias <wid> <name>
+              show the name instead of the title of the window, an empty name removes the
+              alias, and exit
+  --alias-class <wid> <name>
+              same for every window with the class and instance of the window, kept across
+              restarts, and exit
+  --suspend <on|off|toggle|status>
+              let the keys through to the focused window, or grab them again, in the daemon
+              running on the display, and print whether it's suspended
+  --save-session <name>
+              record the class and title of the listed windows under the name and exit
+  --restore-session <name>
+              launch the applications of the recorded session that have no window and exit
+  --bench     measure the icon loading, the redraws and the keypress to frame latency and exit
+  --display <display>
+              connect to the display (e.g. `:1`) instead of `$DISPLAY`, the daemon accepts it more
+              than once to serve several displays
+  --config <path>
+              read the config from the file instead of `$XDG_CONFIG_HOME/goto/config`
+  --state <path>
+              keep the state in the file instead of `$XDG_STATE_HOME/goto/state`
+  -h, --help  print this help
+
+in --oneshot and --dmenu modes the selected window id and class are printed on stdout, exit codes:
+  0  a window was selected
+  1  the selection was cancelled
+  2  there were no windows to select
+  3  an error occurred";
+
+#[derive(PartialEq)]
+pub(crate) enum Mode {
+    Daemon,
+    Oneshot,
+    Dmenu,
+}
+pub(crate) enum Target {
+    Class(String),
+    Wid(Window),
+}
+#[derive(Clone, Copy)]
+pub(crate) enum Suspend {
+    On,
+    Off,
+    Toggle,
+    Status,
+}
+pub(crate) enum Alias {
+    Window(Window, String),
+    Class(Window, String),
+}
+pub(crate) enum Session {
+    Save(String),
+    Restore(String),
+}
+pub(crate) struct Args {
+    pub(crate) events: bool,
+    pub(crate) mode: Mode,
+    pub(crate) focus: Option<Target>,
+    pub(crate) or_exec: Option<String>,
+    pub(crate) cycle: bool,
+    pub(crate) set: Option<(String, String)>,
+    pub(crate) alias: Option<Alias>,
+    pub(crate) suspend: Option<Suspend>,
+    pub(crate) session: Option<Session>,
+    pub(crate) bench: bool,
+    pub(crate) displays: Vec<String>,
+    pub(crate) config: Option<PathBuf>,
+    pub(crate) state: Option<PathBuf>,
+}
+impl Args {
+    pub(crate) fn parse() -> Result<Self> {
+        let mut this = Self {
+            events: false,
+            mode: Mode::Daemon,
+            focus: None,
+            or_exec: None,
+            cycle: false,
+            set: None,
+            alias: None,
+            suspend: None,
+            session: None,
+            bench: false,
+            displays: vec![],
+            config: None,
+            state: None,
+        };
+        let mut args = std::env::args().skip(1);
+        while let Some(arg) = args.next() {
+            let mut value = || args.next().ok_or(format!("missing value for `{arg}`"));
+            match arg.as_str() {
+                "--events" => this.events = true,
+                "--oneshot" => this.mode = Mode::Oneshot,
+                "--dmenu" => this.mode = Mode::Dmenu,
+                "--focus-class" => this.focus = Some(Target::Class(value()?)),
+                "--focus-wid" => this.focus = Some(Target::Wid(str_to_wid(&value()?)?)),
+                "--or-exec" => this.or_exec = Some(value()?),
+                "--run-or-raise" => {
+                    this.focus = Some(Target::Class(value()?));
+                    this.or_exec = Some(value()?);
+                    this.cycle = true;
+                }
+                "--set" => this.set = Some((value()?, value()?)),
+                "--alias" => this.alias = Some(Alias::Window(str_to_wid(&value()?)?, value()?)),
+                "--alias-class" => {
+                    this.alias = Some(Alias::Class(str_to_wid(&value()?)?, value()?));
+                }
+                "--suspend" => this.suspend = Some(str_to_suspend(&value()?)?),
+                "--save-session" => this.session = Some(Session::Save(str_to_session(&value()?)?)),
+                "--restore-session" => {
+                    this.session = Some(Session::Restore(str_to_session(&value()?)?));
+                }
+                "--bench" => this.bench = true,
+                "--display" => this.displays.push(value()?),
+                "--config" => this.config = Some(PathBuf::from(value()?)),
+                "--state" => this.state = Some(PathBuf::from(value()?)),
+                "-h" | "--help" => {
+                    println!("{USAGE}");
+                    std::process::exit(0);
+                }
+                _ => return Err(format!("unknown argument `{arg}`, see `--help`").into()),
+            }
+        }
+        Ok(this)
+    }
+    /// the file given with `--config`, or the one in the config directory
+    pub(crate) fn config_path(&self) -> Option<PathBuf> {
+        self.config.clone().or_else(Config::config_path)
+    }
+    /// the file given with `--state`, or the one in the state directory
+    pub(crate) fn state_path(&self) -> Option<PathBuf> {
+        self.state.clone().or_else(State::state_path)
+    }
+}
+/// validates the value and writes it to the config file
+pub(crate) fn set_config_value(path: Option<PathBuf>, key: &str, value: &str) -> Result<ExitCode> {
+    let line = format!("{key}: {value}");
+    let mut conf = Config::defaults(96.0);
+    if let Some(warning) = conf.parse(&line, 96.0).pop() {
+        return Err(warning.into());
+    }
+    let path = path.ok_or("`$XDG_CONFIG_HOME` and `$HOME` are not set")?;
+    let contents = read_to_string(&path).unwrap_or_default();
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    // a crash midway leaves the old config in place
+    let tmp = path.with_extension("tmp");
+    std::fs::write(&tmp, set_config_line(&contents, key, &line))?;
+    std::fs::rename(tmp, path)?;
+    Ok(ExitCode::SUCCESS)
+}
+/// replaces the line in effect for the key, the comments and the other lines are kept
+pub(crate) fn set_config_line(contents: &str, key: &str, line: &str) -> String {
+    let mut lines: Vec<&str> = contents.lines().collect();
+    let current = lines.iter().rposition(|l| {
+        let l = l.trim();
+        !l.starts_with('#') && l.split_once(':').is_some_and(|(k, _)| k.trim() == key)
+    });
+    match current {
+        Some(idx) => lines[idx] = line,
+        None => lines.push(line),
+    }
+    let mut contents = lines.join("\n");
+    contents.push('\n');
+    contents
+}
+pub(crate) fn str_to_wid(value: &str) -> Result<Window> {
+    let value = value.trim();
+    let wid = match value.strip_prefix("0x") {
+        Some(hex) => Window::from_str_radix(hex, 16),
+        None => value.parse(),
+    };
+    wid.map_err(|e| format!("invalid window id `{value}`: {e}").into())
+}
+pub(crate) fn str_to_suspend(value: &str) -> Result<Suspend> {
+    match value.trim().to_lowercase().as_str() {
+        "on" => Ok(Suspend::On),
+        "off" => Ok(Suspend::Off),
+        "toggle" => Ok(Suspend::Toggle),
+        "status" => Ok(Suspend::Status),
+        _ => Err(
+            format!("invalid suspend: `{value}`, expecting: `on`, `off`, `toggle`, `status`")
+                .into(),
+        ),
+    }
+}
+/// asks the daemon to suspend or resume its grabs through `_GOTO_SUSPENDED`
+pub(crate) fn request_suspend(
+    conn: &Conn,
+    screen: &Screen,
+    atoms: &Atoms,
+    suspend: Suspend,
+) -> Result<ExitCode> {
+    let current =
+        get_suspended(conn, screen, atoms)?.ok_or("no daemon is running on the display")?;
+    let suspended = match suspend {
+        Suspend::On => true,
+        Suspend::Off => false,
+        Suspend::Toggle => !current,
+        Suspend::Status => current,
+    };
+    if suspended != current {
+        request_suspended_publish(conn, screen, atoms, suspended)?;
+        conn.flush()?;
+    }
+    println!("{}", if suspended { "suspended" } else { "running" });
+    Ok(ExitCode::SUCCESS)
+}
+/// the aliases set with `--alias-class`, stored as `alias.<instance>/<class>` keys
+pub(crate) fn load_aliases(state: &State) -> HashMap<(String, String), String> {
+    state
+        .values
+        .iter()
+        .filter_map(|(key, alias)| {
+            let (instance, class) = key.strip_prefix("alias.")?.split_once('/')?;
+            Some((
+                (unescape_key(instance)?, unescape_key(class)?),
+                alias.clone(),
+            ))
+        })
+        .collect()
+}
+pub(crate) fn store_alias(state: &mut State, (instance, class): &(String, String), alias: &str) {
+    let key = format!("alias.{}/{}", escape_key(instance), escape_key(class));
+    match alias.trim() {
+        "" => state.values.remove(&key),
+        alias => state.values.insert(key, alias.to_string()),
+    };
+}
+/// the text as part of a key of the state file, with `%`, `/`, `:` and spaces as `%xx`
+pub(crate) fn escape_key(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '%' | '/' | ':' => out.push_str(&format!("%{:02x}", c as u32)),
+            c if c.is_whitespace() || c.is_control() => {
+                let mut buf = [0; 4];
+                for b in c.encode_utf8(&mut buf).bytes() {
+                    out.push_str(&format!("%{b:02x}"));
+                }
+            }
+            c => out.push(c),
+        }
+    }
+    out
+}
+pub(crate) fn unescape_key(key: &str) -> Option<String> {
+    let mut bytes = Vec::with_capacity(key.len());
+    let mut rest = key.as_bytes();
+    while let Some((&b, tail)) = rest.split_first() {
+        if b == b'%' {
+            let hex = std::str::from_utf8(tail.get(..2)?).ok()?;
+            bytes.push(u8::from_str_radix(hex, 16).ok()?);
+            rest = &tail[2..];
+        } else {
+            bytes.push(b);
+            rest = tail;
+        }
+    }
+    String::from_utf8(bytes).ok()
+}
+/// sets the `_GOTO_ALIAS` of the window, which the daemon shows instead of its title
+pub(crate) fn set_alias(conn: &Conn, atoms: &Atoms, wid: Window, alias: &str) -> Result<ExitCode> {
+    match alias.trim() {
+        "" => conn.delete_property(wid, atoms._GOTO_ALIAS)?,
+        alias => conn.change_property8(
+            PropMode::REPLACE,
+            wid,
+            atoms._GOTO_ALIAS,
+            atoms.UTF8_STRING,
+            alias.as_bytes(),
+        )?,
+    };
+    conn.flush()?;
+    Ok(ExitCode::SUCCESS)
+}
+/// saves the alias of the class and instance of the window, the daemon reloads them when the
+/// `_GOTO_ALIAS` of the root window changes
+pub(crate) fn set_class_alias(
+    conn: &Conn,
+    screen: &Screen,
+    atoms: &Atoms,
+    wid: Window,
+    alias: &str,
+    state_path: Option<PathBuf>,
+) -> Result<ExitCode> {
+    let class = get_window_class(conn, atoms, wid)?;
+    let mut state = State::load(state_path);
+    store_alias(&mut state, &class, alias);
+    state.save()?;
+    conn.change_property8(
+        PropMode::REPLACE,
+        screen.root,
+        atoms._GOTO_ALIAS,
+        atoms.UTF8_STRING,
+        alias.trim().as_bytes(),
+    )?;
+    conn.flush()?;
+    Ok(ExitCode::SUCCESS)
+}
+/// the name is part of the keys of the state file
+pub(crate) fn str_to_session(value: &str) -> Result<String> {
+    let value = value.trim();
+    if value.is_empty() || value.contains(|c: char| c == ':' || c.is_whitespace()) {
+        return Err(
+            format!("invalid session name `{value}`, expecting no spaces and no `:`").into(),
+        );
+    }
+    Ok(value.to_string())
+}
+/// a window of a saved session, the title is only there for the reader of the state file
+#[derive(Debug, PartialEq)]
+pub(crate) struct SessionEntry {
+    pub(crate) class: (String, String),
+    pub(crate) title: String,
+}
+impl SessionEntry {
+    pub(crate) fn to_value(&self) -> String {
+        let title = self.title.replace(['\t', '\n', '\r'], " ");
+        format!("{}\t{}\t{title}", self.class.0, self.class.1)
+    }
+    pub(crate) fn from_value(value: &str) -> Option<Self> {
+        let mut fields = value.splitn(3, '\t');
+        let class = (fields.next()?.to_string(), fields.next()?.to_string());
+        let title = fields.next().unwrap_or_default().to_string();
+        Some(Self { class, title })
+    }
+}
+/// the entries of the session, stored as `session.<name>.<index>` keys in the order they were
+/// listed
+pub(crate) fn load_session(state: &State, name: &str) -> Vec<SessionEntry> {
+    let prefix = format!("session.{name}.");
+    let mut entries: Vec<_> = state
+        .values
+        .iter()
+        .filter_map(|(key, val)| Some((key.strip_prefix(&prefix)?.parse::<usize>().ok()?, val)))
+        .filter_map(|(idx, val)| Some((idx, SessionEntry::from_value(val)?)))
+        .collect();
+    entries.sort_by_key(|(idx, _)| *idx);
+    entries.into_iter().map(|(_, entry)| entry).collect()
+}
+/// replaces the session with the entries
+pub(crate) fn store_session(state: &mut State, name: &str, entries: &[SessionEntry]) {
+    let prefix = format!("session.{name}.");
+    state.values.retain(|key, _| !key.starts_with(&prefix));
+    for (idx, entry) in entries.iter().enumerate() {
+        state.set(&format!("{prefix}{idx}"), entry.to_value());
+    }
+}
+/// the classes of the session that have no window, once each
+pub(crate) fn missing_classes<'a>(
+    entries: &'a [SessionEntry],
+    present: &[(String, String)],
+) -> Vec<&'a (String, String)> {
+    let same = |a: &(String, String), b: &(String, String)| {
+        a.0.eq_ignore_ascii_case(&b.0) && a.1.eq_ignore_ascii_case(&b.1)
+    };
+    let mut missing: Vec<&(String, String)> = vec![];
+    for entry in entries {
+        if !present.iter().any(|class| same(class, &entry.class))
+            && !missing.iter().any(|class| same(class, &entry.class))
+        {
+            missing.push(&entry.class);
+        }
+    }
+    missing
+}
+/// records or restores a session, see `--save-session` and `--restore-session`
+pub(crate) fn run_session(
+    conn: &Conn,
+    atoms: &Atoms,
+    wids: Vec<Window>,
+    session: &Session,
+    state_path: Option<PathBuf>,
+) -> Result<ExitCode> {
+    let mut tasks = TaskList::new();
+    tasks.diff_update(wids, conn, atoms);
+    let mut state = State::load(state_path);
+    match session {
+        Session::Save(name) => {
+            let entries: Vec<_> = tasks
+                .list_ascending()
+                .0
+                .map(|task| SessionEntry {
+                    class: task.class.clone(),
+                    title: task.title.clone(),
+                })
+                .collect();
+            store_session(&mut state, name, &entries);
+            state.save()?;
+            println!("{} windows saved to `{name}`", entries.len());
+        }
+        Session::Restore(name) => {
+            let entries = load_session(&state, name);
+            if entries.is_empty() {
+                return Err(format!("no session named `{name}`").into());
+            }
+            let present: Vec<_> = tasks.list_ascending().0.map(|t| t.class.clone()).collect();
+            for class in missing_classes(&entries, &present) {
+                let command = match_desktop_entry(desktop_entries(), class)
+                    .and_then(|entry| entry.exec.as_deref())
+                    .map(strip_field_codes);
+                let Some(command) = command else {
+                    eprintln!("[WARNING] no desktop entry launches `{}`", class.1);
+                    continue;
+                };
+                println!("{command}");
+                std::process::Command::new("sh")
+                    .arg("-c")
+                    .arg(&command)
+                    .spawn()?;
+            }
+        }
+    }
+    Ok(ExitCode::SUCCESS)
+}
+/// activates the target window without showing the list, or runs the fallback command
+pub(crate) fn focus_target(
+    conn: &Conn,
+    screen: &Screen,
+    atoms: &Atoms,
+    conf: &Config,
+    wids: Vec<Window>,
+    args: &Args,
+) -> Result<ExitCode> {
+    let Some(target) = &args.focus else {
+        return Ok(ExitCode::from(EXIT_NO_CANDIDATES));
+    };
+    let matches: Vec<_> = wids
+        .into_iter()
+        .filter(|wid| match target {
+            Target::Wid(target) => wid == target,
+            Target::Class(target) => get_window_class(conn, atoms, *wid)
+                .is_ok_and(|class| class_matches(&class, std::slice::from_ref(target))),
+        })
+        .collect();
+    let mut wid = matches.first().copied();
+    if args.cycle
+        && let Target::Class(class) = target
+    {
+        // pick the window after the one raised by the previous invocation
+        let mut state = State::load(args.state_path());
+        let key = format!("last_raised.{}", class.to_lowercase());
+        let last = state.get(&key).and_then(|wid| str_to_wid(wid).ok());
+        if let Some(idx) = matches.iter().position(|wid| Some(*wid) == last) {
+            wid = Some(matches[(idx + 1) % matches.len()]);
+        }
+        if let Some(wid) = wid {
+            state.set(&key, format!("{wid:#x}"));
+            if let Err(e) = state.save() {
+                eprintln!("[WARNING] failed to save the state: {e}");
+            }
+        }
+    }
+    if let Some(wid) = wid {
+        let wid = request_window_activation(conn, screen, atoms, conf, wid)?;
+        if conf.focus_follows_mouse {
+            let _ = request_pointer_warp(conn, wid);
+        }
+        conn.flush()?;
+        wait_for_activation(conn, screen, atoms, wid)?;
+        return Ok(ExitCode::SUCCESS);
+    }
+    if let Some(command) = &args.or_exec {
+        std::process::Command::new("sh")
+            .arg("-c")
+            .arg(command)
+            .spawn()?;
+        return Ok(ExitCode::SUCCESS);
+    }
+    Ok(ExitCode::from(EXIT_NO_CANDIDATES))
+}
+/// times the stages of a switch on this display, with the real windows and then with made up
+/// lists of growing length
+pub(crate) fn bench(
+    conn: &Conn,
+    screen: &Screen,
+    atoms: &Atoms,
+    conf: &Config,
+    wids: Vec<Window>,
+    (depth, visual): (u8, Visualid),
+) -> Result<ExitCode> {
+    let median = |mut times: Vec<Duration>| {
+        times.sort();
+        times[times.len() / 2]
+    };
+
+    let start = Instant::now();
+    let mut tasks = TaskList::new();
+    tasks.diff_update(wids, conn, atoms);
+    println!("windows: {} read in {:?}", tasks.len(), start.elapsed());
+    let icons = &mut IconCache::new(conf.icon_cache);
+    let start = Instant::now();
+    icons.set_icons(conn, atoms, &tasks);
+    println!(
+        "icons: {} classes loaded in {:?}",
+        icons.icons.len(),
+        start.elapsed()
+    );
+
+    // the made up tasks reuse the classes of the real ones, so that they have icons
+    let mut classes: Vec<_> = tasks
+        .list_ascending()
+        .0
+        .map(|task| task.class.1.clone())
+        .collect();
+    if classes.is_empty() {
+        classes.push(APP_NAME.to_string());
+    }
+    let tr = &mut TextRenderer::new(conf)?;
+    println!("redraws with put_image, median of {BENCH_RUNS} runs:");
+    for count in BENCH_TASKS {
+        let mut tasks = TaskList::new();
+        let fake = (0..count).map(|i| {
+            let class = &classes[i % classes.len()];
+            virtual_task(
+                "bench",
+                &i.to_string(),
+                class,
+                &format!("{class} {i}"),
+                String::new(),
+            )
+        });
+        tasks.update_virtual(fake.collect());
+        tasks.set_paginate(conf.overflow == Overflow::Paginate);
+        let output = Area::of_screen(screen);
+        tasks.set_layout(compute_layout(conf, output, tasks.rows()));
+        tasks.set_page_size(compute_page_size(conf, output, tasks.rows()));
+        icons.set_icons(conn, atoms, &tasks);
+        let geometry = compute_window_geometry(conf, output, None, tasks.rows())
+            .unwrap_or(Area::new(0.0, 0.0, 1.0, 1.0));
+        let window = create_window(
+            conn,
+            screen,
+            atoms,
+            geometry,
+            depth,
+            visual,
+            Stacking::Above,
+        )?;
+        let gc = create_graphic_context(conn, window)?;
+        let mut frame = Frame::new(geometry.w as u32, geometry.h as u32);
+
+        let (mut draws, mut sends) = (vec![], vec![]);
+        for _ in 0..BENCH_RUNS {
+            // nothing cached, as when the list is first shown
+            tr.cells = CellCache::new();
+            let start = Instant::now();
+            draw_list(&mut frame, conf, &tasks, None, tr, icons);
+            frame.reduce_colors(conf.color_depth, conf.dither);
+            draws.push(start.elapsed());
+            let start = Instant::now();
+            send_frame(conn, window, gc, &frame, depth)?;
+            conn.get_input_focus()?.reply()?;
+            sends.push(start.elapsed());
+        }
+        println!(
+            "  {count} tasks ({}x{}): draw {:?}, send {:?}",
+            frame.width(),
+            frame.height(),
+            median(draws),
+            median(sends)
+        );
+
+        // a key press sent to the window itself, up to the frame of the next selection
+        let mut latencies = vec![];
+        for _ in 0..BENCH_RUNS {
+            let start = Instant::now();
+            let press = KeyPressEvent {
+                response_type: KEY_PRESS_EVENT,
+                detail: 0,
+                sequence: 0,
+                time: x11rb::CURRENT_TIME,
+                root: screen.root,
+                event: window,
+                child: x11rb::NONE,
+                root_x: 0,
+                root_y: 0,
+                event_x: 0,
+                event_y: 0,
+                state: KeyButMask::default(),
+                same_screen: true,
+            };
+            conn.send_event(false, window, EventMask::KEY_PRESS, press)?;
+            conn.flush()?;
+            while !matches!(conn.wait_for_event()?, Event::KeyPress(e) if e.event == window) {}
+            tasks.select_older();
+            draw_list(&mut frame, conf, &tasks, None, tr, icons);
+            frame.reduce_colors(conf.color_depth, conf.dither);
+            send_frame(conn, window, gc, &frame, depth)?;
+            conn.get_input_focus()?.reply()?;
+            latencies.push(start.elapsed());
+        }
+        println!("  {count} tasks: keypress to frame {:?}", median(latencies));
+        conn.destroy_window(window)?;
+    }
+    Ok(ExitCode::SUCCESS)
+}
+pub(crate) fn print_event(event: &str, wid: Window, task: Option<&Task>) {
+    match task {
+        Some(task) => println!(
+            r#"{{"event":"{event}","wid":{wid},"instance":{},"class":{},"title":{}}}"#,
+            json_string(&task.class.0),
+            json_string(&task.class.1),
+            json_string(&task.title),
+        ),
+        None => println!(r#"{{"event":"{event}","wid":{wid}}}"#),
+    }
+}
+pub(crate) fn json_string(value: &str) -> String {
+    let mut out = String::with_capacity(value.len() + 2);
+    out.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\t' => out.push_str("\\t"),
+            '\r' => out.push_str("\\r"),
+            c if c.is_control() => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use super::SessionEntry;
+    use super::load_session;
+    use super::missing_classes;
+    use super::set_config_line;
+    use super::store_session;
+    use super::str_to_session;
+    use crate::config::State;
+
+    #[test]
+    fn config_lines_are_set_in_place() {
+        let contents = "# colors\nbg_color: #000000 \n# bg_color: #ffffff\nborder_width: 1";
+        assert_eq!(
+            set_config_line(contents, "bg_color", "bg_color: #101010"),
+            "# colors\nbg_color: #101010\n# bg_color: #ffffff\nborder_width: 1\n"
+        );
+        // the last line is the one in effect
+        assert_eq!(
+            set_config_line("sort: recent\nsort: stacking\n", "sort", "sort: position"),
+            "sort: recent\nsort: position\n"
+        );
+        assert_eq!(set_config_line("", "tmux", "tmux: true"), "tmux: true\n");
+    }
+
+    #[test]
+    fn sessions() {
+        let entry = |instance: &str, class: &str, title: &str| SessionEntry {
+            class: (instance.to_string(), class.to_string()),
+            title: title.to_string(),
+        };
+        let mut state = State {
+            values: BTreeMap::new(),
+            path: None,
+        };
+        state.set("session.work.7", "stale\tStale\t".to_string());
+        state.set("session.play.0", "mpv\tmpv\tmovie".to_string());
+        let entries: Vec<_> = (0..12)
+            .map(|i| entry("kitty", "kitty", &format!("shell {i}")))
+            .chain([entry("firefox", "Firefox", "docs: a\ttab")])
+            .collect();
+        store_session(&mut state, "work", &entries);
+        let loaded = load_session(&state, "work");
+        // in the order they were saved, past the tenth, and the tabs of the title are dropped
+        assert_eq!(loaded.len(), 13);
+        assert_eq!(loaded[10], entry("kitty", "kitty", "shell 10"));
+        assert_eq!(loaded[12], entry("firefox", "Firefox", "docs: a tab"));
+        assert_eq!(load_session(&state, "play"), [entry("mpv", "mpv", "movie")]);
+        assert!(load_session(&state, "none").is_empty());
+
+        let present = [("KITTY".to_string(), "Kitty".to_string())];
+        let missing = missing_classes(&loaded, &present);
+        assert_eq!(missing, [&("firefox".to_string(), "Firefox".to_string())]);
+
+        assert!(str_to_session("my work").is_err());
+        assert!(str_to_session("a:b").is_err());
+        assert_eq!(str_to_session(" work ").unwrap(), "work");
+    }
+}
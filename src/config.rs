@@ -0,0 +1,1883 @@
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::fs::read_to_string;
+use std::path::Path;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::time::Duration;
+
+use fontdue::layout::HorizontalAlign;
+use fontdue::layout::VerticalAlign;
+use x11rb::protocol::xproto::Screen;
+use x11rb::resource_manager::Database;
+use xkbcommon::xkb::Keysym;
+use xkbcommon::xkb::keysym_from_name;
+
+use crate::APP_NAME;
+use crate::LOW_BANDWIDTH_INTERVAL;
+use crate::Result;
+use crate::render::Color;
+use crate::tasks::DEFAULT_TITLE_FALLBACK;
+use crate::tasks::Filter;
+use crate::tasks::MonitorLabels;
+use crate::tasks::Sort;
+use crate::tasks::TitleFallback;
+use crate::x11::XSetting;
+use crate::x11::get_dpi;
+
+pub const INCH_TO_MM: f32 = 25.4;
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum ListLayout {
+    Rows,
+    Columns,
+    /// rows wrapped in as many columns as needed
+    Grid,
+}
+/// the horizontal order of the cells, and of the columns
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum LayoutDirection {
+    /// icon, title and marker from left to right
+    Ltr,
+    /// everything mirrored, the marker is on the left
+    Rtl,
+}
+/// when the daemon populates the task list
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Startup {
+    /// right away, icons included
+    Eager,
+    /// on the first keypress, to keep the login light
+    Lazy,
+}
+/// how the window is sized
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum WindowSize {
+    /// grow with the number of tasks, up to the screen size
+    Grow,
+    /// always `width` by `height`, the tasks are scaled to fit
+    Fixed,
+}
+/// a copy of the text drawn under it, offset by `x` and `y`
+#[derive(Debug, Copy, Clone)]
+pub struct TextShadow {
+    pub x: f32,
+    pub y: f32,
+    pub color: Color,
+}
+/// a border of `width` pixels drawn around each glyph
+#[derive(Debug, Copy, Clone)]
+pub struct TextOutline {
+    pub width: f32,
+    pub color: Color,
+}
+/// what the loaded icons are shared by
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum IconCaching {
+    /// the windows of an application show the same icon
+    Class,
+    /// each window shows its own `_NET_WM_ICON`, like the profiles of a browser
+    Window,
+}
+/// where the contents of the windows are shown, captured with the composite extension
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Thumbnails {
+    Off,
+    /// in a cell of their own, after the icon
+    Beside,
+    /// in the cell of the icon, the icon is shown for the windows without one
+    Instead,
+}
+/// how many colors the frames are reduced to before being sent
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum ColorDepth {
+    Full,
+    /// 16 gray levels, for e-ink displays
+    Grayscale,
+    /// black and white, for 1-bit or 8-bit sessions like some vnc servers
+    Mono,
+}
+/// how raster icons are scaled to the cell size
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum IconScaling {
+    /// bilinear when growing, averaging the covered pixels when shrinking
+    Bilinear,
+    /// crisp pixels, for pixel art
+    Nearest,
+    /// nearest neighbor for integer upscales, bilinear otherwise
+    Auto,
+}
+/// where the list sits among the other windows
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Stacking {
+    /// on top, hinted with `_NET_WM_STATE_ABOVE` and raised when shown
+    Above,
+    /// wherever the compositor puts new windows
+    Normal,
+}
+/// an edge of the monitor
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Edge {
+    Top,
+    Bottom,
+    Left,
+    Right,
+}
+/// how the list appears when it's shown
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum EnterAnimation {
+    /// mapped in place
+    None,
+    /// from transparent, with `_NET_WM_WINDOW_OPACITY`, it needs a compositor
+    Fade,
+    /// from past an edge, the nearest to `location` when none is given
+    Slide(Option<Edge>),
+}
+/// the monitor the list is placed on
+#[derive(Debug, Clone, PartialEq)]
+pub enum MonitorChoice {
+    /// the one of the focused window
+    Focused,
+    /// the one under the pointer
+    Pointer,
+    /// the primary output set with xrandr
+    Primary,
+    /// an output by name, like `DP-1`
+    Named(String),
+}
+/// where the marker background is drawn
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum MarkerBg {
+    /// no background, the marker is drawn over the list
+    None,
+    /// behind each drawn marker
+    Cell,
+    /// along the whole marker column
+    Gutter,
+}
+/// where the marker goes relative to the title in the columns layout
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum MarkerPosition {
+    /// between the icon and the title
+    Above,
+    /// at the bottom of the column
+    Below,
+}
+/// what happens when the tasks don't fit on the screen
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Overflow {
+    /// squash the tasks down to `task_min_height`, then scroll
+    Shrink,
+    /// keep the task size and scroll the list to the selection
+    Scroll,
+    /// keep the task size and split the list in pages
+    Paginate,
+}
+/// how the entry of the focused window is shown
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum ShowCurrent {
+    Show,
+    Hide,
+    Dim,
+}
+/// how the selected task is told apart from the others
+#[derive(Debug, Copy, Clone)]
+pub enum SelectionStyle {
+    Box,
+    Underline,
+    LeftBar,
+    Invert,
+}
+#[derive(Debug, Copy, Clone)]
+pub enum Size {
+    Absolute(u32),
+    Relative(f32),
+}
+impl Size {
+    pub fn resolve(&self, dim: f32) -> f32 {
+        match self {
+            Size::Absolute(n) => *n as f32,
+            Size::Relative(n) => n * dim,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Anchor {
+    pub x: f32,
+    pub y: f32,
+}
+impl Anchor {
+    pub const TOP_LEFT: Self = Self::new(0.0, 0.0);
+    pub const TOP_CENTER: Self = Self::new(0.5, 0.0);
+    pub const TOP_RIGHT: Self = Self::new(1.0, 0.0);
+    pub const LEFT: Self = Self::new(0.0, 0.5);
+    pub const CENTER: Self = Self::new(0.5, 0.5);
+    pub const RIGHT: Self = Self::new(1.0, 0.5);
+    pub const BOTTOM_LEFT: Self = Self::new(0.0, 1.0);
+    pub const BOTTOM_CENTER: Self = Self::new(0.5, 1.0);
+    pub const BOTTOM_RIGHT: Self = Self::new(1.0, 1.0);
+    /// the `location` values, laid out like a numpad
+    pub const LOCATIONS: [Self; 9] = [
+        Self::TOP_LEFT,
+        Self::TOP_CENTER,
+        Self::TOP_RIGHT,
+        Self::LEFT,
+        Self::CENTER,
+        Self::RIGHT,
+        Self::BOTTOM_LEFT,
+        Self::BOTTOM_CENTER,
+        Self::BOTTOM_RIGHT,
+    ];
+
+    pub const fn new(x: f32, y: f32) -> Self {
+        Self { x, y }
+    }
+    /// the `location` value of the anchor, 5 for the ones in between
+    pub fn location(&self) -> usize {
+        Self::LOCATIONS
+            .iter()
+            .position(|anchor| anchor == self)
+            .map_or(5, |i| i + 1)
+    }
+    /// the edge of the monitor the anchor is the closest to, the bottom one for the center
+    pub fn nearest_edge(&self) -> Edge {
+        let (dx, dy) = ((self.x - 0.5).abs(), (self.y - 0.5).abs());
+        match (dx > dy, self.x < 0.5, self.y < 0.5) {
+            (true, true, _) => Edge::Left,
+            (true, false, _) => Edge::Right,
+            (false, _, true) if dy > 0.0 => Edge::Top,
+            (false, _, _) => Edge::Bottom,
+        }
+    }
+    /// the same anchor with left and right swapped
+    pub fn mirrored(&self) -> Self {
+        Self::new(1.0 - self.x, self.y)
+    }
+    pub fn resolve(&self, (aw, ah): (f32, f32), (bw, bh): (f32, f32)) -> (f32, f32) {
+        let x = (bw - aw) * self.x;
+        let y = (bh - ah) * self.y;
+        (x, y)
+    }
+}
+
+pub struct TaskStyle<'a> {
+    pub bg_color: &'a Color,
+    pub fg_color: &'a Color,
+    pub border_color: &'a Color,
+    pub border_width: f32,
+    pub selection: SelectionStyle,
+}
+#[derive(Clone)]
+pub struct Config {
+    pub font_1: Option<PathBuf>,
+    pub font_2: Option<PathBuf>,
+    pub font_3: Option<PathBuf>,
+    pub font_size: f32,
+    pub text_halign: HorizontalAlign,
+    pub text_valign: VerticalAlign,
+    pub line_height: f32,
+    pub text_shadow: Option<TextShadow>,
+    pub text_outline: Option<TextOutline>,
+    pub show_marker: bool,
+    pub marker: char,
+    pub marker_fg_color: Color,
+    pub marker_bg_color: Color,
+    pub marker_bg: MarkerBg,
+    pub marker_border_width: f32,
+    pub marker_border_color: Color,
+    pub marker_width: Option<f32>,
+    pub marker_height: Option<f32>,
+    pub marker_position: MarkerPosition,
+    pub numbered_marker: bool,
+    pub show_icons: bool,
+    pub icon_padding: Size,
+    pub icon_border_width: f32,
+    pub icon_border_color: Color,
+    pub icon_bg_color: Color,
+    pub icon_scaling: IconScaling,
+    pub icon_cache: IconCaching,
+    pub thumbnails: Thumbnails,
+    pub layout: ListLayout,
+    // `layout: auto`, the layout is picked by the number of tasks
+    pub auto_layout: bool,
+    pub layout_direction: LayoutDirection,
+    pub anchor: Anchor,
+    pub location_x_offset: Size,
+    pub location_y_offset: Size,
+    // `location: pointer`, the list is centered on the pointer instead of the anchor
+    pub follow_pointer: bool,
+    pub monitor: MonitorChoice,
+    pub bg_color: Color,
+    pub border_color: Color,
+    pub border_width: f32,
+    pub color_depth: ColorDepth,
+    pub dither: bool,
+    // none is `auto`, on when the server lacks the render extension
+    pub low_bandwidth: Option<bool>,
+    // the frames are sent at most once per interval, set by `low_bandwidth`
+    pub redraw_interval: Duration,
+    pub width: Size,
+    pub height: Size,
+    pub window_size: WindowSize,
+    pub stacking: Stacking,
+    pub raise_on_show: bool,
+    pub enter_animation: EnterAnimation,
+    pub col_sep_width: f32,
+    pub col_sep_color: Color,
+    pub row_sep_width: f32,
+    pub row_sep_color: Color,
+    pub task_height: Size,
+    pub task_width: Size,
+    pub task_min_height: f32,
+    pub overflow: Overflow,
+    pub task_bg_color: Color,
+    pub task_fg_color: Color,
+    pub task_border_color: Color,
+    pub task_border_width: f32,
+    pub task_gradient: bool,
+    pub task_gap: f32,
+    pub selected_task_bg_color: Color,
+    pub selected_task_fg_color: Color,
+    pub selected_task_border_color: Color,
+    pub selected_task_border_width: f32,
+    pub key_quit: Keysym,
+    pub key_next: Keysym,
+    pub key_prev: Keysym,
+    pub key_kill: Keysym,
+    pub key_toggle_scope: Keysym,
+    pub key_toggle_monitor: Keysym,
+    pub key_palette: Keysym,
+    pub key_search: Keysym,
+    pub key_move_overlay: Keysym,
+    pub key_select: Keysym,
+    pub key_mod: Keysym,
+    pub key_suspend: Option<Keysym>,
+    pub passthrough_class: Vec<String>,
+    pub focus_follows_mouse: bool,
+    pub unminimize_on_focus: bool,
+    pub quick_switch: bool,
+    pub persist_toggles: bool,
+    pub show_new_badge: bool,
+    pub new_badge_fg_color: Color,
+    pub new_badge_bg_color: Color,
+    pub sticky_badge: String,
+    pub sticky_badge_fg_color: Color,
+    pub sticky_badge_bg_color: Color,
+    pub filter: Filter,
+    pub override_redirect_classes: Vec<String>,
+    pub collapse_transients: bool,
+    pub title_fallback: Vec<TitleFallback>,
+    pub title_format: String,
+    pub aliases: Vec<(String, String)>,
+    pub providers: Vec<String>,
+    pub tmux: bool,
+    pub closed_history: usize,
+    pub startup: Startup,
+    pub ping: bool,
+    pub unresponsive_badge_fg_color: Color,
+    pub unresponsive_badge_bg_color: Color,
+    pub show_tooltips: bool,
+    pub tooltip_fg_color: Color,
+    pub tooltip_bg_color: Color,
+    pub debug_overlay: bool,
+    pub sort: Sort,
+    pub monitor_labels: MonitorLabels,
+    pub desktop_sections: bool,
+    pub group_by_class: bool,
+    pub count_badge_fg_color: Color,
+    pub count_badge_bg_color: Color,
+    pub header_fg_color: Color,
+    pub header_bg_color: Color,
+    pub selection_style: SelectionStyle,
+    pub show_current: ShowCurrent,
+    pub hover_bg_color: Color,
+    pub match_fg_color: Color,
+    pub light_theme: Option<PathBuf>,
+    pub dark_theme: Option<PathBuf>,
+}
+impl Config {
+    pub fn new(
+        config_path: Option<&Path>,
+        state_path: Option<&Path>,
+        screen: &Screen,
+        res_db: &Database,
+        xsettings: &HashMap<String, XSetting>,
+    ) -> Self {
+        let dpi = get_dpi(res_db, screen, xsettings).unwrap();
+        let mut this = Self::defaults(dpi);
+        // the desktop font size is the default, the config overrides it
+        if let Some(size) = desktop_font_size(xsettings) {
+            this.font_size = apply_dpi(size, dpi);
+        }
+        this.load_user_config(config_path, dpi);
+        // the settings changed from the palette win over the config
+        if this.persist_toggles {
+            for warning in this.parse(
+                &State::load(state_path.map(Path::to_path_buf)).config_overlay(),
+                dpi,
+            ) {
+                eprintln!("[WARNING] state: {warning}");
+            }
+        }
+        // relative theme paths are resolved against the directory of the config file
+        if let Some(dir) = config_path.and_then(Path::parent) {
+            for path in [&mut this.light_theme, &mut this.dark_theme]
+                .into_iter()
+                .flatten()
+                .filter(|path| path.is_relative())
+            {
+                *path = dir.join(&*path);
+            }
+        }
+        this
+    }
+    pub fn defaults(dpi: f32) -> Self {
+        let mut this = Self {
+            font_1: None,
+            font_2: None,
+            font_3: None,
+            font_size: 11.0,
+            line_height: 1.1,
+            text_halign: HorizontalAlign::Center,
+            text_valign: VerticalAlign::Middle,
+            text_shadow: None,
+            text_outline: None,
+            show_marker: true,
+            marker: '•',
+            marker_width: Some(10.0),
+            marker_height: None,
+            marker_position: MarkerPosition::Below,
+            marker_fg_color: Color::new(255, 255, 255, 255),
+            marker_bg_color: Color::new(0, 0, 0, 255),
+            marker_bg: MarkerBg::Cell,
+            marker_border_width: 0.0,
+            marker_border_color: Color::new(255, 255, 255, 255),
+            numbered_marker: false,
+            show_icons: true,
+            icon_padding: Size::Relative(0.2),
+            icon_border_width: 1.0,
+            icon_border_color: Color::new(0, 0, 0, 255),
+            icon_bg_color: Color::new(0, 0, 0, 255),
+            icon_scaling: IconScaling::Bilinear,
+            icon_cache: IconCaching::Class,
+            thumbnails: Thumbnails::Off,
+            layout: ListLayout::Rows,
+            auto_layout: false,
+            layout_direction: LayoutDirection::Ltr,
+            anchor: Anchor::CENTER,
+            location_x_offset: Size::Absolute(0),
+            location_y_offset: Size::Absolute(0),
+            follow_pointer: false,
+            monitor: MonitorChoice::Focused,
+            bg_color: Color::new(0, 0, 0, 255),
+            border_color: Color::new(64, 64, 64, 255),
+            border_width: 1.0,
+            color_depth: ColorDepth::Full,
+            dither: true,
+            low_bandwidth: None,
+            redraw_interval: Duration::ZERO,
+            stacking: Stacking::Above,
+            raise_on_show: true,
+            enter_animation: EnterAnimation::None,
+            col_sep_width: 0.0,
+            col_sep_color: Color::new(64, 64, 64, 255),
+            row_sep_width: 0.0,
+            row_sep_color: Color::new(64, 64, 64, 255),
+            task_height: Size::Absolute(64),
+            task_width: Size::Absolute(200),
+            task_min_height: 24.0,
+            overflow: Overflow::Shrink,
+            width: Size::Relative(0.4),
+            height: Size::Relative(0.2),
+            task_bg_color: Color::new(50, 50, 50, 255),
+            task_fg_color: Color::new(255, 255, 255, 255),
+            task_border_color: Color::new(200, 200, 200, 255),
+            task_border_width: 0.0,
+            task_gradient: true,
+            selected_task_bg_color: Color::new(92, 64, 64, 255),
+            selected_task_fg_color: Color::new(255, 255, 255, 255),
+            selected_task_border_color: Color::new(128, 64, 32, 255),
+            selected_task_border_width: 4.0,
+            key_quit: Keysym::Escape,
+            key_next: Keysym::Tab,
+            key_prev: Keysym::backslash,
+            key_kill: Keysym::K,
+            key_toggle_scope: Keysym::D,
+            key_toggle_monitor: Keysym::o,
+            key_palette: Keysym::p,
+            key_search: Keysym::slash,
+            key_move_overlay: Keysym::m,
+            key_select: Keysym::Return,
+            key_mod: Keysym::Alt_L,
+            key_suspend: None,
+            passthrough_class: vec![],
+            focus_follows_mouse: false,
+            unminimize_on_focus: false,
+            quick_switch: false,
+            persist_toggles: false,
+            show_new_badge: false,
+            new_badge_fg_color: Color::new(255, 255, 255, 255),
+            new_badge_bg_color: Color::new(160, 48, 48, 255),
+            sticky_badge: String::new(),
+            sticky_badge_fg_color: Color::new(255, 255, 255, 255),
+            sticky_badge_bg_color: Color::new(48, 96, 160, 255),
+            filter: Filter::All,
+            override_redirect_classes: vec![],
+            collapse_transients: false,
+            title_fallback: DEFAULT_TITLE_FALLBACK.to_vec(),
+            title_format: "{title}".into(),
+            aliases: vec![],
+            providers: vec![],
+            tmux: false,
+            closed_history: 0,
+            startup: Startup::Eager,
+            ping: false,
+            unresponsive_badge_fg_color: Color::new(255, 255, 255, 255),
+            unresponsive_badge_bg_color: Color::new(128, 128, 128, 255),
+            show_tooltips: false,
+            tooltip_fg_color: Color::new(255, 255, 255, 255),
+            tooltip_bg_color: Color::new(40, 40, 40, 255),
+            debug_overlay: false,
+            sort: Sort::Recent,
+            monitor_labels: MonitorLabels::Off,
+            desktop_sections: false,
+            group_by_class: false,
+            count_badge_fg_color: Color::new(255, 255, 255, 255),
+            count_badge_bg_color: Color::new(160, 48, 48, 255),
+            header_fg_color: Color::new(160, 160, 160, 255),
+            header_bg_color: Color::new(0, 0, 0, 255),
+            selection_style: SelectionStyle::Box,
+            show_current: ShowCurrent::Show,
+            hover_bg_color: Color::new(70, 70, 70, 255),
+            match_fg_color: Color::new(250, 189, 47, 255),
+            task_gap: 0.0,
+            window_size: WindowSize::Grow,
+            light_theme: None,
+            dark_theme: None,
+        };
+        this.font_size = apply_dpi(this.font_size, dpi);
+        this
+    }
+    pub fn load_user_config(&mut self, config_path: Option<&Path>, dpi: f32) {
+        let Some(config_path) = config_path else {
+            eprintln!(
+                "[INFO] `$XDG_CONFIG_HOME` and `$HOME` are not set, using default configuration"
+            );
+            return;
+        };
+        let Ok(file) = read_to_string(config_path) else {
+            eprintln!("[INFO] failed to load `{config_path:?}`, using default configuration");
+            return;
+        };
+        for warning in self.parse(&file, dpi) {
+            eprintln!("[WARNING] {warning}");
+        }
+    }
+    /// applies the `key: value` lines on top of the current values, returns the warnings
+    pub fn parse(&mut self, contents: &str, dpi: f32) -> Vec<String> {
+        let mut warnings = vec![];
+        for (i, line) in contents.lines().map(str::trim).enumerate() {
+            macro_rules! warning {
+                ($e:expr) => {
+                    warnings.push(format!("line {}, failed to parse `{line}`: {}", i + 1, $e))
+                };
+            }
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let Some((key, val)) = line.split_once(':') else {
+                warning!("the format must be `key: value`");
+                continue;
+            };
+            macro_rules! parse_assign {
+                ($parser:ident, $field:ident) => {
+                    match $parser(val) {
+                        Ok(v) => self.$field = v,
+                        Err(e) => warning!(e),
+                    }
+                };
+            }
+            macro_rules! parse_assign_font {
+                ($field:ident) => {
+                    match str_to_font_path(val) {
+                        Ok(v) => self.$field = Some(v),
+                        Err(e) => warning!(e),
+                    }
+                };
+            }
+            match key.trim() {
+                "font_size" => {
+                    parse_assign!(str_to_primitive, font_size);
+                    self.font_size = apply_dpi(self.font_size, dpi);
+                }
+                "font_1" => parse_assign_font!(font_1),
+                "font_2" => parse_assign_font!(font_2),
+                "font_3" => parse_assign_font!(font_3),
+                "line_height" => parse_assign!(str_to_primitive, line_height),
+                "text_halign" => parse_assign!(str_to_halign, text_halign),
+                "text_valign" => parse_assign!(str_to_valign, text_valign),
+                "text_shadow" => parse_assign!(str_to_text_shadow, text_shadow),
+                "text_outline" => parse_assign!(str_to_text_outline, text_outline),
+                "show_marker" => parse_assign!(str_to_primitive, show_marker),
+                "marker" => parse_assign!(str_to_primitive, marker),
+                "marker_width" => parse_assign!(str_to_some_primitive, marker_width),
+                "marker_height" => parse_assign!(str_to_some_primitive, marker_height),
+                "marker_position" => parse_assign!(str_to_marker_position, marker_position),
+                "marker_fg_color" => parse_assign!(str_to_color, marker_fg_color),
+                "marker_bg_color" => parse_assign!(str_to_color, marker_bg_color),
+                "marker_bg" => parse_assign!(str_to_marker_bg, marker_bg),
+                "marker_border_width" => parse_assign!(str_to_primitive, marker_border_width),
+                "marker_border_color" => parse_assign!(str_to_color, marker_border_color),
+                "numbered_marker" => parse_assign!(str_to_primitive, numbered_marker),
+                "show_icons" => parse_assign!(str_to_primitive, show_icons),
+                "icon_padding" => parse_assign!(str_to_size, icon_padding),
+                "icon_border_width" => parse_assign!(str_to_primitive, icon_border_width),
+                "icon_border_color" => parse_assign!(str_to_color, icon_border_color),
+                "icon_bg_color" => parse_assign!(str_to_color, icon_bg_color),
+                "icon_scaling" => parse_assign!(str_to_icon_scaling, icon_scaling),
+                "icon_cache" => parse_assign!(str_to_icon_caching, icon_cache),
+                "thumbnails" => parse_assign!(str_to_thumbnails, thumbnails),
+                "layout" if val.trim().eq_ignore_ascii_case("auto") => {
+                    self.auto_layout = true;
+                }
+                "layout" => match str_to_list_layout(val) {
+                    Ok(layout) => {
+                        self.layout = layout;
+                        self.auto_layout = false;
+                    }
+                    Err(e) => warning!(e),
+                },
+                "layout_direction" => parse_assign!(str_to_layout_direction, layout_direction),
+                "location" if val.trim().eq_ignore_ascii_case("pointer") => {
+                    self.follow_pointer = true;
+                }
+                "location" => match str_to_position(val) {
+                    Ok(anchor) => {
+                        self.anchor = anchor;
+                        self.follow_pointer = false;
+                    }
+                    Err(e) => warning!(e),
+                },
+                "monitor" => parse_assign!(str_to_monitor, monitor),
+                "stacking" => parse_assign!(str_to_stacking, stacking),
+                "raise_on_show" => parse_assign!(str_to_primitive, raise_on_show),
+                "enter_animation" => parse_assign!(str_to_enter_animation, enter_animation),
+                "bg_color" => parse_assign!(str_to_color, bg_color),
+                "border_color" => parse_assign!(str_to_color, border_color),
+                "border_width" => parse_assign!(str_to_primitive, border_width),
+                "color_depth" => parse_assign!(str_to_color_depth, color_depth),
+                "dither" => parse_assign!(str_to_primitive, dither),
+                "low_bandwidth" => parse_assign!(str_to_some_primitive, low_bandwidth),
+                "task_height" => parse_assign!(str_to_size, task_height),
+                "task_width" => parse_assign!(str_to_size, task_width),
+                "task_min_height" => parse_assign!(str_to_primitive, task_min_height),
+                "overflow" => parse_assign!(str_to_overflow, overflow),
+                "location_x_offset" => parse_assign!(str_to_size, location_x_offset),
+                "location_y_offset" => parse_assign!(str_to_size, location_y_offset),
+                "width" => parse_assign!(str_to_size, width),
+                "height" => parse_assign!(str_to_size, height),
+                "col_sep_width" => parse_assign!(str_to_primitive, col_sep_width),
+                "col_sep_color" => parse_assign!(str_to_color, col_sep_color),
+                "row_sep_width" => parse_assign!(str_to_primitive, row_sep_width),
+                "row_sep_color" => parse_assign!(str_to_color, row_sep_color),
+                "task_bg_color" => parse_assign!(str_to_color, task_bg_color),
+                "task_fg_color" => parse_assign!(str_to_color, task_fg_color),
+                "task_border_width" => parse_assign!(str_to_primitive, task_border_width),
+                "task_border_color" => parse_assign!(str_to_color, task_border_color),
+                "task_gradient" => parse_assign!(str_to_primitive, task_gradient),
+                "selected_task_bg_color" => {
+                    parse_assign!(str_to_color, selected_task_bg_color)
+                }
+                "selected_task_fg_color" => {
+                    parse_assign!(str_to_color, selected_task_fg_color)
+                }
+                "selected_task_border_color" => {
+                    parse_assign!(str_to_color, selected_task_border_color)
+                }
+                "selected_task_border_width" => {
+                    parse_assign!(str_to_primitive, selected_task_border_width)
+                }
+                "key_quit" => parse_assign!(str_to_keysym, key_quit),
+                "key_next" => parse_assign!(str_to_keysym, key_next),
+                "key_prev" => parse_assign!(str_to_keysym, key_prev),
+                "key_kill" => parse_assign!(str_to_keysym, key_kill),
+                "key_toggle_scope" => parse_assign!(str_to_keysym, key_toggle_scope),
+                "key_toggle_monitor" => parse_assign!(str_to_keysym, key_toggle_monitor),
+                "key_palette" => parse_assign!(str_to_keysym, key_palette),
+                "key_search" => parse_assign!(str_to_keysym, key_search),
+                "key_move_overlay" => parse_assign!(str_to_keysym, key_move_overlay),
+                "key_select" => parse_assign!(str_to_keysym, key_select),
+                "key_mod" => parse_assign!(str_to_keysym, key_mod),
+                "key_suspend" => parse_assign!(str_to_optional_keysym, key_suspend),
+                "passthrough_class" => parse_assign!(str_to_list, passthrough_class),
+                "focus_follows_mouse" => parse_assign!(str_to_primitive, focus_follows_mouse),
+                "unminimize_on_focus" => parse_assign!(str_to_primitive, unminimize_on_focus),
+                "quick_switch" => parse_assign!(str_to_primitive, quick_switch),
+                "persist_toggles" => parse_assign!(str_to_primitive, persist_toggles),
+                "show_new_badge" => parse_assign!(str_to_primitive, show_new_badge),
+                "new_badge_fg_color" => parse_assign!(str_to_color, new_badge_fg_color),
+                "new_badge_bg_color" => parse_assign!(str_to_color, new_badge_bg_color),
+                "sticky_badge" => self.sticky_badge = val.trim().to_string(),
+                "sticky_badge_fg_color" => parse_assign!(str_to_color, sticky_badge_fg_color),
+                "sticky_badge_bg_color" => parse_assign!(str_to_color, sticky_badge_bg_color),
+                "filter" => parse_assign!(str_to_filter, filter),
+                "startup" => parse_assign!(str_to_startup, startup),
+                "override_redirect_classes" => {
+                    parse_assign!(str_to_list, override_redirect_classes)
+                }
+                "collapse_transients" => parse_assign!(str_to_primitive, collapse_transients),
+                "title_fallback" => parse_assign!(str_to_title_fallback, title_fallback),
+                "title_format" => parse_assign!(str_to_title_format, title_format),
+                "aliases" => parse_assign!(str_to_aliases, aliases),
+                "providers" => parse_assign!(str_to_list, providers),
+                "tmux" => parse_assign!(str_to_primitive, tmux),
+                "closed_history" => parse_assign!(str_to_primitive, closed_history),
+                "ping" => parse_assign!(str_to_primitive, ping),
+                "unresponsive_badge_fg_color" => {
+                    parse_assign!(str_to_color, unresponsive_badge_fg_color)
+                }
+                "unresponsive_badge_bg_color" => {
+                    parse_assign!(str_to_color, unresponsive_badge_bg_color)
+                }
+                "show_tooltips" => parse_assign!(str_to_primitive, show_tooltips),
+                "tooltip_fg_color" => parse_assign!(str_to_color, tooltip_fg_color),
+                "tooltip_bg_color" => parse_assign!(str_to_color, tooltip_bg_color),
+                "debug_overlay" => parse_assign!(str_to_primitive, debug_overlay),
+                "sort" => parse_assign!(str_to_sort, sort),
+                "monitor_labels" => parse_assign!(str_to_monitor_labels, monitor_labels),
+                "desktop_sections" => parse_assign!(str_to_primitive, desktop_sections),
+                "group_by_class" => parse_assign!(str_to_primitive, group_by_class),
+                "count_badge_fg_color" => parse_assign!(str_to_color, count_badge_fg_color),
+                "count_badge_bg_color" => parse_assign!(str_to_color, count_badge_bg_color),
+                "header_fg_color" => parse_assign!(str_to_color, header_fg_color),
+                "header_bg_color" => parse_assign!(str_to_color, header_bg_color),
+                "selection_style" => parse_assign!(str_to_selection_style, selection_style),
+                "show_current" => parse_assign!(str_to_show_current, show_current),
+                "hover_bg_color" => parse_assign!(str_to_color, hover_bg_color),
+                "match_fg_color" => parse_assign!(str_to_color, match_fg_color),
+                "task_gap" => parse_assign!(str_to_primitive, task_gap),
+                "window_size" => parse_assign!(str_to_window_size, window_size),
+                "light_theme" => parse_assign!(str_to_theme_path, light_theme),
+                "dark_theme" => parse_assign!(str_to_theme_path, dark_theme),
+                _ => warning!(format!("unknown key: `{key}`")),
+            }
+        }
+        if self.font_1.is_none() && self.font_2.is_none() && self.font_3.is_none() {
+            self.font_1 = Some(PathBuf::from("/usr/share/fonts/noto/NotoSans-Regular.ttf"));
+        }
+        warnings
+    }
+    pub fn task_style(&self) -> TaskStyle<'_> {
+        TaskStyle {
+            fg_color: &self.task_fg_color,
+            bg_color: &self.task_bg_color,
+            border_color: &self.task_border_color,
+            border_width: self.task_border_width,
+            selection: SelectionStyle::Box,
+        }
+    }
+    pub fn selected_task_style(&self) -> TaskStyle<'_> {
+        TaskStyle {
+            fg_color: &self.selected_task_fg_color,
+            bg_color: &self.selected_task_bg_color,
+            border_color: &self.selected_task_border_color,
+            border_width: self.selected_task_border_width,
+            selection: self.selection_style,
+        }
+    }
+    /// resolves `low_bandwidth: auto`, the profile trades the looks for fewer and smaller frames
+    pub fn with_bandwidth(mut self, has_render: bool) -> Self {
+        let low = self.low_bandwidth.unwrap_or(!has_render);
+        self.low_bandwidth = Some(low);
+        if low {
+            self.show_icons = false;
+            self.thumbnails = Thumbnails::Off;
+            self.task_gradient = false;
+            self.debug_overlay = false;
+            self.task_height = Size::Absolute(self.task_min_height as u32);
+            self.redraw_interval = LOW_BANDWIDTH_INTERVAL;
+        }
+        self
+    }
+    /// a copy with the theme of the color scheme applied on top, themes are config files
+    /// that usually only set colors
+    pub fn themed(
+        &self,
+        scheme: ColorScheme,
+        screen: &Screen,
+        res_db: &Database,
+        xsettings: &HashMap<String, XSetting>,
+    ) -> Self {
+        let mut this = self.clone();
+        let path = match scheme {
+            ColorScheme::Light => &self.light_theme,
+            ColorScheme::Dark => &self.dark_theme,
+        };
+        let Some(path) = path else {
+            return this;
+        };
+        let Ok(file) = read_to_string(path) else {
+            eprintln!("[WARNING] failed to load the theme `{path:?}`");
+            return this;
+        };
+        let dpi = get_dpi(res_db, screen, xsettings).unwrap();
+        for warning in this.parse(&file, dpi) {
+            eprintln!("[WARNING] {path:?}: {warning}");
+        }
+        this
+    }
+    /// follows a change of the dpi or of the desktop font size, given as `(dpi, font size)` before
+    /// and after, without reading the files again which would undo the changes from the palette
+    pub fn rescale(&mut self, from: (f32, Option<f32>), to: (f32, Option<f32>)) {
+        let ((dpi, size), (new_dpi, new_size)) = (from, to);
+        // a font size still at the desktop one follows it, any other keeps its point size
+        match (size, new_size) {
+            (Some(size), Some(new_size)) if self.font_size == apply_dpi(size, dpi) => {
+                self.font_size = apply_dpi(new_size, new_dpi);
+            }
+            _ => self.font_size = self.font_size * new_dpi / dpi,
+        }
+    }
+    /// the config file in the config directory, see `--config`
+    pub fn config_path() -> Option<PathBuf> {
+        if let Ok(xdg) = std::env::var("XDG_CONFIG_HOME") {
+            return Some(PathBuf::from(xdg).join(format!("{APP_NAME}/config")));
+        }
+        if let Ok(home) = std::env::var("HOME") {
+            return Some(PathBuf::from(home).join(format!(".config/{APP_NAME}/config")));
+        }
+        None
+    }
+}
+pub fn str_to_primitive<T>(value: &str) -> Result<T, String>
+where
+    T: FromStr,
+    T::Err: Display,
+{
+    let value = value.trim();
+    if value.is_empty() {
+        return Err("missing value".into());
+    }
+    value.parse::<T>().map_err(|e| e.to_string())
+}
+pub fn str_to_some_primitive<T>(value: &str) -> Result<Option<T>, String>
+where
+    T: FromStr,
+    T::Err: Display,
+{
+    let value = value.trim();
+    if value.is_empty() {
+        return Err("missing value".into());
+    }
+    match value.to_lowercase().as_str() {
+        "auto" => Ok(None),
+        val => str_to_primitive(val).map(Some),
+    }
+}
+pub fn str_to_list(value: &str) -> Result<Vec<String>> {
+    Ok(value
+        .split(',')
+        .map(str::trim)
+        .filter(|item| !item.is_empty())
+        .map(String::from)
+        .collect())
+}
+/// the fields between braces, `{title}`, `{class}`, `{instance}`, `{role}` and `{visible_name}`
+pub fn str_to_title_format(value: &str) -> Result<String> {
+    let value = value.trim();
+    if value.is_empty() {
+        return Err("missing value".into());
+    }
+    const FIELDS: [&str; 5] = ["title", "class", "instance", "role", "visible_name"];
+    for field in value.split('{').skip(1) {
+        let Some((name, _)) = field.split_once('}') else {
+            return Err(format!("unclosed field in `{value}`").into());
+        };
+        if !FIELDS.contains(&name) {
+            return Err(format!(
+                "invalid field: `{{{name}}}`, expecting: `{{title}}`, `{{class}}`, `{{instance}}`, `{{role}}`, `{{visible_name}}`"
+            )
+            .into());
+        }
+    }
+    Ok(value.to_string())
+}
+/// `class=name` items, the class also matches the instance
+pub fn str_to_aliases(value: &str) -> Result<Vec<(String, String)>> {
+    str_to_list(value)?
+        .into_iter()
+        .map(|item| match item.split_once('=') {
+            Some((class, name)) if !class.trim().is_empty() && !name.trim().is_empty() => {
+                Ok((class.trim().to_string(), name.trim().to_string()))
+            }
+            _ => Err(format!("invalid alias: `{item}`, expecting: `class=name`").into()),
+        })
+        .collect()
+}
+/// `icon_name`, `class` and `instance` read the window, anything else is shown as is
+pub fn str_to_title_fallback(value: &str) -> Result<Vec<TitleFallback>> {
+    Ok(str_to_list(value)?
+        .into_iter()
+        .map(|item| match item.as_str() {
+            "icon_name" => TitleFallback::IconName,
+            "class" => TitleFallback::Class,
+            "instance" => TitleFallback::Instance,
+            _ => TitleFallback::Text(Cow::Owned(item)),
+        })
+        .collect())
+}
+pub fn str_to_size(value: &str) -> Result<Size> {
+    let value = value.trim();
+    if value.is_empty() {
+        return Err("missing value".into());
+    }
+    if value.ends_with('%') {
+        return match value[0..value.len() - 1].trim_end().parse::<f32>() {
+            Ok(n) => Ok(Size::Relative(n / 100.0)),
+            Err(e) => Err(e.into()),
+        };
+    }
+    match value[0..value.len()].trim_end().parse::<u32>() {
+        Ok(n) => Ok(Size::Absolute(n)),
+        Err(e) => Err(e.into()),
+    }
+}
+pub fn str_to_position(value: &str) -> Result<Anchor> {
+    let value = value.trim();
+    if value.is_empty() {
+        return Err("missing value".into());
+    }
+    match value.parse::<usize>() {
+        Ok(n @ 1..=9) => Ok(Anchor::LOCATIONS[n - 1].clone()),
+        _ => Err(format!(
+            "invalid location `{value}`, expected a value between 1 (top left) and 9 (bottom right) or `pointer`"
+        )
+        .into()),
+    }
+}
+pub fn str_to_color(value: &str) -> Result<Color> {
+    let value = value.trim();
+    if value.is_empty() {
+        return Err("missing value".into());
+    }
+    if &value[0..1] != "#" {
+        return Err("a color must start with `#`".into());
+    }
+    let value = &value[1..];
+    if value.len() == 3 {
+        let r = u8::from_str_radix(&value[0..1].repeat(2), 16).map_err(|e| e.to_string())?;
+        let g = u8::from_str_radix(&value[1..2].repeat(2), 16).map_err(|e| e.to_string())?;
+        let b = u8::from_str_radix(&value[2..3].repeat(2), 16).map_err(|e| e.to_string())?;
+        return Ok(Color::new(r, g, b, 255));
+    }
+    if value.len() == 4 {
+        let r = u8::from_str_radix(&value[0..1].repeat(2), 16).map_err(|e| e.to_string())?;
+        let g = u8::from_str_radix(&value[1..2].repeat(2), 16).map_err(|e| e.to_string())?;
+        let b = u8::from_str_radix(&value[2..3].repeat(2), 16).map_err(|e| e.to_string())?;
+        let a = u8::from_str_radix(&value[3..4].repeat(2), 16).map_err(|e| e.to_string())?;
+        return Ok(Color::new(r, g, b, a));
+    }
+    if value.len() == 6 {
+        let r = u8::from_str_radix(&value[0..2], 16).map_err(|e| e.to_string())?;
+        let g = u8::from_str_radix(&value[2..4], 16).map_err(|e| e.to_string())?;
+        let b = u8::from_str_radix(&value[4..6], 16).map_err(|e| e.to_string())?;
+        return Ok(Color::new(r, g, b, 255));
+    }
+    if value.len() == 8 {
+        let r = u8::from_str_radix(&value[0..2], 16).map_err(|e| e.to_string())?;
+        let g = u8::from_str_radix(&value[2..4], 16).map_err(|e| e.to_string())?;
+        let b = u8::from_str_radix(&value[4..6], 16).map_err(|e| e.to_string())?;
+        let a = u8::from_str_radix(&value[6..8], 16).map_err(|e| e.to_string())?;
+        return Ok(Color::new(r, g, b, a));
+    }
+    Err(format!(
+        "invalid hex color `{value}`, valid formats: `#rgb`, `#rgba`, `#rrggbb`, `#rrggbbaa`"
+    )
+    .into())
+}
+pub fn str_to_keysym(value: &str) -> Result<Keysym> {
+    let value = value.trim();
+    if value.is_empty() {
+        return Err("missing value".into());
+    }
+    let sym = keysym_from_name(value, 0);
+    if sym == Keysym::NoSymbol {
+        return Err(format!("invalid keysym `{value}`").into());
+    }
+    Ok(sym)
+}
+/// `none` binds nothing
+pub fn str_to_optional_keysym(value: &str) -> Result<Option<Keysym>> {
+    match value.trim().to_lowercase().as_str() {
+        "none" => Ok(None),
+        _ => str_to_keysym(value).map(Some),
+    }
+}
+pub fn str_to_font_path(value: &str) -> Result<PathBuf> {
+    let value = value.trim();
+    if value.is_empty() {
+        return Err("missing value".into());
+    }
+    let path = PathBuf::from(value);
+    if !path.exists() {
+        return Err(format!("couldn't find font `{value}`").into());
+    }
+    Ok(path)
+}
+/// relative paths are resolved against the directory of the config file, see `Config::new`
+pub fn str_to_theme_path(value: &str) -> Result<Option<PathBuf>> {
+    let value = value.trim();
+    if value.is_empty() {
+        return Err("missing value".into());
+    }
+    Ok(Some(PathBuf::from(value)))
+}
+pub fn str_to_halign(value: &str) -> Result<HorizontalAlign> {
+    let value = value.trim();
+    if value.is_empty() {
+        return Err("missing value".into());
+    }
+    match value.to_lowercase().as_str() {
+        "left" => Ok(HorizontalAlign::Left),
+        "center" => Ok(HorizontalAlign::Center),
+        "right" => Ok(HorizontalAlign::Right),
+        _ => Err(
+            format!("invalid alignment: `{value}`, expecting: `left`, `center` or `right`").into(),
+        ),
+    }
+}
+pub fn str_to_valign(value: &str) -> Result<VerticalAlign> {
+    let value = value.trim();
+    if value.is_empty() {
+        return Err("missing value".into());
+    }
+    match value.to_lowercase().as_str() {
+        "top" => Ok(VerticalAlign::Top),
+        "middle" => Ok(VerticalAlign::Middle),
+        "bottom" => Ok(VerticalAlign::Bottom),
+        _ => Err(
+            format!("invalid alignment: `{value}`, expecting: `top`, `middle` or `bottom`").into(),
+        ),
+    }
+}
+pub fn str_to_filter(value: &str) -> Result<Filter> {
+    let value = value.trim();
+    if value.is_empty() {
+        return Err("missing value".into());
+    }
+    match value.to_lowercase().as_str() {
+        "all" => Ok(Filter::All),
+        "current_desktop" => Ok(Filter::CurrentDesktop),
+        "current_monitor" => Ok(Filter::CurrentMonitor),
+        _ => Err(format!(
+            "invalid filter: `{value}`, expecting: `all`, `current_desktop`, `current_monitor`"
+        )
+        .into()),
+    }
+}
+pub fn str_to_show_current(value: &str) -> Result<ShowCurrent> {
+    let value = value.trim();
+    if value.is_empty() {
+        return Err("missing value".into());
+    }
+    match value.to_lowercase().as_str() {
+        "true" => Ok(ShowCurrent::Show),
+        "false" => Ok(ShowCurrent::Hide),
+        "dim" => Ok(ShowCurrent::Dim),
+        _ => Err(format!("invalid value: `{value}`, expecting: `true`, `false`, `dim`").into()),
+    }
+}
+pub fn str_to_selection_style(value: &str) -> Result<SelectionStyle> {
+    let value = value.trim();
+    if value.is_empty() {
+        return Err("missing value".into());
+    }
+    match value.to_lowercase().as_str() {
+        "box" => Ok(SelectionStyle::Box),
+        "underline" => Ok(SelectionStyle::Underline),
+        "leftbar" => Ok(SelectionStyle::LeftBar),
+        "invert" => Ok(SelectionStyle::Invert),
+        _ => Err(format!(
+            "invalid selection style: `{value}`, expecting: `box`, `underline`, `leftbar`, `invert`"
+        )
+        .into()),
+    }
+}
+pub fn str_to_sort(value: &str) -> Result<Sort> {
+    let value = value.trim();
+    if value.is_empty() {
+        return Err("missing value".into());
+    }
+    match value.to_lowercase().as_str() {
+        "recent" => Ok(Sort::Recent),
+        "stacking" => Ok(Sort::Stacking),
+        "position" => Ok(Sort::Position),
+        _ => Err(
+            format!("invalid sort: `{value}`, expecting: `recent`, `stacking`, `position`").into(),
+        ),
+    }
+}
+pub fn str_to_monitor_labels(value: &str) -> Result<MonitorLabels> {
+    let value = value.trim();
+    if value.is_empty() {
+        return Err("missing value".into());
+    }
+    match value.to_lowercase().as_str() {
+        "off" => Ok(MonitorLabels::Off),
+        "annotate" => Ok(MonitorLabels::Annotate),
+        "group" => Ok(MonitorLabels::Group),
+        _ => Err(format!(
+            "invalid monitor labels: `{value}`, expecting: `off`, `annotate`, `group`"
+        )
+        .into()),
+    }
+}
+pub fn str_to_list_layout(value: &str) -> Result<ListLayout> {
+    let value = value.trim();
+    if value.is_empty() {
+        return Err("missing value".into());
+    }
+    match value.to_lowercase().as_str() {
+        "rows" => Ok(ListLayout::Rows),
+        "columns" => Ok(ListLayout::Columns),
+        "grid" => Ok(ListLayout::Grid),
+        _ => Err(format!(
+            "invalid list layout: `{value}`, expecting: `rows`, `columns`, `grid`, `auto`"
+        )
+        .into()),
+    }
+}
+pub fn str_to_layout_direction(value: &str) -> Result<LayoutDirection> {
+    let value = value.trim();
+    if value.is_empty() {
+        return Err("missing value".into());
+    }
+    match value.to_lowercase().as_str() {
+        "ltr" => Ok(LayoutDirection::Ltr),
+        "rtl" => Ok(LayoutDirection::Rtl),
+        _ => Err(format!("invalid layout direction: `{value}`, expecting: `ltr`, `rtl`").into()),
+    }
+}
+pub fn str_to_icon_scaling(value: &str) -> Result<IconScaling> {
+    let value = value.trim();
+    if value.is_empty() {
+        return Err("missing value".into());
+    }
+    match value.to_lowercase().as_str() {
+        "bilinear" => Ok(IconScaling::Bilinear),
+        "nearest" => Ok(IconScaling::Nearest),
+        "auto" => Ok(IconScaling::Auto),
+        _ => Err(format!(
+            "invalid icon scaling: `{value}`, expecting: `bilinear`, `nearest`, `auto`"
+        )
+        .into()),
+    }
+}
+pub fn str_to_color_depth(value: &str) -> Result<ColorDepth> {
+    let value = value.trim();
+    if value.is_empty() {
+        return Err("missing value".into());
+    }
+    match value.to_lowercase().as_str() {
+        "full" => Ok(ColorDepth::Full),
+        "grayscale" | "greyscale" => Ok(ColorDepth::Grayscale),
+        "mono" => Ok(ColorDepth::Mono),
+        _ => Err(
+            format!("invalid color depth: `{value}`, expecting: `full`, `grayscale`, `mono`")
+                .into(),
+        ),
+    }
+}
+pub fn str_to_icon_caching(value: &str) -> Result<IconCaching> {
+    let value = value.trim();
+    if value.is_empty() {
+        return Err("missing value".into());
+    }
+    match value.to_lowercase().as_str() {
+        "class" => Ok(IconCaching::Class),
+        "window" => Ok(IconCaching::Window),
+        _ => Err(format!("invalid icon cache: `{value}`, expecting: `class`, `window`").into()),
+    }
+}
+pub fn str_to_thumbnails(value: &str) -> Result<Thumbnails> {
+    let value = value.trim();
+    if value.is_empty() {
+        return Err("missing value".into());
+    }
+    match value.to_lowercase().as_str() {
+        "off" => Ok(Thumbnails::Off),
+        "beside" => Ok(Thumbnails::Beside),
+        "instead" => Ok(Thumbnails::Instead),
+        _ => Err(
+            format!("invalid thumbnails: `{value}`, expecting: `off`, `beside`, `instead`").into(),
+        ),
+    }
+}
+/// a length in pixels, the `px` suffix is optional
+pub fn str_to_length(value: &str) -> Result<f32> {
+    let value = value.trim();
+    let number = value.strip_suffix("px").unwrap_or(value);
+    number
+        .parse()
+        .map_err(|_| format!("invalid length: `{value}`, expecting pixels like `1px`").into())
+}
+pub fn str_to_text_shadow(value: &str) -> Result<Option<TextShadow>> {
+    let value = value.trim();
+    if value.is_empty() {
+        return Err("missing value".into());
+    }
+    if value.eq_ignore_ascii_case("none") {
+        return Ok(None);
+    }
+    let parts = value.split_whitespace().collect::<Vec<_>>();
+    let [x, y, color] = parts[..] else {
+        return Err(format!(
+            "invalid text shadow: `{value}`, expecting: `<x> <y> <color>` or `none`"
+        )
+        .into());
+    };
+    Ok(Some(TextShadow {
+        x: str_to_length(x)?,
+        y: str_to_length(y)?,
+        color: str_to_color(color)?,
+    }))
+}
+pub fn str_to_text_outline(value: &str) -> Result<Option<TextOutline>> {
+    let value = value.trim();
+    if value.is_empty() {
+        return Err("missing value".into());
+    }
+    if value.eq_ignore_ascii_case("none") {
+        return Ok(None);
+    }
+    let parts = value.split_whitespace().collect::<Vec<_>>();
+    let [width, color] = parts[..] else {
+        return Err(format!(
+            "invalid text outline: `{value}`, expecting: `<width> <color>` or `none`"
+        )
+        .into());
+    };
+    Ok(Some(TextOutline {
+        width: str_to_length(width)?,
+        color: str_to_color(color)?,
+    }))
+}
+/// any other value is the name of an output
+pub fn str_to_monitor(value: &str) -> Result<MonitorChoice> {
+    let value = value.trim();
+    if value.is_empty() {
+        return Err("missing value".into());
+    }
+    Ok(match value.to_lowercase().as_str() {
+        "focused" => MonitorChoice::Focused,
+        "pointer" => MonitorChoice::Pointer,
+        "primary" => MonitorChoice::Primary,
+        _ => MonitorChoice::Named(value.to_string()),
+    })
+}
+pub fn str_to_stacking(value: &str) -> Result<Stacking> {
+    let value = value.trim();
+    if value.is_empty() {
+        return Err("missing value".into());
+    }
+    match value.to_lowercase().as_str() {
+        "above" => Ok(Stacking::Above),
+        "normal" => Ok(Stacking::Normal),
+        _ => Err(format!("invalid stacking: `{value}`, expecting: `above`, `normal`").into()),
+    }
+}
+pub fn str_to_enter_animation(value: &str) -> Result<EnterAnimation> {
+    let value = value.trim();
+    if value.is_empty() {
+        return Err("missing value".into());
+    }
+    match value.to_lowercase().as_str() {
+        "none" => Ok(EnterAnimation::None),
+        "fade" => Ok(EnterAnimation::Fade),
+        "slide" => Ok(EnterAnimation::Slide(None)),
+        "slide-top" => Ok(EnterAnimation::Slide(Some(Edge::Top))),
+        "slide-bottom" => Ok(EnterAnimation::Slide(Some(Edge::Bottom))),
+        "slide-left" => Ok(EnterAnimation::Slide(Some(Edge::Left))),
+        "slide-right" => Ok(EnterAnimation::Slide(Some(Edge::Right))),
+        _ => Err(format!(
+            "invalid enter animation: `{value}`, expecting: `none`, `fade`, `slide`, `slide-top`, `slide-bottom`, `slide-left`, `slide-right`"
+        )
+        .into()),
+    }
+}
+pub fn str_to_marker_bg(value: &str) -> Result<MarkerBg> {
+    let value = value.trim();
+    if value.is_empty() {
+        return Err("missing value".into());
+    }
+    match value.to_lowercase().as_str() {
+        "none" => Ok(MarkerBg::None),
+        "cell" => Ok(MarkerBg::Cell),
+        "gutter" => Ok(MarkerBg::Gutter),
+        _ => Err(format!(
+            "invalid marker background: `{value}`, expecting: `none`, `cell`, `gutter`"
+        )
+        .into()),
+    }
+}
+pub fn str_to_marker_position(value: &str) -> Result<MarkerPosition> {
+    let value = value.trim();
+    if value.is_empty() {
+        return Err("missing value".into());
+    }
+    match value.to_lowercase().as_str() {
+        "above" => Ok(MarkerPosition::Above),
+        "below" => Ok(MarkerPosition::Below),
+        _ => Err(format!("invalid marker position: `{value}`, expecting: `above`, `below`").into()),
+    }
+}
+pub fn str_to_overflow(value: &str) -> Result<Overflow> {
+    let value = value.trim();
+    if value.is_empty() {
+        return Err("missing value".into());
+    }
+    match value.to_lowercase().as_str() {
+        "shrink" => Ok(Overflow::Shrink),
+        "scroll" => Ok(Overflow::Scroll),
+        "paginate" => Ok(Overflow::Paginate),
+        _ => Err(
+            format!("invalid overflow: `{value}`, expecting: `shrink`, `scroll`, `paginate`")
+                .into(),
+        ),
+    }
+}
+pub fn str_to_startup(value: &str) -> Result<Startup> {
+    let value = value.trim();
+    if value.is_empty() {
+        return Err("missing value".into());
+    }
+    match value.to_lowercase().as_str() {
+        "eager" => Ok(Startup::Eager),
+        "lazy" => Ok(Startup::Lazy),
+        _ => Err(format!("invalid startup: `{value}`, expecting: `eager`, `lazy`").into()),
+    }
+}
+pub fn str_to_window_size(value: &str) -> Result<WindowSize> {
+    let value = value.trim();
+    if value.is_empty() {
+        return Err("missing value".into());
+    }
+    match value.to_lowercase().as_str() {
+        "grow" => Ok(WindowSize::Grow),
+        "fixed" => Ok(WindowSize::Fixed),
+        _ => Err(format!("invalid window size: `{value}`, expecting: `grow`, `fixed`").into()),
+    }
+}
+
+/// values persisted across runs, stored as `key: value` lines like the config
+pub struct State {
+    pub values: BTreeMap<String, String>,
+    pub path: Option<PathBuf>,
+}
+impl State {
+    pub fn load(path: Option<PathBuf>) -> Self {
+        let values = path
+            .as_ref()
+            .and_then(|path| read_to_string(path).ok())
+            .unwrap_or_default()
+            .lines()
+            .filter_map(|line| line.split_once(':'))
+            .map(|(key, val)| (key.trim().to_string(), val.trim().to_string()))
+            .collect();
+        Self { values, path }
+    }
+    pub fn save(&self) -> Result<()> {
+        let path = self
+            .path
+            .as_ref()
+            .ok_or("`$XDG_STATE_HOME` and `$HOME` are not set")?;
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let mut contents = String::new();
+        for (key, val) in &self.values {
+            contents.push_str(&format!("{key}: {val}\n"));
+        }
+        std::fs::write(path, contents)?;
+        Ok(())
+    }
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.values.get(key).map(String::as_str)
+    }
+    pub fn set(&mut self, key: &str, val: String) {
+        self.values.insert(key.to_string(), val);
+    }
+    /// the `config.<key>` values, as config lines
+    pub fn config_overlay(&self) -> String {
+        self.values
+            .iter()
+            .filter_map(|(key, val)| Some(format!("{}: {val}\n", key.strip_prefix("config.")?)))
+            .collect()
+    }
+    pub fn state_path() -> Option<PathBuf> {
+        if let Ok(xdg) = std::env::var("XDG_STATE_HOME") {
+            return Some(PathBuf::from(xdg).join(format!("{APP_NAME}/state")));
+        }
+        if let Ok(home) = std::env::var("HOME") {
+            return Some(PathBuf::from(home).join(format!(".local/state/{APP_NAME}/state")));
+        }
+        None
+    }
+}
+
+/// the desktop preference, picks between `light_theme` and `dark_theme`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ColorScheme {
+    Light,
+    Dark,
+}
+pub fn apply_dpi(val: f32, dpi: f32) -> f32 {
+    val * dpi / 72.0
+}
+/// the point size at the end of the `Gtk/FontName` xsetting, like `Sans 10`
+pub fn desktop_font_size(xsettings: &HashMap<String, XSetting>) -> Option<f32> {
+    match xsettings.get("Gtk/FontName") {
+        Some(XSetting::String(font)) => font.rsplit(' ').next()?.parse().ok(),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::read_to_string;
+    use std::path::PathBuf;
+
+    use fontdue::layout::HorizontalAlign;
+    use fontdue::layout::VerticalAlign;
+    use xkbcommon::xkb::Keysym;
+
+    use super::ColorDepth;
+    use super::Config;
+    use super::IconCaching;
+    use super::IconScaling;
+    use super::LayoutDirection;
+    use super::ListLayout;
+    use super::MarkerBg;
+    use super::MarkerPosition;
+    use super::Overflow;
+    use super::SelectionStyle;
+    use super::ShowCurrent;
+    use super::Size;
+    use super::Stacking;
+    use super::Startup;
+    use super::Thumbnails;
+    use super::WindowSize;
+    use super::apply_dpi;
+    use super::str_to_color;
+    use super::str_to_color_depth;
+    use super::str_to_filter;
+    use super::str_to_font_path;
+    use super::str_to_halign;
+    use super::str_to_icon_caching;
+    use super::str_to_icon_scaling;
+    use super::str_to_keysym;
+    use super::str_to_layout_direction;
+    use super::str_to_list;
+    use super::str_to_list_layout;
+    use super::str_to_marker_bg;
+    use super::str_to_marker_position;
+    use super::str_to_monitor_labels;
+    use super::str_to_overflow;
+    use super::str_to_position;
+    use super::str_to_primitive;
+    use super::str_to_selection_style;
+    use super::str_to_show_current;
+    use super::str_to_size;
+    use super::str_to_some_primitive;
+    use super::str_to_sort;
+    use super::str_to_stacking;
+    use super::str_to_startup;
+    use super::str_to_text_outline;
+    use super::str_to_text_shadow;
+    use super::str_to_valign;
+    use super::str_to_window_size;
+    use crate::LOW_BANDWIDTH_INTERVAL;
+    use crate::mock::DPI;
+    use crate::mock::parse;
+    use crate::render::Color;
+    use crate::tasks::Filter;
+    use crate::tasks::MonitorLabels;
+    use crate::tasks::Sort;
+
+    fn halign_name(align: HorizontalAlign) -> &'static str {
+        match align {
+            HorizontalAlign::Left => "left",
+            HorizontalAlign::Center => "center",
+            HorizontalAlign::Right => "right",
+        }
+    }
+
+    fn valign_name(align: VerticalAlign) -> &'static str {
+        match align {
+            VerticalAlign::Top => "top",
+            VerticalAlign::Middle => "middle",
+            VerticalAlign::Bottom => "bottom",
+        }
+    }
+
+    fn snapshot(conf: &Config, warnings: &[String]) -> String {
+        let mut out = String::new();
+        macro_rules! fields {
+            ($($field:ident),* $(,)?) => {
+                $(out.push_str(&format!("{}: {:?}\n", stringify!($field), conf.$field));)*
+            };
+        }
+        fields!(font_1, font_2, font_3, font_size);
+        out.push_str(&format!("text_halign: {}\n", halign_name(conf.text_halign)));
+        out.push_str(&format!("text_valign: {}\n", valign_name(conf.text_valign)));
+        fields!(
+            line_height,
+            text_shadow,
+            text_outline,
+            show_marker,
+            marker,
+            marker_fg_color,
+            marker_bg_color,
+            marker_bg,
+            marker_border_width,
+            marker_border_color,
+            marker_width,
+            marker_height,
+            marker_position,
+            numbered_marker,
+            show_icons,
+            icon_padding,
+            icon_border_width,
+            icon_border_color,
+            icon_bg_color,
+            icon_scaling,
+            icon_cache,
+            thumbnails,
+            layout,
+            auto_layout,
+            layout_direction,
+            anchor,
+            location_x_offset,
+            location_y_offset,
+            follow_pointer,
+            monitor,
+            bg_color,
+            border_color,
+            border_width,
+            color_depth,
+            dither,
+            low_bandwidth,
+            redraw_interval,
+            width,
+            height,
+            col_sep_width,
+            col_sep_color,
+            row_sep_width,
+            row_sep_color,
+            task_height,
+            task_width,
+            task_bg_color,
+            task_fg_color,
+            task_border_color,
+            task_border_width,
+            task_gradient,
+            selected_task_bg_color,
+            selected_task_fg_color,
+            selected_task_border_color,
+            selected_task_border_width,
+            key_quit,
+            key_next,
+            key_prev,
+            key_kill,
+            key_toggle_scope,
+            key_toggle_monitor,
+            key_palette,
+            key_search,
+            key_move_overlay,
+            key_select,
+            key_mod,
+            key_suspend,
+            passthrough_class,
+            focus_follows_mouse,
+            unminimize_on_focus,
+            quick_switch,
+            persist_toggles,
+            show_new_badge,
+            new_badge_fg_color,
+            new_badge_bg_color,
+            sticky_badge,
+            sticky_badge_fg_color,
+            sticky_badge_bg_color,
+            filter,
+            override_redirect_classes,
+            collapse_transients,
+            title_fallback,
+            title_format,
+            aliases,
+            providers,
+            tmux,
+            closed_history,
+            startup,
+            ping,
+            unresponsive_badge_fg_color,
+            unresponsive_badge_bg_color,
+            show_tooltips,
+            tooltip_fg_color,
+            tooltip_bg_color,
+            debug_overlay,
+            sort,
+            monitor_labels,
+            desktop_sections,
+            group_by_class,
+            count_badge_fg_color,
+            count_badge_bg_color,
+            header_fg_color,
+            header_bg_color,
+            selection_style,
+            show_current,
+            hover_bg_color,
+            match_fg_color,
+            task_gap,
+            window_size,
+            stacking,
+            raise_on_show,
+            enter_animation,
+            task_min_height,
+            overflow,
+            light_theme,
+            dark_theme,
+        );
+        out.push_str("--- warnings\n");
+        for warning in warnings {
+            out.push_str(warning);
+            out.push('\n');
+        }
+        out
+    }
+
+    /// every `<name>.conf` fixture is compared with its `<name>.snap`, set `UPDATE_SNAPSHOTS` to
+    /// rewrite them. the dpi can be overridden with a `# dpi: N` line
+    #[test]
+    fn config_snapshots() {
+        let dir = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/config");
+        let update = std::env::var_os("UPDATE_SNAPSHOTS").is_some();
+        let mut fixtures: Vec<_> = std::fs::read_dir(&dir)
+            .unwrap()
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.extension().is_some_and(|ext| ext == "conf"))
+            .collect();
+        fixtures.sort();
+        assert!(!fixtures.is_empty());
+
+        let mut failed = vec![];
+        for fixture in fixtures {
+            let contents = read_to_string(&fixture).unwrap();
+            let mut dpi = DPI;
+            for line in contents.lines() {
+                if let Some(val) = line.strip_prefix("# dpi:") {
+                    dpi = val.trim().parse().unwrap();
+                }
+            }
+            let (conf, warnings) = parse(&contents, dpi);
+            let actual = snapshot(&conf, &warnings);
+            let snap = fixture.with_extension("snap");
+            if update {
+                std::fs::write(&snap, &actual).unwrap();
+                continue;
+            }
+            match read_to_string(&snap) {
+                Ok(expected) if expected == actual => {}
+                _ => failed.push(fixture.display().to_string()),
+            }
+        }
+        assert!(failed.is_empty(), "snapshots differ: {failed:?}");
+    }
+
+    #[test]
+    fn primitive_values() {
+        assert_eq!(str_to_primitive::<f32>(" 1.5 "), Ok(1.5));
+        assert_eq!(str_to_primitive::<bool>("true"), Ok(true));
+        assert_eq!(str_to_primitive::<char>("•"), Ok('•'));
+        assert!(str_to_primitive::<bool>("yes").is_err());
+        assert!(str_to_primitive::<u32>("").is_err());
+        assert_eq!(str_to_some_primitive::<f32>("auto"), Ok(None));
+        assert_eq!(str_to_some_primitive::<f32>("AUTO"), Ok(None));
+        assert_eq!(str_to_some_primitive::<f32>("4"), Ok(Some(4.0)));
+        assert!(str_to_some_primitive::<f32>("four").is_err());
+    }
+
+    #[test]
+    fn size_values() {
+        assert!(matches!(str_to_size("20"), Ok(Size::Absolute(20))));
+        assert!(matches!(str_to_size("50%"), Ok(Size::Relative(n)) if n == 0.5));
+        assert!(matches!(str_to_size("50 %"), Ok(Size::Relative(n)) if n == 0.5));
+        assert!(str_to_size("-1").is_err());
+        assert!(str_to_size("%").is_err());
+        assert!(str_to_size("").is_err());
+        assert_eq!(Size::Relative(0.25).resolve(1080.0), 270.0);
+        assert_eq!(Size::Absolute(42).resolve(1080.0), 42.0);
+    }
+
+    #[test]
+    fn color_values() {
+        let rgba = |c: Color| (c.r, c.g, c.b, c.a);
+        assert_eq!(rgba(str_to_color("#fff").unwrap()), (255, 255, 255, 255));
+        assert_eq!(rgba(str_to_color("#1d2021").unwrap()), (29, 32, 33, 255));
+        assert_eq!(rgba(str_to_color("#10203040").unwrap()), (16, 32, 48, 64));
+        assert!(str_to_color("fff").is_err());
+        assert!(str_to_color("#ff").is_err());
+        assert!(str_to_color("#gggggg").is_err());
+        assert!(str_to_color("").is_err());
+    }
+
+    #[test]
+    fn enum_values() {
+        assert!(matches!(str_to_list_layout("rows"), Ok(ListLayout::Rows)));
+        assert!(matches!(
+            str_to_list_layout("Columns"),
+            Ok(ListLayout::Columns)
+        ));
+        assert_eq!(str_to_list_layout("Grid").unwrap(), ListLayout::Grid);
+        assert!(str_to_list_layout("tiles").is_err());
+        assert!(matches!(str_to_halign("left"), Ok(HorizontalAlign::Left)));
+        assert!(str_to_halign("top").is_err());
+        assert_eq!(
+            str_to_layout_direction("RTL").unwrap(),
+            LayoutDirection::Rtl
+        );
+        assert!(str_to_layout_direction("ttb").is_err());
+        assert_eq!(str_to_marker_bg("Gutter").unwrap(), MarkerBg::Gutter);
+        assert!(str_to_marker_bg("column").is_err());
+        assert_eq!(
+            str_to_marker_position("Above").unwrap(),
+            MarkerPosition::Above
+        );
+        assert!(str_to_marker_position("left").is_err());
+        assert_eq!(str_to_stacking("Normal").unwrap(), Stacking::Normal);
+        assert!(str_to_stacking("below").is_err());
+        let shadow = str_to_text_shadow("1px 2 #000a").unwrap().unwrap();
+        assert_eq!((shadow.x, shadow.y, shadow.color.a), (1.0, 2.0, 170));
+        assert!(str_to_text_shadow("None").unwrap().is_none());
+        assert!(str_to_text_shadow("1px #000").is_err());
+        let outline = str_to_text_outline("2px #fff").unwrap().unwrap();
+        assert_eq!(outline.width, 2.0);
+        assert!(str_to_text_outline("thick #fff").is_err());
+        assert!(matches!(str_to_valign("bottom"), Ok(VerticalAlign::Bottom)));
+        assert!(str_to_valign("left").is_err());
+        assert_eq!(
+            str_to_filter("current_desktop").unwrap(),
+            Filter::CurrentDesktop
+        );
+        assert!(str_to_filter("desktop").is_err());
+        assert_eq!(
+            str_to_filter("current_monitor").unwrap(),
+            Filter::CurrentMonitor
+        );
+        assert_eq!(str_to_sort("stacking").unwrap(), Sort::Stacking);
+        assert_eq!(str_to_sort("position").unwrap(), Sort::Position);
+        assert!(str_to_sort("name").is_err());
+        assert_eq!(
+            str_to_monitor_labels("group").unwrap(),
+            MonitorLabels::Group
+        );
+        assert!(str_to_monitor_labels("").is_err());
+        assert_eq!(str_to_show_current("dim").unwrap(), ShowCurrent::Dim);
+        assert_eq!(str_to_show_current("false").unwrap(), ShowCurrent::Hide);
+        assert!(matches!(
+            str_to_selection_style("leftbar"),
+            Ok(SelectionStyle::LeftBar)
+        ));
+        assert!(str_to_selection_style("bar").is_err());
+        assert_eq!(str_to_window_size("Fixed").unwrap(), WindowSize::Fixed);
+        assert!(str_to_window_size("auto").is_err());
+        assert_eq!(str_to_startup("lazy").unwrap(), Startup::Lazy);
+        assert!(str_to_startup("never").is_err());
+        assert_eq!(str_to_icon_scaling("auto").unwrap(), IconScaling::Auto);
+        assert!(str_to_icon_scaling("bicubic").is_err());
+        assert_eq!(str_to_icon_caching("Window").unwrap(), IconCaching::Window);
+        assert_eq!(
+            str_to_color_depth("Grayscale").unwrap(),
+            ColorDepth::Grayscale
+        );
+        assert!(str_to_color_depth("256").is_err());
+        assert!(str_to_icon_caching("app").is_err());
+        assert_eq!(str_to_overflow("scroll").unwrap(), Overflow::Scroll);
+        assert_eq!(str_to_overflow("paginate").unwrap(), Overflow::Paginate);
+        assert!(str_to_overflow("clip").is_err());
+        assert!(str_to_position("5").is_ok());
+        assert!(str_to_position("10").is_err());
+    }
+
+    #[test]
+    fn list_and_key_values() {
+        assert_eq!(
+            str_to_list("Conky, Xfdesktop").unwrap(),
+            ["Conky", "Xfdesktop"]
+        );
+        assert_eq!(str_to_list(" a ,, b ").unwrap(), ["a", "b"]);
+        assert_eq!(str_to_keysym("Tab").unwrap(), Keysym::Tab);
+        assert!(str_to_keysym("NotAKey").is_err());
+        assert!(str_to_font_path("/nonexistent/font.ttf").is_err());
+    }
+
+    #[test]
+    fn dpi_scaling() {
+        let (conf, _) = parse("font_size: 10", 144.0);
+        assert_eq!(conf.font_size, 20.0);
+        let (conf, _) = parse("", 144.0);
+        assert_eq!(conf.font_size, apply_dpi(11.0, 144.0));
+
+        // a font size from the config keeps its point size, the desktop one follows the desktop
+        let (mut conf, _) = parse("font_size: 10", 72.0);
+        conf.rescale((72.0, Some(12.0)), (144.0, Some(8.0)));
+        assert_eq!(conf.font_size, 20.0);
+        let (mut conf, _) = parse("font_size: 12", 72.0);
+        conf.rescale((72.0, Some(12.0)), (144.0, Some(8.0)));
+        assert_eq!(conf.font_size, 16.0);
+        conf.rescale((144.0, Some(8.0)), (72.0, None));
+        assert_eq!(conf.font_size, 8.0);
+    }
+
+    #[test]
+    fn relative_sizes_resolve_against_the_screen() {
+        let (conf, warnings) = parse("width: 50%\nheight: 10%", DPI);
+        assert!(warnings.is_empty());
+        assert_eq!(conf.width.resolve(1000.0), 500.0);
+        assert_eq!(conf.height.resolve(800.0), 80.0);
+        // resolved again when the screen changes
+        assert_eq!(conf.width.resolve(2000.0), 1000.0);
+        let (conf, _) = parse("", DPI);
+        assert_eq!(conf.height.resolve(800.0), 160.0);
+    }
+
+    #[test]
+    fn low_bandwidth_profile() {
+        let (conf, warnings) = parse("low_bandwidth: auto\ntask_min_height: 20", DPI);
+        assert!(warnings.is_empty() && conf.low_bandwidth.is_none());
+        let conf = conf.with_bandwidth(true);
+        assert!(conf.show_icons && conf.redraw_interval.is_zero());
+        // servers without the render extension are likely vnc
+        let (conf, _) = parse("task_min_height: 20", DPI);
+        let conf = conf.with_bandwidth(false);
+        assert_eq!(conf.low_bandwidth, Some(true));
+        assert!(!conf.show_icons && !conf.task_gradient);
+        assert_eq!(conf.task_height.resolve(1000.0), 20.0);
+        assert_eq!(conf.redraw_interval, LOW_BANDWIDTH_INTERVAL);
+        let (conf, _) = parse("low_bandwidth: false", DPI);
+        assert!(conf.with_bandwidth(false).show_icons);
+        let (conf, _) = parse("thumbnails: beside", DPI);
+        assert_eq!(conf.with_bandwidth(false).thumbnails, Thumbnails::Off);
+    }
+
+    #[test]
+    fn invalid_lines_warn_and_keep_the_previous_value() {
+        let (conf, warnings) = parse("border_width: thick\nno separator\nfoo: 1", DPI);
+        assert_eq!(conf.border_width, Config::defaults(DPI).border_width);
+        assert_eq!(warnings.len(), 3);
+        assert!(warnings[0].starts_with("line 1,"));
+        assert!(warnings[1].contains("the format must be `key: value`"));
+        assert!(warnings[2].contains("unknown key: `foo`"));
+    }
+}
@@ -0,0 +1,661 @@
+use std::collections::HashMap;
+use std::fs::read_to_string;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::OnceLock;
+
+use x11rb::protocol::xproto::ConnectionExt as _;
+use x11rb::protocol::xproto::Window;
+
+use crate::Atoms;
+use crate::Conn;
+use crate::ICON_SNAPSHOT_SIZE;
+use crate::Result;
+use crate::config::IconCaching;
+use crate::config::IconScaling;
+use crate::render::Frame;
+use crate::tasks::Task;
+use crate::tasks::TaskList;
+use crate::tasks::window_to_task;
+use crate::x11::get_window_parent;
+use crate::x11::get_window_snapshot;
+
+// exported by sandboxed apps, most setups also list them in `$XDG_DATA_DIRS`
+pub const SANDBOX_DATA_DIRS: [&str; 3] = [
+    "/var/lib/flatpak/exports/share",
+    "~/.local/share/flatpak/exports/share",
+    "/var/lib/snapd/desktop",
+];
+/// the keys of an installed `.desktop` file used to find the icon of a window
+#[derive(Debug, PartialEq)]
+pub struct DesktopEntry {
+    pub id: String,
+    pub name: Option<String>,
+    pub exec: Option<String>,
+    pub startup_wm_class: Option<String>,
+    pub icon: String,
+}
+/// an icon as found, it's rendered at the size of the cells when drawn
+#[derive(Clone)]
+pub enum Icon {
+    // every size the application provides
+    Raster(Vec<Frame>),
+    // rasterized at the target size, so that it stays sharp
+    Svg(PathBuf),
+}
+impl Icon {
+    pub fn blank() -> Self {
+        Self::Raster(vec![])
+    }
+    /// the icon scaled to fit a `size` square
+    pub fn render(&self, size: u32, scaling: IconScaling) -> Frame {
+        match self {
+            Self::Raster(frames) => {
+                let Some(frame) = pick_icon_size(frames, size) else {
+                    return Frame::new(0, 0);
+                };
+                let factor = size as f32 / frame.width().max(frame.height()) as f32;
+                let nearest = match scaling {
+                    IconScaling::Bilinear => false,
+                    IconScaling::Nearest => true,
+                    IconScaling::Auto => factor >= 1.0 && factor.fract() == 0.0,
+                };
+                if nearest {
+                    frame.scale_nn(factor)
+                } else {
+                    frame.scale_smooth(factor)
+                }
+            }
+            Self::Svg(path) => rasterize_svg(path, size).unwrap_or_else(|e| {
+                eprintln!("[WARNING] failed to rasterize `{path:?}`: {e}");
+                Frame::new(0, 0)
+            }),
+        }
+    }
+}
+/// the smallest size that doesn't need upscaling, the biggest one when they all do
+pub fn pick_icon_size(frames: &[Frame], size: u32) -> Option<&Frame> {
+    let side = |frame: &&Frame| frame.width().max(frame.height());
+    frames
+        .iter()
+        .filter(|frame| side(frame) >= size)
+        .min_by_key(side)
+        .or_else(|| frames.iter().max_by_key(side))
+}
+pub struct IconCache {
+    pub icons: HashMap<(String, String), Icon>,
+    // the icons replaced at runtime, they win over the one shared by the class
+    pub windows: HashMap<Window, Icon>,
+    // the icons rendered at `size`, dropped when the size or the scaling changes
+    pub rendered: HashMap<(String, String), Frame>,
+    pub rendered_windows: HashMap<Window, Frame>,
+    // the contents of the windows, see `thumbnails`, and the same fitted in a cell
+    pub thumbnails: HashMap<Window, Frame>,
+    pub rendered_thumbnails: HashMap<Window, Frame>,
+    pub thumbnail_size: (u32, u32),
+    pub size: u32,
+    pub scaling: IconScaling,
+    pub caching: IconCaching,
+}
+impl IconCache {
+    pub fn new(caching: IconCaching) -> Self {
+        Self {
+            icons: HashMap::new(),
+            windows: HashMap::new(),
+            rendered: HashMap::new(),
+            rendered_windows: HashMap::new(),
+            thumbnails: HashMap::new(),
+            rendered_thumbnails: HashMap::new(),
+            thumbnail_size: (0, 0),
+            size: 0,
+            scaling: IconScaling::Bilinear,
+            caching,
+        }
+    }
+    pub fn set_icon(&mut self, conn: &Conn, atoms: &Atoms, task: &Task) {
+        if !task.is_virtual()
+            && let Ok(sizes) = get_net_wm_icon(conn, atoms, task.wid)
+        {
+            self.icons.insert(task.class.clone(), Icon::Raster(sizes));
+            return;
+        }
+        if let Some(entry) = match_desktop_entry(desktop_entries(), &task.class)
+            && let Ok(icon) = get_desktop_entry_icon(entry)
+        {
+            self.icons.insert(task.class.clone(), icon);
+            return;
+        }
+        if let Ok(icon) = get_hicolor_icon(&task.class.1) {
+            self.icons.insert(task.class.clone(), icon);
+            return;
+        }
+        if !task.is_virtual()
+            && let Ok(Some(wid)) = get_window_parent(conn, atoms, task.wid)
+            && let Some(parent) = window_to_task(conn, atoms, wid, &[], false)
+            && let Some(icon) = self.icons.get(&parent.class)
+        {
+            self.icons.insert(task.class.clone(), icon.clone());
+            return;
+        }
+        // a small capture of the window is still easier to spot than a blank cell
+        if !task.is_virtual()
+            && let Ok(snapshot) = get_window_snapshot(conn, task.wid, ICON_SNAPSHOT_SIZE)
+        {
+            self.icons
+                .insert(task.class.clone(), Icon::Raster(vec![snapshot]));
+            return;
+        }
+        self.icons.insert(task.class.clone(), Icon::blank());
+    }
+    /// the window changed its `_NET_WM_ICON`, as `xseticon` does, the rest of the class keeps its icon
+    pub fn set_window_icon(&mut self, conn: &Conn, atoms: &Atoms, task: &Task) {
+        self.rendered_windows.remove(&task.wid);
+        if !self.icons.contains_key(&task.class) {
+            self.set_icon(conn, atoms, task);
+            return;
+        }
+        match get_net_wm_icon(conn, atoms, task.wid) {
+            Ok(sizes) => self.windows.insert(task.wid, Icon::Raster(sizes)),
+            Err(_) => self.windows.remove(&task.wid),
+        };
+    }
+    pub fn set_icons(&mut self, conn: &Conn, atoms: &Atoms, tasks: &TaskList) {
+        self.windows.retain(|wid, _| tasks.contains(*wid));
+        self.rendered_windows.retain(|wid, _| tasks.contains(*wid));
+        self.thumbnails.retain(|wid, _| tasks.contains(*wid));
+        self.rendered_thumbnails
+            .retain(|wid, _| tasks.contains(*wid));
+        for task in tasks.list_ascending().0 {
+            if !self.icons.contains_key(&task.class) {
+                self.set_icon(conn, atoms, task);
+            }
+            // without an icon of its own the window keeps the one of its class
+            if self.caching == IconCaching::Window
+                && !task.is_virtual()
+                && !self.windows.contains_key(&task.wid)
+            {
+                let icon = match get_net_wm_icon(conn, atoms, task.wid) {
+                    Ok(sizes) => Icon::Raster(sizes),
+                    Err(_) => self.icons[&task.class].clone(),
+                };
+                self.windows.insert(task.wid, icon);
+            }
+        }
+    }
+    pub fn get(&mut self, task: &Task, size: u32, scaling: IconScaling) -> &Frame {
+        if (size, scaling) != (self.size, self.scaling) {
+            self.rendered.clear();
+            self.rendered_windows.clear();
+            self.size = size;
+            self.scaling = scaling;
+        }
+        if let Some(icon) = self.windows.get(&task.wid) {
+            return self
+                .rendered_windows
+                .entry(task.wid)
+                .or_insert_with(|| icon.render(size, scaling));
+        }
+        let icon = &self.icons[&task.class];
+        self.rendered
+            .entry(task.class.clone())
+            .or_insert_with(|| icon.render(size, scaling))
+    }
+    /// the window was captured again
+    pub fn set_thumbnail(&mut self, wid: Window, thumbnail: Frame) {
+        self.rendered_thumbnails.remove(&wid);
+        self.thumbnails.insert(wid, thumbnail);
+    }
+    /// the thumbnail of the window scaled to fit `size`, keeping its proportions
+    pub fn get_thumbnail(&mut self, wid: Window, size: (u32, u32)) -> Option<&Frame> {
+        if size != self.thumbnail_size {
+            self.rendered_thumbnails.clear();
+            self.thumbnail_size = size;
+        }
+        let frame = self.thumbnails.get(&wid)?;
+        Some(self.rendered_thumbnails.entry(wid).or_insert_with(|| {
+            let (w, h) = (frame.width() as f32, frame.height() as f32);
+            let factor = (size.0 as f32 / w).min(size.1 as f32 / h);
+            frame.scale_smooth(factor)
+        }))
+    }
+}
+/// every size of the icon, the one to show depends on the size of the cells
+pub fn get_net_wm_icon(conn: &Conn, atoms: &Atoms, wid: Window) -> Result<Vec<Frame>> {
+    let reply = conn
+        .get_property(false, wid, atoms._NET_WM_ICON, atoms.CARDINAL, 0, u32::MAX)?
+        .reply()?;
+    let Some(it) = reply.value32() else {
+        return Err("no _NET_WM_ICON".into());
+    };
+    let bytes = it.collect::<Vec<_>>();
+    let mut bytes = bytes.as_slice();
+    let mut sizes = vec![];
+
+    loop {
+        if bytes.len() < 2 {
+            break;
+        }
+        let w = bytes[0] as usize;
+        let h = bytes[1] as usize;
+        let step = w * h;
+        bytes = &bytes[2..];
+        if bytes.len() < step {
+            break;
+        }
+        if step > 0 {
+            sizes.push(Frame::from_argb_u32(&bytes[0..step], w as u32, h as u32));
+        }
+        bytes = &bytes[step..];
+    }
+    if sizes.is_empty() {
+        return Err("no _net_wm_icon".into());
+    }
+    Ok(sizes)
+}
+/// a scalable icon if there's one, every size found otherwise
+pub fn get_hicolor_icon(name: &str) -> Result<Icon> {
+    let search_term = name.to_lowercase();
+    let mut sizes = vec![];
+    let files = data_dirs()
+        .into_iter()
+        .flat_map(|dir| [dir.join("icons/hicolor"), dir.join("pixmaps")])
+        .flat_map(|dir| visit_dir(dir).unwrap_or_default());
+    for file in files {
+        let Some(filename) = file.file_name().map(|f| f.to_string_lossy()) else {
+            continue;
+        };
+        if filename.to_lowercase().contains(&search_term) {
+            match load_icon_file(&file) {
+                Ok(Icon::Raster(img)) => sizes.extend(img),
+                Ok(icon) => return Ok(icon),
+                Err(_) => continue,
+            };
+        }
+    }
+    if sizes.is_empty() {
+        return Err("no hicolor icon".into());
+    }
+    Ok(Icon::Raster(sizes))
+}
+pub fn load_icon_file(file: &PathBuf) -> Result<Icon> {
+    let ext = file.extension().and_then(|s| s.to_str());
+    if ext == Some("png") {
+        let pixmap = resvg::tiny_skia::Pixmap::load_png(file)?;
+        return Ok(Icon::Raster(vec![pixmap_to_frame(&pixmap)]));
+    }
+    if ext == Some("xpm") {
+        return Ok(Icon::Raster(vec![decode_xpm(&read_to_string(file)?)?]));
+    }
+    if ext != Some("svg") {
+        return Err(format!("unknown icon format `{file:?}`").into());
+    }
+    // parsed upfront so that broken files fall through to the next source
+    parse_svg(file)?;
+    Ok(Icon::Svg(file.clone()))
+}
+pub fn parse_svg(file: &Path) -> Result<resvg::usvg::Tree> {
+    let data = std::fs::read(file)?;
+    Ok(resvg::usvg::Tree::from_data(
+        &data,
+        &resvg::usvg::Options::default(),
+    )?)
+}
+pub fn rasterize_svg(file: &Path, size: u32) -> Result<Frame> {
+    let svg = parse_svg(file)?;
+    let svg_size = svg.size();
+    let scale = size as f32 / svg_size.width().max(svg_size.height());
+    let w = (svg_size.width() * scale).round() as u32;
+    let h = (svg_size.height() * scale).round() as u32;
+    let Some(mut pixmap) = resvg::tiny_skia::Pixmap::new(w, h) else {
+        return Ok(Frame::new(0, 0));
+    };
+    let transform = resvg::tiny_skia::Transform::from_scale(scale, scale);
+    resvg::render(&svg, transform, &mut pixmap.as_mut());
+    Ok(pixmap_to_frame(&pixmap))
+}
+pub fn pixmap_to_frame(pixmap: &resvg::tiny_skia::Pixmap) -> Frame {
+    // tiny-skia works with premultiplied alpha
+    let rgba: Vec<u8> = pixmap
+        .pixels()
+        .iter()
+        .flat_map(|px| {
+            let px = px.demultiply();
+            [px.red(), px.green(), px.blue(), px.alpha()]
+        })
+        .collect();
+    Frame::from_rgba_u8(&rgba, pixmap.width(), pixmap.height())
+}
+/// decodes the xpm3 images still shipped by legacy x apps, usually in `pixmaps`
+pub fn decode_xpm(contents: &str) -> Result<Frame> {
+    // the image is a c array of strings, everything outside of them is irrelevant
+    let mut strings = contents.split('"').skip(1).step_by(2);
+    let mut header = strings
+        .next()
+        .ok_or("missing xpm header")?
+        .split_whitespace();
+    let mut value = || -> Result<usize> { Ok(header.next().ok_or("short xpm header")?.parse()?) };
+    let (w, h, colors, cpp) = (value()?, value()?, value()?, value()?);
+    if cpp == 0 || w * h == 0 || w * h > 4096 * 4096 {
+        return Err(format!("invalid xpm size `{w}x{h}`").into());
+    }
+
+    let mut palette = HashMap::with_capacity(colors);
+    for _ in 0..colors {
+        let line = strings.next().ok_or("missing xpm color")?;
+        let key = line.get(..cpp).ok_or("short xpm color")?;
+        let tokens: Vec<_> = line[cpp..].split_whitespace().collect();
+        // prefer the color visual, the others are for mono and grayscale displays
+        let color = ["c", "g", "g4", "m"].iter().find_map(|visual| {
+            let start = tokens.iter().position(|t| t == visual)? + 1;
+            let end = tokens[start..]
+                .iter()
+                .position(|t| ["c", "g", "g4", "m", "s"].contains(t))
+                .map_or(tokens.len(), |i| start + i);
+            Some(tokens[start..end].join(" "))
+        });
+        let rgba = xpm_color(color.as_deref().unwrap_or("None"));
+        palette.insert(key, rgba);
+    }
+
+    let mut buf = Vec::with_capacity(w * h * 4);
+    for _ in 0..h {
+        let row = strings.next().ok_or("missing xpm row")?;
+        for x in 0..w {
+            let key = row.get(x * cpp..(x + 1) * cpp).ok_or("short xpm row")?;
+            buf.extend(palette.get(key).ok_or("unknown xpm color")?);
+        }
+    }
+    Ok(Frame::from_rgba_u8(&buf, w as u32, h as u32))
+}
+/// `#rgb` with 1 to 4 hex digits per channel, or one of the common color names
+pub fn xpm_color(color: &str) -> [u8; 4] {
+    if let Some(hex) = color.strip_prefix('#')
+        && !hex.is_empty()
+        && hex.len() % 3 == 0
+        && hex.len() <= 12
+    {
+        let digits = hex.len() / 3;
+        let channel = |i: usize| {
+            let v = u16::from_str_radix(&hex[i * digits..(i + 1) * digits], 16).unwrap_or(0);
+            // keep the most significant byte, scaling up the short forms
+            match digits {
+                1 => (v * 17) as u8,
+                2 => v as u8,
+                _ => (v >> ((digits - 2) * 4)) as u8,
+            }
+        };
+        return [channel(0), channel(1), channel(2), 255];
+    }
+    match color.to_lowercase().replace(' ', "").as_str() {
+        "none" => [0, 0, 0, 0],
+        "white" => [255, 255, 255, 255],
+        "red" => [255, 0, 0, 255],
+        "green" => [0, 255, 0, 255],
+        "blue" => [0, 0, 255, 255],
+        "yellow" => [255, 255, 0, 255],
+        "cyan" => [0, 255, 255, 255],
+        "magenta" => [255, 0, 255, 255],
+        "gray" | "grey" => [190, 190, 190, 255],
+        "lightgray" | "lightgrey" => [211, 211, 211, 255],
+        "darkgray" | "darkgrey" => [169, 169, 169, 255],
+        _ => [0, 0, 0, 255],
+    }
+}
+/// the icon of a desktop entry is either a path or the name of a themed icon
+pub fn get_desktop_entry_icon(entry: &DesktopEntry) -> Result<Icon> {
+    let path = PathBuf::from(&entry.icon);
+    if path.is_absolute() {
+        return load_icon_file(&path);
+    }
+    get_hicolor_icon(&entry.icon)
+}
+/// `$XDG_DATA_HOME`, `$XDG_DATA_DIRS` and the flatpak and snap exports, without duplicates
+pub fn data_dirs() -> Vec<PathBuf> {
+    let home = std::env::var("HOME").ok();
+    let mut dirs = vec![];
+    if let Ok(data_home) = std::env::var("XDG_DATA_HOME") {
+        dirs.push(PathBuf::from(data_home));
+    } else if let Some(home) = &home {
+        dirs.push(PathBuf::from(home).join(".local/share"));
+    }
+    let data_dirs = std::env::var("XDG_DATA_DIRS")
+        .ok()
+        .filter(|dirs| !dirs.is_empty())
+        .unwrap_or_else(|| "/usr/local/share:/usr/share".to_string());
+    dirs.extend(data_dirs.split(':').map(PathBuf::from));
+    for dir in SANDBOX_DATA_DIRS {
+        match (dir.strip_prefix("~/"), &home) {
+            (Some(dir), Some(home)) => dirs.push(PathBuf::from(home).join(dir)),
+            (Some(_), None) => {}
+            (None, _) => dirs.push(PathBuf::from(dir)),
+        }
+    }
+    let mut unique = vec![];
+    for dir in dirs {
+        if !unique.contains(&dir) {
+            unique.push(dir);
+        }
+    }
+    unique
+}
+/// indexed the first time they're needed, e.g. by a window with no icon of its own
+pub fn desktop_entries() -> &'static [DesktopEntry] {
+    static ENTRIES: OnceLock<Vec<DesktopEntry>> = OnceLock::new();
+    ENTRIES.get_or_init(get_desktop_entries)
+}
+/// the `applications` directories of the data dirs
+pub fn get_desktop_entries() -> Vec<DesktopEntry> {
+    let mut entries = vec![];
+    for dir in data_dirs() {
+        for file in visit_dir(dir.join("applications")).unwrap_or_default() {
+            if file.extension().is_none_or(|ext| ext != "desktop") {
+                continue;
+            }
+            let id = file.file_stem().unwrap_or_default().to_string_lossy();
+            if let Ok(contents) = read_to_string(&file)
+                && let Some(entry) = parse_desktop_entry(&id, &contents)
+            {
+                entries.push(entry);
+            }
+        }
+    }
+    entries
+}
+pub fn parse_desktop_entry(id: &str, contents: &str) -> Option<DesktopEntry> {
+    let mut in_group = false;
+    let (mut name, mut exec, mut startup_wm_class, mut icon) = (None, None, None, None);
+    for line in contents.lines().map(str::trim) {
+        if line.starts_with('[') {
+            in_group = line == "[Desktop Entry]";
+            continue;
+        }
+        let Some((key, value)) = line.split_once('=').filter(|_| in_group) else {
+            continue;
+        };
+        let value = Some(value.trim().to_string());
+        match key.trim() {
+            "Name" => name = value,
+            "Exec" => exec = value,
+            "StartupWMClass" => startup_wm_class = value,
+            "Icon" => icon = value,
+            _ => {}
+        }
+    }
+    Some(DesktopEntry {
+        id: id.to_string(),
+        name,
+        exec,
+        startup_wm_class,
+        icon: icon.filter(|icon| !icon.is_empty())?,
+    })
+}
+/// matches the window class against the declared `StartupWMClass`, then the desktop file id
+/// (also reverse dns ids like `com.spotify.Client`), the name and finally the executable
+pub fn match_desktop_entry<'a>(
+    entries: &'a [DesktopEntry],
+    (instance, class): &(String, String),
+) -> Option<&'a DesktopEntry> {
+    let is_class =
+        |value: &str| value.eq_ignore_ascii_case(class) || value.eq_ignore_ascii_case(instance);
+    let executable = |exec: &str| {
+        // skip `env` and the variables it sets
+        exec.split_whitespace()
+            .find(|arg| *arg != "env" && !arg.contains('='))
+            .and_then(|arg| arg.rsplit('/').next())
+            .map(str::to_string)
+    };
+    let heuristics: [&dyn Fn(&DesktopEntry) -> bool; 4] = [
+        &|entry| entry.startup_wm_class.as_deref().is_some_and(is_class),
+        &|entry| is_class(&entry.id) || entry.id.rsplit('.').next().is_some_and(is_class),
+        &|entry| entry.name.as_deref().is_some_and(is_class),
+        &|entry| {
+            entry
+                .exec
+                .as_deref()
+                .and_then(executable)
+                .is_some_and(|exec| is_class(&exec))
+        },
+    ];
+    heuristics
+        .iter()
+        .find_map(|matches| entries.iter().find(|entry| matches(entry)))
+}
+pub fn visit_dir(dir: PathBuf) -> Result<Vec<PathBuf>> {
+    let mut files = vec![];
+    let mut dirs = vec![dir];
+
+    while let Some(dir) = dirs.pop() {
+        let Ok(entries) = std::fs::read_dir(dir) else {
+            continue;
+        };
+        for entry in entries.filter_map(|entry| entry.ok()) {
+            let path = entry.path();
+            if path.is_dir() {
+                dirs.push(path);
+            } else {
+                files.push(path);
+            }
+        }
+    }
+    Ok(files)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Icon;
+    use super::IconCache;
+    use super::decode_xpm;
+    use super::load_icon_file;
+    use super::match_desktop_entry;
+    use super::parse_desktop_entry;
+    use super::pick_icon_size;
+    use super::xpm_color;
+    use crate::APP_NAME;
+    use crate::config::IconCaching;
+    use crate::render::Frame;
+
+    #[test]
+    fn thumbnails_keep_their_proportions() {
+        let mut icons = IconCache::new(IconCaching::Class);
+        assert!(icons.get_thumbnail(1, (64, 64)).is_none());
+        icons.set_thumbnail(1, Frame::new(320, 180));
+        let thumbnail = icons.get_thumbnail(1, (64, 64)).unwrap();
+        assert_eq!((thumbnail.width(), thumbnail.height()), (64, 36));
+        let thumbnail = icons.get_thumbnail(1, (160, 45)).unwrap();
+        assert_eq!((thumbnail.width(), thumbnail.height()), (80, 45));
+    }
+
+    #[test]
+    fn desktop_entries_match_window_classes() {
+        let entry = |id: &str, contents: &str| parse_desktop_entry(id, contents).unwrap();
+        let entries = [
+            entry(
+                "discord",
+                "[Desktop Entry]\nName=Discord\nExec=/usr/bin/Discord\nIcon=discord\n",
+            ),
+            entry(
+                "com.spotify.Client",
+                "[Desktop Entry]\nName=Spotify\nIcon=com.spotify.Client\n[Desktop Action x]\nIcon=x",
+            ),
+            entry(
+                "code",
+                "[Desktop Entry]\nName=Visual Studio Code\nExec=env FOO=1 /usr/share/code/code %F\nStartupWMClass=Code\nIcon=vscode",
+            ),
+        ];
+        let find = |instance: &str, class: &str| {
+            match_desktop_entry(&entries, &(instance.to_string(), class.to_string()))
+                .map(|entry| entry.icon.as_str())
+        };
+        assert_eq!(find("discord", "discord"), Some("discord"));
+        assert_eq!(find("spotify", "Spotify"), Some("com.spotify.Client"));
+        assert_eq!(find("code", "Code"), Some("vscode"));
+        assert_eq!(find("unknown", "Unknown"), None);
+        assert_eq!(entries[1].icon, "com.spotify.Client");
+        assert!(parse_desktop_entry("x", "[Desktop Entry]\nName=No icon\n").is_none());
+    }
+
+    #[test]
+    fn icon_sizes() {
+        let sizes = [16, 32, 48, 256].map(|side| Frame::new(side, side));
+        let pick = |size| pick_icon_size(&sizes, size).map(Frame::width);
+        assert_eq!(pick(32), Some(32));
+        assert_eq!(pick(40), Some(48));
+        assert_eq!(pick(12), Some(16));
+        assert_eq!(pick(512), Some(256));
+        assert_eq!(pick_icon_size(&[], 32).map(Frame::width), None);
+    }
+
+    #[test]
+    fn xpm_images() {
+        let xpm = r##"/* XPM */
+static char *icon[] = {
+/* columns rows colors chars-per-pixel */
+"3 2 3 2 ",
+"   c None",
+".. c #ff0000",
+"xx s border c light gray",
+"  ..xx",
+"xx..  "
+};"##;
+        let frame = decode_xpm(xpm).unwrap();
+        assert_eq!((frame.width(), frame.height()), (3, 2));
+        let expected = Frame::from_rgba_u8(
+            &[
+                [0, 0, 0, 0],
+                [255, 0, 0, 255],
+                [211, 211, 211, 255],
+                [211, 211, 211, 255],
+                [255, 0, 0, 255],
+                [0, 0, 0, 0],
+            ]
+            .concat(),
+            3,
+            2,
+        );
+        assert_eq!(frame.buf_u8(), expected.buf_u8());
+        assert_eq!(xpm_color("#fff"), [255, 255, 255, 255]);
+        assert_eq!(xpm_color("#12345678abcd"), [0x12, 0x56, 0xab, 255]);
+        assert!(decode_xpm(&xpm.replace("\"xx..  \"", "")).is_err());
+        assert!(decode_xpm("\"0 0 0 1\"").is_err());
+    }
+
+    #[test]
+    fn png_images() {
+        let mut pixmap = resvg::tiny_skia::Pixmap::new(2, 1).unwrap();
+        pixmap.pixels_mut()[0] = resvg::tiny_skia::ColorU8::from_rgba(255, 0, 0, 255).premultiply();
+        pixmap.pixels_mut()[1] = resvg::tiny_skia::ColorU8::from_rgba(0, 0, 255, 128).premultiply();
+        let dir = std::env::temp_dir().join(format!("{APP_NAME}-png-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("icon.png");
+        pixmap.save_png(&path).unwrap();
+        let Ok(Icon::Raster(frames)) = load_icon_file(&path) else {
+            panic!("the png wasn't loaded");
+        };
+        assert_eq!((frames[0].width(), frames[0].height()), (2, 1));
+        let expected = Frame::from_rgba_u8(&[[255, 0, 0, 255], [0, 0, 255, 128]].concat(), 2, 1);
+        assert_eq!(frames[0].buf_u8(), expected.buf_u8());
+        std::fs::write(&path, b"not a png").unwrap();
+        assert!(load_icon_file(&path).is_err());
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
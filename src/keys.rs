@@ -0,0 +1,244 @@
+use x11rb::connection::Connection;
+use x11rb::protocol::xinput;
+use x11rb::protocol::xinput::DeviceId;
+use x11rb::protocol::xinput::XIEventMask;
+use x11rb::protocol::xproto::ConnectionExt as _;
+use x11rb::protocol::xproto::GrabMode;
+use x11rb::protocol::xproto::KeyButMask;
+use x11rb::protocol::xproto::Keycode;
+use x11rb::protocol::xproto::ModMask;
+use x11rb::protocol::xproto::Screen;
+use xkbcommon::xkb::Keysym;
+use xkbcommon::xkb::keysym_to_utf32;
+use xkbcommon::xkb::utf32_to_keysym;
+
+use crate::Conn;
+use crate::Result;
+use crate::config::Config;
+
+pub struct Keymap {
+    pub key_next: Keycode,
+    pub key_prev: Keycode,
+    pub key_kill: Keycode,
+    pub key_toggle_scope: Keycode,
+    pub key_toggle_monitor: Keycode,
+    pub key_palette: Keycode,
+    pub key_search: Keycode,
+    pub key_move_overlay: Keycode,
+    pub key_quit: Keycode,
+    pub key_select: Keycode,
+    pub key_mod: Keycode,
+    pub key_suspend: Option<Keycode>,
+    pub modifier: ModMask,
+    // the keyboard mapping, to turn the keys typed in a query into text
+    pub min_keycode: Keycode,
+    pub keysyms_per_keycode: usize,
+    pub keysyms: Vec<u32>,
+}
+impl Keymap {
+    pub fn init(conn: &Conn, screen: &Screen, conf: &Config, grab: bool) -> Result<Self> {
+        let setup = conn.setup();
+        let min_keycode = setup.min_keycode;
+        let max_keycode = setup.max_keycode;
+        let reply = conn
+            .get_keyboard_mapping(min_keycode, max_keycode - min_keycode + 1)?
+            .reply()?;
+        let sym_to_code = |k: Keysym| {
+            reply
+                .keysyms
+                .iter()
+                .position(|&ks| ks == k.raw())
+                .map(|i| (i / reply.keysyms_per_keycode as usize) as u8 + min_keycode)
+                .unwrap()
+        };
+
+        let key_next = sym_to_code(conf.key_next);
+        let key_prev = sym_to_code(conf.key_prev);
+        let key_kill = sym_to_code(conf.key_kill);
+        let key_toggle_scope = sym_to_code(conf.key_toggle_scope);
+        let key_toggle_monitor = sym_to_code(conf.key_toggle_monitor);
+        let key_palette = sym_to_code(conf.key_palette);
+        let key_search = sym_to_code(conf.key_search);
+        let key_move_overlay = sym_to_code(conf.key_move_overlay);
+        let key_quit = sym_to_code(conf.key_quit);
+        let key_select = sym_to_code(conf.key_select);
+        let key_mod = sym_to_code(conf.key_mod);
+        let key_suspend = conf.key_suspend.map(sym_to_code);
+
+        let map = conn.get_modifier_mapping()?.reply()?;
+        let keycodes_per_mod = map.keycodes_per_modifier() as usize;
+        let mut modifier = 0;
+        for (mod_index, chunk) in map.keycodes.chunks(keycodes_per_mod).enumerate() {
+            if chunk.contains(&key_mod) {
+                modifier = 1 << mod_index;
+                break;
+            }
+        }
+        if modifier == 0 {
+            return Err(format!("`{key_mod}` is not a modifier").into());
+        }
+        let modifier = ModMask::from(modifier as u16);
+        let this = Self {
+            key_next,
+            key_prev,
+            key_kill,
+            key_toggle_scope,
+            key_toggle_monitor,
+            key_palette,
+            key_search,
+            key_move_overlay,
+            key_quit,
+            key_select,
+            key_mod,
+            key_suspend,
+            modifier,
+            min_keycode,
+            keysyms_per_keycode: reply.keysyms_per_keycode as usize,
+            keysyms: reply.keysyms,
+        };
+        if grab {
+            this.grab(conn, screen, false)?;
+        }
+        Ok(this)
+    }
+    /// the keysym of the key at the shift level of `state`, letters without a second level are
+    /// uppercased the way the core protocol does
+    pub fn keysym(&self, key: Keycode, state: KeyButMask) -> Keysym {
+        let per = self.keysyms_per_keycode;
+        let start = key.saturating_sub(self.min_keycode) as usize * per;
+        let syms = self.keysyms.get(start..start + per).unwrap_or_default();
+        let lower = Keysym::new(syms.first().copied().unwrap_or_default());
+        let shifted = state.contains(KeyButMask::SHIFT) != state.contains(KeyButMask::LOCK);
+        match syms.get(1).copied().filter(|sym| *sym != 0) {
+            Some(upper) if shifted => Keysym::new(upper),
+            None if shifted => char::from_u32(keysym_to_utf32(lower))
+                .and_then(|c| c.to_uppercase().next())
+                .map_or(lower, |c| utf32_to_keysym(c as u32)),
+            _ => lower,
+        }
+    }
+    /// the character typed by the key, none for the keys that don't type one
+    pub fn key_char(&self, key: Keycode, state: KeyButMask) -> Option<char> {
+        char::from_u32(keysym_to_utf32(self.keysym(key, state))).filter(|c| !c.is_control())
+    }
+    /// the keys grabbed on the root window, while suspended they reach the focused window. the
+    /// ones that only act on the shown list come through the keyboard grab taken on show
+    fn switching_keys(&self) -> [Keycode; 4] {
+        [self.key_next, self.key_prev, self.key_kill, self.key_quit]
+    }
+    /// `key_suspend` is grabbed even while suspended, to resume
+    pub fn grab(&self, conn: &Conn, screen: &Screen, suspended: bool) -> Result<()> {
+        let mode = GrabMode::ASYNC;
+        let modifier = self.modifier;
+        if !suspended {
+            for key in self.switching_keys() {
+                conn.grab_key(false, screen.root, modifier, key, mode, mode)?;
+            }
+        }
+        if let Some(key) = self.key_suspend {
+            conn.grab_key(false, screen.root, modifier, key, mode, mode)?;
+        }
+
+        xinput::ConnectionExt::xinput_xi_select_events(
+            conn,
+            screen.root,
+            &[xinput::EventMask {
+                deviceid: DeviceId::from(0u16),
+                mask: vec![XIEventMask::KEY_RELEASE],
+            }],
+        )?;
+        Ok(())
+    }
+    /// lets the switching keys through to the focused window, see `key_suspend`
+    pub fn ungrab(&self, conn: &Conn, screen: &Screen) -> Result<()> {
+        for key in self.switching_keys() {
+            conn.ungrab_key(key, screen.root, self.modifier)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use x11rb::protocol::xproto::KeyButMask;
+    use xkbcommon::xkb::Keysym;
+
+    use crate::cli::Mode;
+    use crate::mock;
+    use crate::mock::WINDOWS;
+    use crate::mock::desktop;
+
+    #[test]
+    fn suspend_lets_the_keys_through() {
+        let (server, conn) = desktop();
+        let daemon = mock::spawn_with(Mode::Daemon, conn, "key_suspend: F1\n");
+        let suspended = server.atom("_GOTO_SUSPENDED");
+        server.wait_for(|call| *call == mock::Call::ChangeProperty(mock::ROOT, suspended));
+        assert_eq!(server.property(mock::ROOT, "_GOTO_SUSPENDED"), Some(0));
+        let (tab, f1) = (server.keycode(Keysym::Tab), server.keycode(Keysym::F1));
+        server.clear_calls();
+        server.key_press(Keysym::F1, KeyButMask::MOD1);
+        server.wait_for(|call| *call == mock::Call::ChangeProperty(mock::ROOT, suspended));
+        assert_eq!(server.property(mock::ROOT, "_GOTO_SUSPENDED"), Some(1));
+        let calls = server.calls();
+        assert!(calls.contains(&mock::Call::UngrabKey(tab)));
+        assert!(!calls.contains(&mock::Call::UngrabKey(f1)));
+
+        // as written by `--suspend off`, the grabs are back and a notice is shown
+        server.clear_calls();
+        server.set_property(mock::ROOT, "_GOTO_SUSPENDED", "CARDINAL", &[0]);
+        server.notify_property(mock::ROOT, "_GOTO_SUSPENDED");
+        server.wait_for(|call| matches!(call, mock::Call::MapWindow(_)));
+        assert!(server.calls().contains(&mock::Call::GrabKey(tab)));
+
+        drop(server);
+        assert!(daemon.join().unwrap().is_err());
+    }
+
+    #[test]
+    fn passthrough_follows_the_focus() {
+        let (server, conn) = desktop();
+        server.set_text(
+            WINDOWS[1],
+            "WM_CLASS",
+            "STRING",
+            "qemu\0Qemu-system-x86_64\0",
+        );
+        let daemon = mock::spawn_with(Mode::Daemon, conn, "passthrough_class: qemu\n");
+        let tab = server.keycode(Keysym::Tab);
+        server.wait_for(|call| *call == mock::Call::GrabKey(tab));
+        assert!(!server.calls().contains(&mock::Call::UngrabKey(tab)));
+
+        server.set_property(mock::ROOT, "_NET_ACTIVE_WINDOW", "WINDOW", &WINDOWS[1..]);
+        server.notify_property(mock::ROOT, "_NET_ACTIVE_WINDOW");
+        server.wait_for(|call| *call == mock::Call::UngrabKey(tab));
+        server.clear_calls();
+        server.set_property(mock::ROOT, "_NET_ACTIVE_WINDOW", "WINDOW", &WINDOWS[..1]);
+        server.notify_property(mock::ROOT, "_NET_ACTIVE_WINDOW");
+        server.wait_for(|call| *call == mock::Call::GrabKey(tab));
+
+        drop(server);
+        assert!(daemon.join().unwrap().is_err());
+    }
+
+    #[test]
+    fn in_list_keys_are_grabbed_while_shown() {
+        let (server, conn) = desktop();
+        let daemon = mock::spawn(Mode::Daemon, conn);
+        server.wait_for(|call| matches!(call, mock::Call::GrabKey(_)));
+        server.key_press(Keysym::Tab, KeyButMask::MOD1);
+        server.wait_for(|call| *call == mock::Call::GrabKeyboard(mock::ROOT));
+        let in_list = [Keysym::D, Keysym::o, Keysym::p, Keysym::m].map(|k| server.keycode(k));
+        let calls = server.calls();
+        assert!(
+            !calls
+                .iter()
+                .any(|call| in_list.map(mock::Call::GrabKey).contains(call))
+        );
+        server.key_press(Keysym::Escape, KeyButMask::MOD1);
+        server.wait_for(|call| *call == mock::Call::UngrabKeyboard);
+
+        drop(server);
+        assert!(daemon.join().unwrap().is_err());
+    }
+}
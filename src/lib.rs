@@ -0,0 +1,450 @@
+// #![allow(unused)]
+#![allow(clippy::identity_op)]
+
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::error::Error;
+use std::process::ExitCode;
+use std::time::Duration;
+use std::time::Instant;
+use std::time::SystemTime;
+
+use x11rb::connection::Connection;
+use x11rb::connection::RequestConnection;
+use x11rb::protocol::randr;
+use x11rb::protocol::randr::ConnectionExt as _;
+use x11rb::protocol::xproto::ChangeWindowAttributesAux;
+use x11rb::protocol::xproto::ConnectionExt as _;
+use x11rb::protocol::xproto::EventMask;
+use x11rb::rust_connection::RustConnection;
+
+use crate::cli::Alias;
+use crate::cli::Args;
+use crate::cli::Mode;
+use crate::cli::bench;
+use crate::cli::focus_target;
+use crate::cli::load_aliases;
+use crate::cli::print_event;
+use crate::cli::request_suspend;
+use crate::cli::run_session;
+use crate::cli::set_alias;
+use crate::cli::set_class_alias;
+use crate::cli::set_config_value;
+use crate::config::ColorScheme;
+use crate::config::Overflow;
+use crate::config::ShowCurrent;
+use crate::config::Startup;
+use crate::config::State;
+use crate::config::Thumbnails;
+use crate::icons::IconCache;
+use crate::keys::Keymap;
+use crate::render::Area;
+use crate::render::Frame;
+use crate::render::TextRenderer;
+use crate::render::Tooltip;
+use crate::render::compute_layout;
+use crate::render::compute_page_size;
+use crate::render::compute_window_geometry;
+use crate::switcher::Changes;
+use crate::switcher::ListState;
+use crate::switcher::Switcher;
+use crate::switcher::load_themes;
+use crate::tasks::Sort;
+use crate::tasks::TaskList;
+use crate::tasks::VirtualQuery;
+use crate::tasks::VirtualRefresh;
+use crate::x11::AtomCollection;
+use crate::x11::Reactor;
+use crate::x11::Teardown;
+use crate::x11::Timer;
+use crate::x11::WindowSource;
+use crate::x11::choose_visual;
+use crate::x11::color_scheme;
+use crate::x11::create_graphic_context;
+use crate::x11::create_window;
+use crate::x11::get_active_window;
+use crate::x11::get_current_desktop;
+use crate::x11::get_desktop_names;
+use crate::x11::get_dpi;
+use crate::x11::get_monitors;
+use crate::x11::get_xsettings;
+use crate::x11::has_composite;
+use crate::x11::has_damage;
+use crate::x11::has_render;
+use crate::x11::is_xwayland;
+use crate::x11::load_cursor;
+use crate::x11::request_suspended_publish;
+use crate::x11::watch_xsettings;
+
+#[allow(unused)]
+macro_rules! log_time {
+    ($fn_call:expr) => {{
+        let start = std::time::Instant::now();
+        let result = $fn_call;
+        let elapsed = start.elapsed();
+        println!("Took: {:?}", elapsed);
+        result
+    }};
+}
+
+mod cli;
+pub mod config;
+pub mod icons;
+pub mod keys;
+#[cfg(test)]
+mod mock;
+pub mod render;
+mod switcher;
+pub mod tasks;
+pub mod x11;
+
+const APP_NAME: &str = "goto";
+const PING_TIMEOUT: Duration = Duration::from_secs(3);
+const TICK_INTERVAL: Duration = Duration::from_secs(1);
+// the clocks are compared on every wakeup, the timer only bounds how late a resume is noticed
+// when nothing else happens
+const CLOCK_INTERVAL: Duration = Duration::from_secs(30);
+const ACTIVATION_TIMEOUT: Duration = Duration::from_millis(250);
+// the frames sent per second with `low_bandwidth`, over ssh or vnc each one is a full image
+const LOW_BANDWIDTH_INTERVAL: Duration = Duration::from_millis(100);
+// the longest side of the captured thumbnails, they are scaled down again to fit the cells
+const THUMBNAIL_SIZE: u32 = 320;
+// the capture of a window without icon, see `IconCache::set_icon`
+const ICON_SNAPSHOT_SIZE: u32 = 64;
+// the damaged windows are captured again at most this often
+const THUMBNAIL_INTERVAL: Duration = Duration::from_millis(200);
+// how long `enter_animation` takes and how often its frames are sent
+const ENTER_DURATION: Duration = Duration::from_millis(150);
+const ENTER_FRAME: Duration = Duration::from_millis(16);
+// how long the notice of `key_suspend` stays on screen
+const NOTICE_DURATION: Duration = Duration::from_millis(1200);
+// a provider that takes longer is killed, the list doesn't wait for it anyway
+const PROVIDER_TIMEOUT: Duration = Duration::from_secs(5);
+const PROVIDER_POLL: Duration = Duration::from_millis(20);
+// a wall clock jump past the monotonic clock longer than this is taken as a resume
+const RESUME_THRESHOLD: Duration = Duration::from_secs(5);
+const EXIT_CANCELLED: u8 = 1;
+const EXIT_NO_CANDIDATES: u8 = 2;
+const EXIT_ERROR: u8 = 3;
+
+type Atoms = AtomCollection;
+type Conn = RustConnection;
+type Result<T, E = Box<dyn Error>> = std::result::Result<T, E>;
+
+pub fn main() -> ExitCode {
+    match std::panic::catch_unwind(run) {
+        Ok(Ok(code)) => code,
+        Ok(Err(e)) => {
+            eprintln!("[ERROR] {e}");
+            ExitCode::from(EXIT_ERROR)
+        }
+        Err(_) => ExitCode::from(EXIT_ERROR),
+    }
+}
+fn run() -> Result<ExitCode> {
+    let args = &Args::parse()?;
+    if let Some((key, value)) = &args.set {
+        return set_config_value(args.config_path(), key, value);
+    }
+    let displays = match args.displays.as_slice() {
+        [] => return run_display(args, None),
+        [display] => return run_display(args, Some(display)),
+        displays => displays,
+    };
+    if args.mode != Mode::Daemon
+        || args.focus.is_some()
+        || args.alias.is_some()
+        || args.suspend.is_some()
+        || args.session.is_some()
+        || args.bench
+    {
+        return Err("more than one `--display` is only supported by the daemon".into());
+    }
+    // one daemon per display, each with its own window, grabs and tasks
+    let failed = std::thread::scope(|scope| {
+        let daemons: Vec<_> = displays
+            .iter()
+            .map(|display| {
+                let daemon =
+                    scope.spawn(|| run_display(args, Some(display)).map_err(|e| e.to_string()));
+                (display, daemon)
+            })
+            .collect();
+        let mut failed = false;
+        for (display, daemon) in daemons {
+            match daemon.join() {
+                Ok(Ok(_)) => {}
+                Ok(Err(e)) => {
+                    eprintln!("[ERROR] {display}: {e}");
+                    failed = true;
+                }
+                // the panic message was already printed by the thread
+                Err(_) => {
+                    eprintln!("[ERROR] {display}: the daemon panicked");
+                    failed = true;
+                }
+            }
+        }
+        failed
+    });
+    Ok(if failed {
+        ExitCode::from(EXIT_ERROR)
+    } else {
+        ExitCode::SUCCESS
+    })
+}
+fn run_display(args: &Args, display: Option<&str>) -> Result<ExitCode> {
+    let (conn, screen_num) = x11rb::connect(display)?;
+    run_connection(args, &conn, screen_num)
+}
+/// the switcher on an established connection, the tests pass one to a fake server
+fn run_connection(args: &Args, conn: &Conn, screen_num: usize) -> Result<ExitCode> {
+    let oneshot = args.mode != Mode::Daemon;
+    let screen_num = &screen_num;
+    let res_db = x11rb::resource_manager::new_from_default(conn)?;
+    let screen = &conn.setup().roots[*screen_num];
+    let (depth, visual) = choose_visual(conn, *screen_num)?;
+    let has_render = has_render(conn);
+    let atoms = &AtomCollection::new(conn)?.reply()?;
+
+    // the xsettings manager of the desktop provides the dpi, the font size and the color scheme
+    let xsettings_selection = conn
+        .intern_atom(false, format!("_XSETTINGS_S{screen_num}").as_bytes())?
+        .reply()?
+        .atom;
+    let xsettings_owner = watch_xsettings(conn, xsettings_selection).ok().flatten();
+    let xsettings = xsettings_owner
+        .and_then(|owner| get_xsettings(conn, atoms, owner).ok())
+        .unwrap_or_default();
+    let config_path = args.config_path();
+    let state_path = args.state_path();
+    let themes = load_themes(
+        config_path.as_deref(),
+        state_path.as_deref(),
+        screen,
+        &res_db,
+        &xsettings,
+        has_render,
+    );
+    let themed = themes[0].light_theme.is_some() || themes[0].dark_theme.is_some();
+    let scheme = if themed {
+        color_scheme(&xsettings)
+    } else {
+        ColorScheme::Dark
+    };
+    let conf = &themes[scheme as usize];
+    let dpi = get_dpi(&res_db, screen, &xsettings)?;
+    let has_composite = has_composite(conn);
+    if conf.thumbnails != Thumbnails::Off && !has_composite {
+        eprintln!("[WARNING] the composite extension is not available, `thumbnails` are off");
+    }
+    if !has_render && themes[0].low_bandwidth == Some(true) {
+        eprintln!("[INFO] the render extension is not available, using the low bandwidth profile");
+    }
+
+    // the wayland clients are invisible to goto, and the grabs only fire while an x one is focused
+    if is_xwayland(conn) {
+        eprintln!(
+            "[INFO] running under Xwayland: only the X clients are listed and the pointer is never warped"
+        );
+        if !oneshot && args.focus.is_none() {
+            eprintln!(
+                "[WARNING] under Xwayland `key_next` and `key_prev` only work while an X window is focused, bind `goto --oneshot` to a key of the compositor instead"
+            );
+        }
+    }
+
+    let source = WindowSource::new(conn, screen, atoms, conf);
+    let mut root_mask = EventMask::PROPERTY_CHANGE;
+    if source.use_tree {
+        eprintln!("[INFO] `_NET_CLIENT_LIST` is not available, falling back to the window tree");
+    }
+    if source.stacking_tree && !source.use_tree {
+        eprintln!(
+            "[INFO] `_NET_CLIENT_LIST_STACKING` is not available, falling back to the window tree"
+        );
+    }
+    // clients answer pings by sending the event back to the root window
+    if source.use_tree || source.tracks_override_redirect() || conf.ping || source.stacking_tree {
+        root_mask |= EventMask::SUBSTRUCTURE_NOTIFY;
+    }
+    // a new xsettings manager announces itself on the root window
+    root_mask |= EventMask::STRUCTURE_NOTIFY;
+    conn.change_window_attributes(
+        screen.root,
+        &ChangeWindowAttributesAux::new().event_mask(root_mask),
+    )?;
+
+    if args.focus.is_some() {
+        let wids = source.list(conn, screen, atoms)?;
+        return focus_target(conn, screen, atoms, conf, wids, args);
+    }
+    if let Some(suspend) = args.suspend {
+        return request_suspend(conn, screen, atoms, suspend);
+    }
+    match &args.alias {
+        Some(Alias::Window(wid, name)) => return set_alias(conn, atoms, *wid, name),
+        Some(Alias::Class(wid, name)) => {
+            return set_class_alias(conn, screen, atoms, *wid, name, args.state_path());
+        }
+        None => {}
+    }
+    if let Some(session) = &args.session {
+        let wids = source.list(conn, screen, atoms)?;
+        return run_session(conn, atoms, wids, session, args.state_path());
+    }
+    if args.bench {
+        let wids = source.list(conn, screen, atoms)?;
+        return bench(conn, screen, atoms, conf, wids, (depth, visual));
+    }
+
+    let kb = Keymap::init(conn, screen, conf, !oneshot)?;
+    if !oneshot {
+        request_suspended_publish(conn, screen, atoms, false)?;
+    }
+    let mut tasks = TaskList::new();
+    tasks.set_filter(conf.filter);
+    tasks.set_collapse_transients(conf.collapse_transients);
+    tasks.set_title_fallback(conf.title_fallback.clone());
+    tasks.set_title_format(conf.title_format.clone());
+    tasks.set_aliases(conf.aliases.clone());
+    tasks.set_saved_aliases(load_aliases(&State::load(args.state_path())));
+    tasks.set_sort(conf.sort);
+    tasks.set_group_by_class(conf.group_by_class);
+    tasks.set_hide_current(conf.show_current == ShowCurrent::Hide);
+    tasks.set_history(conf.closed_history);
+    let has_randr = conn
+        .extension_information(randr::X11_EXTENSION_NAME)?
+        .is_some();
+    if has_randr {
+        let mask = randr::NotifyMask::SCREEN_CHANGE
+            | randr::NotifyMask::CRTC_CHANGE
+            | randr::NotifyMask::OUTPUT_CHANGE;
+        conn.randr_select_input(screen.root, mask)?;
+        let monitors = get_monitors(conn, screen).unwrap_or_default();
+        tasks.set_monitors(monitors, conf.monitor_labels);
+    }
+    if conf.desktop_sections {
+        let names = get_desktop_names(conn, screen, atoms).unwrap_or_default();
+        tasks.set_desktop_names(names, true);
+    }
+    tasks.set_override_redirect_classes(conf.override_redirect_classes.clone());
+    tasks.set_desktop(get_current_desktop(conn, screen, atoms).ok().flatten());
+    // a lazy daemon doesn't query the windows until the first keypress
+    let populated = oneshot || conf.startup == Startup::Eager;
+    if populated {
+        let wids = source.list(conn, screen, atoms).unwrap_or_default();
+        let (_, mut added) = tasks.diff_update(wids, conn, atoms);
+        let provided = VirtualQuery::new(conn, screen, atoms, conf).run();
+        added.extend(tasks.update_virtual(provided).1);
+        if conf.sort == Sort::Stacking {
+            let wids = source.list_stacking(conn, screen, atoms);
+            tasks.restack(wids.unwrap_or_default());
+        }
+        if conf.show_current != ShowCurrent::Show
+            && let Ok(Some(wid)) = get_active_window(conn, screen, atoms)
+        {
+            tasks.focus_by_wid(wid);
+        }
+        tasks.mark_seen();
+        if args.events {
+            added
+                .iter()
+                .for_each(|wid| print_event("added", *wid, tasks.get_task_by_id(*wid)));
+        }
+        if let Ok(Some(wid)) = get_active_window(conn, screen, atoms) {
+            tasks.focus_by_wid(wid)
+        }
+    }
+    let mut icons = IconCache::new(conf.icon_cache);
+    if conf.show_icons && populated {
+        icons.set_icons(conn, atoms, &tasks);
+    }
+    // the monitor is picked when the list is shown
+    let output = Area::of_screen(screen);
+    let geometry = compute_window_geometry(conf, output, None, tasks.rows())
+        .unwrap_or(Area::new(0.0, 0.0, 1.0, 1.0));
+    tasks.set_paginate(conf.overflow == Overflow::Paginate);
+    tasks.set_layout(compute_layout(conf, output, tasks.rows()));
+    tasks.set_page_size(compute_page_size(conf, output, tasks.rows()));
+    let window = create_window(conn, screen, atoms, geometry, depth, visual, conf.stacking)?;
+    let teardown = Teardown {
+        conn,
+        root: screen.root,
+        window,
+        selection: atoms._GOTO_SELECTION,
+        suspended: atoms._GOTO_SUSPENDED,
+    };
+    let gc = create_graphic_context(conn, window)?;
+    let pointer_mask =
+        EventMask::POINTER_MOTION | EventMask::LEAVE_WINDOW | EventMask::BUTTON_PRESS;
+    conn.change_window_attributes(
+        window,
+        &ChangeWindowAttributesAux::new().event_mask(
+            EventMask::EXPOSURE | EventMask::KEY_PRESS | EventMask::KEY_RELEASE | pointer_mask,
+        ),
+    )?;
+    let hand_cursor = load_cursor(conn, *screen_num, &res_db, "hand2")
+        .inspect_err(|e| eprintln!("[WARNING] failed to load the `hand2` cursor: {e}"))
+        .unwrap_or(x11rb::NONE);
+    let tooltip = Tooltip::new(conn, screen, atoms, depth, visual)?;
+    let mut reactor = Reactor::new();
+    reactor.set_timer(Timer::Clock, CLOCK_INTERVAL);
+    let tr = TextRenderer::new(conf)?;
+
+    let switcher = Switcher {
+        args,
+        conn,
+        screen: screen.clone(),
+        atoms,
+        oneshot,
+        source,
+        kb,
+        res_db,
+        has_composite,
+        has_damage: has_composite && has_damage(conn),
+        xsettings_selection,
+        xsettings_owner,
+        xsettings,
+        themes,
+        dpi,
+        themed,
+        scheme,
+        tasks,
+        populated,
+        icons,
+        tr,
+        window,
+        gc,
+        depth,
+        frame: Frame::new(geometry.w as u32, geometry.h as u32),
+        geometry,
+        output,
+        origin: None,
+        tooltip,
+        hand_cursor,
+        reactor,
+        _teardown: teardown,
+        state: ListState::Hidden,
+        hovered: None,
+        overflowing: None,
+        selected_at: Instant::now(),
+        redirected: HashSet::new(),
+        damages: HashMap::new(),
+        damaged: HashSet::new(),
+        virtual_refresh: VirtualRefresh::new(),
+        activating: None,
+        prior_focus: x11rb::NONE,
+        raised: false,
+        suspended: false,
+        passthrough: false,
+        released: false,
+        frames: HashMap::new(),
+        clock: (Instant::now(), SystemTime::now()),
+        draw_time: Duration::ZERO,
+        sent_at: Instant::now(),
+        published: None,
+        changed: Changes::default(),
+    };
+    switcher.run()
+}
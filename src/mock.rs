@@ -0,0 +1,726 @@
+//! a fake x server on the other end of a socket pair, so that the event loop runs in the tests.
+//! it speaks just enough of the protocol for goto: the requests are answered from a table of
+//! properties, the ones with a visible effect are recorded and the test sends the input events
+use std::collections::HashMap;
+use std::fs::read_to_string;
+use std::io::Read;
+use std::io::Write;
+use std::os::unix::net::UnixStream;
+use std::path::Path;
+use std::path::PathBuf;
+use std::process::ExitCode;
+use std::sync::Arc;
+use std::sync::Condvar;
+use std::sync::Mutex;
+use std::sync::OnceLock;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+use x11rb::protocol::xinput;
+use x11rb::protocol::xproto::*;
+use x11rb::rust_connection::DefaultStream;
+use x11rb::rust_connection::RustConnection;
+use x11rb::x11_utils::Serialize;
+use xkbcommon::xkb::Keysym;
+
+use crate::APP_NAME;
+use crate::Conn;
+use crate::Result;
+use crate::cli::Args;
+use crate::cli::Mode;
+use crate::config::Config;
+use crate::run_connection;
+
+pub(crate) const ROOT: Window = 0x100;
+pub(crate) const SCREEN_SIZE: (u16, u16) = (800, 600);
+const ROOT_VISUAL: Visualid = 0x21;
+const XINPUT_OPCODE: u8 = 131;
+const MIN_KEYCODE: Keycode = 8;
+// how long a test waits for goto before failing
+const TIMEOUT: Duration = Duration::from_secs(5);
+// the text needs a font, the first one found is used
+const FONTS: [&str; 3] = [
+    "/usr/share/fonts/noto/NotoSans-Regular.ttf",
+    "/usr/share/fonts/TTF/DejaVuSans.ttf",
+    "/usr/share/fonts/truetype/dejavu/DejaVuSans.ttf",
+];
+
+/// the requests with a visible effect, in the order goto made them
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Call {
+    CreateWindow(Window),
+    MapWindow(Window),
+    UnmapWindow(Window),
+    PutImage(Window),
+    GetImage(Window),
+    GrabKey(Keycode),
+    UngrabKey(Keycode),
+    GrabKeyboard(Window),
+    UngrabKeyboard,
+    SetInputFocus(Window),
+    ClientMessage(Window, Atom, [u32; 5]),
+    ChangeProperty(Window, Atom),
+    DeleteProperty(Window, Atom),
+}
+
+struct State {
+    atoms: Vec<String>,
+    // the type, the format and the value of each property
+    properties: HashMap<(Window, Atom), (Atom, u8, Vec<u8>)>,
+    calls: Vec<Call>,
+    // of the last request, events carry it
+    sequence: u16,
+    focus: Window,
+}
+
+type Shared = (Mutex<State>, Condvar);
+
+pub(crate) struct MockServer {
+    shared: Arc<Shared>,
+    // events are written between two replies
+    writer: Arc<Mutex<UnixStream>>,
+}
+impl MockServer {
+    /// starts answering on a thread, the connection is set up already
+    pub(crate) fn start() -> Result<(Self, Conn)> {
+        let (client, server) = UnixStream::pair()?;
+        let state = State {
+            // the predefined atoms keep their ids, `{:#?}` prints their names
+            atoms: (0..=68u8)
+                .map(|atom| format!("{:#?}", AtomEnum::from(atom)))
+                .collect(),
+            properties: HashMap::new(),
+            calls: vec![],
+            sequence: 0,
+            focus: ROOT,
+        };
+        let shared = Arc::new((Mutex::new(state), Condvar::new()));
+        let writer = Arc::new(Mutex::new(server.try_clone()?));
+        let this = Self {
+            shared: shared.clone(),
+            writer: writer.clone(),
+        };
+        std::thread::spawn(move || serve(server, &shared, &writer));
+        let (stream, _) = DefaultStream::from_unix_stream(client)?;
+        let conn = RustConnection::connect_to_stream(stream, 0)?;
+        Ok((this, conn))
+    }
+    pub(crate) fn atom(&self, name: &str) -> Atom {
+        self.shared.0.lock().unwrap().intern(name, false)
+    }
+    pub(crate) fn set_property(&self, window: Window, name: &str, type_: &str, value: &[u32]) {
+        let value = value.iter().flat_map(|v| v.to_ne_bytes()).collect();
+        let mut state = self.shared.0.lock().unwrap();
+        let (name, type_) = (state.intern(name, false), state.intern(type_, false));
+        state.properties.insert((window, name), (type_, 32, value));
+    }
+    pub(crate) fn set_text(&self, window: Window, name: &str, type_: &str, value: &str) {
+        let mut state = self.shared.0.lock().unwrap();
+        let (name, type_) = (state.intern(name, false), state.intern(type_, false));
+        let value = value.as_bytes().to_vec();
+        state.properties.insert((window, name), (type_, 8, value));
+    }
+    /// the first 32 bits value of the property
+    pub(crate) fn property(&self, window: Window, name: &str) -> Option<u32> {
+        let mut state = self.shared.0.lock().unwrap();
+        let name = state.intern(name, false);
+        let (_, _, value) = state.properties.get(&(window, name))?;
+        Some(u32::from_ne_bytes(value.get(..4)?.try_into().ok()?))
+    }
+    pub(crate) fn text(&self, window: Window, name: &str) -> Option<String> {
+        let mut state = self.shared.0.lock().unwrap();
+        let name = state.intern(name, false);
+        let (_, _, value) = state.properties.get(&(window, name))?;
+        String::from_utf8(value.clone()).ok()
+    }
+    pub(crate) fn calls(&self) -> Vec<Call> {
+        self.shared.0.lock().unwrap().calls.clone()
+    }
+    /// blocks until goto made a call matching `f`, the calls are kept for the next wait
+    pub(crate) fn wait_for(&self, f: impl Fn(&Call) -> bool) -> Call {
+        let (lock, changed) = &*self.shared;
+        let state = lock.lock().unwrap();
+        let (state, _) = changed
+            .wait_timeout_while(state, TIMEOUT, |state| !state.calls.iter().any(&f))
+            .unwrap();
+        match state.calls.iter().find(|call| f(call)) {
+            Some(call) => call.clone(),
+            None => panic!("timed out, the calls were {:?}", state.calls),
+        }
+    }
+    /// forgets the calls made so far
+    pub(crate) fn clear_calls(&self) {
+        self.shared.0.lock().unwrap().calls.clear();
+    }
+    pub(crate) fn keycode(&self, keysym: Keysym) -> Keycode {
+        let i = keysyms().iter().position(|k| *k == keysym.raw()).unwrap();
+        MIN_KEYCODE + i as Keycode
+    }
+    /// another client changed the property
+    pub(crate) fn notify_property(&self, window: Window, name: &str) {
+        let event = PropertyNotifyEvent {
+            response_type: PROPERTY_NOTIFY_EVENT,
+            window,
+            atom: self.atom(name),
+            state: Property::NEW_VALUE,
+            ..Default::default()
+        };
+        self.send(|sequence| <[u8; 32]>::from(PropertyNotifyEvent { sequence, ..event }).to_vec());
+    }
+    /// a key pressed with a modifier, as delivered to the passive grabs on the root window
+    pub(crate) fn key_press(&self, keysym: Keysym, state: KeyButMask) {
+        let event = KeyPressEvent {
+            response_type: KEY_PRESS_EVENT,
+            detail: self.keycode(keysym),
+            root: ROOT,
+            event: ROOT,
+            state,
+            same_screen: true,
+            ..Default::default()
+        };
+        self.send(|sequence| <[u8; 32]>::from(KeyPressEvent { sequence, ..event }).to_vec());
+    }
+    /// the raw release goto selects on the root window to notice that the modifier is let go
+    pub(crate) fn key_release(&self, keysym: Keysym) {
+        let event = xinput::KeyPressEvent {
+            response_type: GE_GENERIC_EVENT,
+            extension: XINPUT_OPCODE,
+            length: 12,
+            event_type: xinput::KEY_RELEASE_EVENT,
+            deviceid: 3,
+            detail: self.keycode(keysym).into(),
+            root: ROOT,
+            event: ROOT,
+            sourceid: 3,
+            ..Default::default()
+        };
+        self.send(|sequence| {
+            xinput::KeyPressEvent {
+                sequence,
+                ..event.clone()
+            }
+            .serialize()
+        });
+    }
+    fn send(&self, event: impl FnOnce(u16) -> Vec<u8>) {
+        let state = self.shared.0.lock().unwrap();
+        let event = event(state.sequence);
+        self.writer.lock().unwrap().write_all(&event).unwrap();
+    }
+}
+/// runs goto on the connection with the default config
+pub(crate) fn spawn(mode: Mode, conn: Conn) -> JoinHandle<Result<ExitCode, String>> {
+    spawn_with(mode, conn, "")
+}
+/// runs goto with the lines appended to the default config
+pub(crate) fn spawn_with(
+    mode: Mode,
+    conn: Conn,
+    lines: &str,
+) -> JoinHandle<Result<ExitCode, String>> {
+    static COUNT: AtomicUsize = AtomicUsize::new(0);
+    let count = COUNT.fetch_add(1, Ordering::Relaxed);
+    let mut config = config_file();
+    // every run starts from an empty state file, next to the config and away from the user's
+    let state = config.with_file_name(format!("state-{count}"));
+    if !lines.is_empty() {
+        let contents = read_to_string(&config).unwrap() + lines;
+        config.set_file_name(format!("config-{count}"));
+        std::fs::write(&config, contents).unwrap();
+    }
+    let args = Args {
+        events: false,
+        mode,
+        focus: None,
+        or_exec: None,
+        cycle: false,
+        set: None,
+        alias: None,
+        suspend: None,
+        session: None,
+        bench: false,
+        displays: vec![],
+        config: Some(config),
+        state: Some(state),
+    };
+    std::thread::spawn(move || run_connection(&args, &conn, 0).map_err(|e| e.to_string()))
+}
+/// a config with nothing but a font, shared by the tests
+fn config_file() -> PathBuf {
+    static PATH: OnceLock<PathBuf> = OnceLock::new();
+    PATH.get_or_init(|| {
+        let font = FONTS
+            .iter()
+            .find(|font| Path::new(font).exists())
+            .expect("the tests need one of the fonts in `FONTS`");
+        let dir = std::env::temp_dir().join(format!("{APP_NAME}-mock-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("config");
+        std::fs::write(&path, format!("font_1: {font}\n")).unwrap();
+        path
+    })
+    .clone()
+}
+impl Drop for MockServer {
+    /// hangs up, the event loop ends with an error
+    fn drop(&mut self) {
+        // the lock is poisoned when a test panicked while sending
+        let writer = self.writer.lock().unwrap_or_else(|e| e.into_inner());
+        let _ = writer.shutdown(std::net::Shutdown::Both);
+    }
+}
+impl State {
+    fn intern(&mut self, name: &str, only_if_exists: bool) -> Atom {
+        match self.atoms.iter().position(|atom| atom == name) {
+            Some(atom) => atom as Atom,
+            None if only_if_exists => x11rb::NONE,
+            None => {
+                self.atoms.push(name.to_string());
+                self.atoms.len() as Atom - 1
+            }
+        }
+    }
+    /// the reply to the request if it has one, and the events it causes
+    fn handle(&mut self, req: &[u8]) -> (Option<Vec<u8>>, Vec<[u8; 32]>) {
+        let u16_at = |i: usize| u16::from_ne_bytes([req[i], req[i + 1]]);
+        let u32_at = |i: usize| u32::from_ne_bytes(req[i..i + 4].try_into().unwrap());
+        let mut events = vec![];
+        let reply = match req[0] {
+            CREATE_WINDOW_REQUEST => {
+                self.calls.push(Call::CreateWindow(u32_at(4)));
+                None
+            }
+            GET_WINDOW_ATTRIBUTES_REQUEST => Some(
+                GetWindowAttributesReply {
+                    visual: ROOT_VISUAL,
+                    class: WindowClass::INPUT_OUTPUT,
+                    map_state: MapState::VIEWABLE,
+                    ..Default::default()
+                }
+                .serialize()
+                .to_vec(),
+            ),
+            MAP_WINDOW_REQUEST => {
+                self.calls.push(Call::MapWindow(u32_at(4)));
+                None
+            }
+            UNMAP_WINDOW_REQUEST => {
+                self.calls.push(Call::UnmapWindow(u32_at(4)));
+                None
+            }
+            GET_GEOMETRY_REQUEST => Some(
+                GetGeometryReply {
+                    depth: 24,
+                    root: ROOT,
+                    width: 200,
+                    height: 100,
+                    ..Default::default()
+                }
+                .serialize()
+                .to_vec(),
+            ),
+            QUERY_TREE_REQUEST => {
+                let window = u32_at(4);
+                let parent = if window == ROOT { x11rb::NONE } else { ROOT };
+                let reply = QueryTreeReply {
+                    root: ROOT,
+                    parent,
+                    ..Default::default()
+                };
+                Some(reply.serialize())
+            }
+            INTERN_ATOM_REQUEST => {
+                let name = String::from_utf8_lossy(&req[8..8 + u16_at(4) as usize]);
+                let atom = self.intern(&name, req[1] != 0);
+                Some(
+                    InternAtomReply {
+                        atom,
+                        ..Default::default()
+                    }
+                    .serialize()
+                    .to_vec(),
+                )
+            }
+            GET_ATOM_NAME_REQUEST => {
+                let name = self
+                    .atoms
+                    .get(u32_at(4) as usize)
+                    .cloned()
+                    .unwrap_or_default();
+                let reply = GetAtomNameReply {
+                    name: name.into_bytes(),
+                    ..Default::default()
+                };
+                Some(reply.serialize())
+            }
+            CHANGE_PROPERTY_REQUEST => {
+                let (window, property, type_, format) = (u32_at(4), u32_at(8), u32_at(12), req[16]);
+                let len = u32_at(20) as usize * format as usize / 8;
+                let data = req[24..24 + len].to_vec();
+                self.calls.push(Call::ChangeProperty(window, property));
+                let entry = self.properties.entry((window, property));
+                let value = &mut entry.or_insert((type_, format, vec![])).2;
+                match PropMode::from(req[1]) {
+                    PropMode::PREPEND => drop(value.splice(0..0, data)),
+                    PropMode::APPEND => value.extend(data),
+                    _ => *value = data,
+                }
+                None
+            }
+            DELETE_PROPERTY_REQUEST => {
+                self.calls.push(Call::DeleteProperty(u32_at(4), u32_at(8)));
+                self.properties.remove(&(u32_at(4), u32_at(8)));
+                None
+            }
+            GET_PROPERTY_REQUEST => {
+                let (window, property, type_) = (u32_at(4), u32_at(8), u32_at(12));
+                let (offset, length) = (u32_at(16) as usize * 4, u32_at(20) as usize * 4);
+                let reply = match self.properties.get(&(window, property)) {
+                    None => GetPropertyReply::default(),
+                    // a mismatching type only reports the actual one
+                    Some((actual, format, value)) if type_ != 0 && type_ != *actual => {
+                        GetPropertyReply {
+                            format: *format,
+                            type_: *actual,
+                            bytes_after: value.len() as u32,
+                            ..Default::default()
+                        }
+                    }
+                    Some((actual, format, value)) => {
+                        let start = offset.min(value.len());
+                        let end = (start + length).min(value.len());
+                        GetPropertyReply {
+                            format: *format,
+                            type_: *actual,
+                            bytes_after: (value.len() - end) as u32,
+                            value_len: ((end - start) * 8 / *format as usize) as u32,
+                            value: value[start..end].to_vec(),
+                            ..Default::default()
+                        }
+                    }
+                };
+                Some(reply.serialize())
+            }
+            GET_SELECTION_OWNER_REQUEST => {
+                Some(GetSelectionOwnerReply::default().serialize().to_vec())
+            }
+            SEND_EVENT_REQUEST => {
+                let event = &req[12..44];
+                if event[0] & 0x7f == CLIENT_MESSAGE_EVENT {
+                    let at = |i: usize| u32::from_ne_bytes(event[i..i + 4].try_into().unwrap());
+                    let (window, type_) = (at(4), at(8));
+                    let data = [at(12), at(16), at(20), at(24), at(28)];
+                    self.calls.push(Call::ClientMessage(window, type_, data));
+                    // the wm obliges right away
+                    let active = self.intern("_NET_ACTIVE_WINDOW", false);
+                    if type_ == active {
+                        let value = window.to_ne_bytes().to_vec();
+                        self.properties
+                            .insert((ROOT, active), (AtomEnum::WINDOW.into(), 32, value));
+                        self.focus = window;
+                        let notify = PropertyNotifyEvent {
+                            response_type: PROPERTY_NOTIFY_EVENT,
+                            window: ROOT,
+                            atom: active,
+                            state: Property::NEW_VALUE,
+                            ..Default::default()
+                        };
+                        events.push(notify.into());
+                    }
+                }
+                None
+            }
+            GRAB_KEYBOARD_REQUEST => {
+                self.calls.push(Call::GrabKeyboard(u32_at(4)));
+                let reply = GrabKeyboardReply {
+                    status: GrabStatus::SUCCESS,
+                    ..Default::default()
+                };
+                Some(reply.serialize().to_vec())
+            }
+            UNGRAB_KEYBOARD_REQUEST => {
+                self.calls.push(Call::UngrabKeyboard);
+                None
+            }
+            GRAB_KEY_REQUEST => {
+                self.calls.push(Call::GrabKey(req[10]));
+                None
+            }
+            UNGRAB_KEY_REQUEST => {
+                self.calls.push(Call::UngrabKey(req[1]));
+                None
+            }
+            QUERY_POINTER_REQUEST => {
+                let reply = QueryPointerReply {
+                    same_screen: true,
+                    root: ROOT,
+                    ..Default::default()
+                };
+                Some(reply.serialize().to_vec())
+            }
+            TRANSLATE_COORDINATES_REQUEST => {
+                let reply = TranslateCoordinatesReply {
+                    same_screen: true,
+                    dst_x: u16_at(12) as i16,
+                    dst_y: u16_at(14) as i16,
+                    ..Default::default()
+                };
+                Some(reply.serialize().to_vec())
+            }
+            SET_INPUT_FOCUS_REQUEST => {
+                self.focus = u32_at(4);
+                self.calls.push(Call::SetInputFocus(self.focus));
+                None
+            }
+            GET_INPUT_FOCUS_REQUEST => {
+                let reply = GetInputFocusReply {
+                    focus: self.focus,
+                    ..Default::default()
+                };
+                Some(reply.serialize().to_vec())
+            }
+            GET_IMAGE_REQUEST => {
+                self.calls.push(Call::GetImage(u32_at(4)));
+                // a gray window, as big as the geometry above
+                let (width, height) = (u16_at(12) as usize, u16_at(14) as usize);
+                let reply = GetImageReply {
+                    depth: 24,
+                    data: vec![128; width * height * 4],
+                    ..Default::default()
+                };
+                Some(reply.serialize())
+            }
+            PUT_IMAGE_REQUEST => {
+                self.calls.push(Call::PutImage(u32_at(4)));
+                None
+            }
+            QUERY_EXTENSION_REQUEST => {
+                let name = &req[8..8 + u16_at(4) as usize];
+                // only the raw key releases are needed
+                let reply = match name {
+                    b"XInputExtension" => QueryExtensionReply {
+                        present: true,
+                        major_opcode: XINPUT_OPCODE,
+                        first_event: 66,
+                        first_error: 129,
+                        ..Default::default()
+                    },
+                    _ => QueryExtensionReply::default(),
+                };
+                Some(reply.serialize().to_vec())
+            }
+            GET_KEYBOARD_MAPPING_REQUEST => {
+                let (first, count) = (req[4] as usize, req[5] as usize);
+                let keysyms = keysyms();
+                let reply = GetKeyboardMappingReply {
+                    keysyms_per_keycode: 1,
+                    keysyms: (first..first + count)
+                        .map(|keycode| keycode - MIN_KEYCODE as usize)
+                        .map(|i| keysyms.get(i).copied().unwrap_or(0))
+                        .collect(),
+                    ..Default::default()
+                };
+                Some(reply.serialize())
+            }
+            GET_MODIFIER_MAPPING_REQUEST => {
+                let code = |keysym: Keysym| {
+                    let i = keysyms().iter().position(|k| *k == keysym.raw()).unwrap();
+                    MIN_KEYCODE + i as Keycode
+                };
+                // shift, lock, control, then mod1 to mod5
+                let keycodes = vec![
+                    code(Keysym::Shift_L),
+                    0,
+                    code(Keysym::Control_L),
+                    code(Keysym::Alt_L),
+                    0,
+                    0,
+                    code(Keysym::Super_L),
+                    0,
+                ];
+                let reply = GetModifierMappingReply {
+                    keycodes,
+                    ..Default::default()
+                };
+                Some(reply.serialize())
+            }
+            // XISelectEvents
+            XINPUT_OPCODE if req[1] == 46 => None,
+            CHANGE_WINDOW_ATTRIBUTES_REQUEST
+            | DESTROY_WINDOW_REQUEST
+            | CONFIGURE_WINDOW_REQUEST
+            | OPEN_FONT_REQUEST
+            | CLOSE_FONT_REQUEST
+            | CREATE_PIXMAP_REQUEST
+            | FREE_PIXMAP_REQUEST
+            | CREATE_GC_REQUEST
+            | CHANGE_GC_REQUEST
+            | FREE_GC_REQUEST
+            | CREATE_COLORMAP_REQUEST
+            | FREE_COLORMAP_REQUEST
+            | CREATE_CURSOR_REQUEST
+            | CREATE_GLYPH_CURSOR_REQUEST
+            | FREE_CURSOR_REQUEST
+            | WARP_POINTER_REQUEST
+            | KILL_CLIENT_REQUEST
+            | NO_OPERATION_REQUEST => None,
+            opcode => panic!("the mock server doesn't handle the request {opcode}"),
+        };
+        let reply = reply.map(|mut reply| {
+            // the length and the sequence are filled in here, the lists padded
+            reply.resize(reply.len().next_multiple_of(4).max(32), 0);
+            let length = (reply.len() as u32 - 32) / 4;
+            reply[2..4].copy_from_slice(&self.sequence.to_ne_bytes());
+            reply[4..8].copy_from_slice(&length.to_ne_bytes());
+            reply
+        });
+        let events = events
+            .into_iter()
+            .map(|mut event: [u8; 32]| {
+                event[2..4].copy_from_slice(&self.sequence.to_ne_bytes());
+                event
+            })
+            .collect();
+        (reply, events)
+    }
+}
+/// one keysym per keycode from `MIN_KEYCODE`, the printable ascii and the usual bindings
+fn keysyms() -> Vec<u32> {
+    let mut keysyms: Vec<_> = (0x20..0x7f).collect();
+    keysyms.extend(
+        [
+            Keysym::Tab,
+            Keysym::ISO_Left_Tab,
+            Keysym::Return,
+            Keysym::Escape,
+            Keysym::BackSpace,
+            Keysym::Delete,
+            Keysym::space,
+            Keysym::Up,
+            Keysym::Down,
+            Keysym::Left,
+            Keysym::Right,
+            Keysym::Shift_L,
+            Keysym::Control_L,
+            Keysym::Alt_L,
+            Keysym::Super_L,
+        ]
+        .map(|keysym| keysym.raw()),
+    );
+    keysyms.extend((0..12).map(|i| Keysym::F1.raw() + i));
+    keysyms
+}
+/// answers the requests until goto hangs up
+fn serve(mut stream: UnixStream, shared: &Shared, writer: &Mutex<UnixStream>) {
+    let _ = (|| -> std::io::Result<()> {
+        // the setup request, without authentication
+        let mut head = [0; 12];
+        stream.read_exact(&mut head)?;
+        let name_len = u16::from_ne_bytes([head[6], head[7]]) as usize;
+        let data_len = u16::from_ne_bytes([head[8], head[9]]) as usize;
+        let mut auth = vec![0; name_len.next_multiple_of(4) + data_len.next_multiple_of(4)];
+        stream.read_exact(&mut auth)?;
+        writer.lock().unwrap().write_all(&setup())?;
+        loop {
+            let mut req = vec![0; 4];
+            stream.read_exact(&mut req)?;
+            let len = u16::from_ne_bytes([req[2], req[3]]) as usize * 4;
+            req.resize(len.max(4), 0);
+            stream.read_exact(&mut req[4..])?;
+
+            let (lock, changed) = shared;
+            let mut state = lock.lock().unwrap();
+            state.sequence = state.sequence.wrapping_add(1);
+            let (reply, events) = state.handle(&req);
+            let mut writer = writer.lock().unwrap();
+            if let Some(reply) = reply {
+                writer.write_all(&reply)?;
+            }
+            for event in events {
+                writer.write_all(&event)?;
+            }
+            changed.notify_all();
+        }
+    })();
+}
+fn setup() -> Vec<u8> {
+    let visual = Visualtype {
+        visual_id: ROOT_VISUAL,
+        class: VisualClass::TRUE_COLOR,
+        bits_per_rgb_value: 8,
+        colormap_entries: 256,
+        red_mask: 0xff0000,
+        green_mask: 0xff00,
+        blue_mask: 0xff,
+    };
+    let format = |depth| Format {
+        depth,
+        bits_per_pixel: 32,
+        scanline_pad: 32,
+    };
+    let screen = Screen {
+        root: ROOT,
+        default_colormap: 0x20,
+        white_pixel: 0xffffff,
+        black_pixel: 0,
+        width_in_pixels: SCREEN_SIZE.0,
+        height_in_pixels: SCREEN_SIZE.1,
+        // 96 dpi
+        width_in_millimeters: 212,
+        height_in_millimeters: 159,
+        min_installed_maps: 1,
+        max_installed_maps: 1,
+        root_visual: ROOT_VISUAL,
+        root_depth: 24,
+        allowed_depths: vec![Depth {
+            depth: 24,
+            visuals: vec![visual],
+        }],
+        ..Default::default()
+    };
+    let setup = Setup {
+        status: 1,
+        protocol_major_version: 11,
+        resource_id_base: 0x0200_0000,
+        resource_id_mask: 0x001f_ffff,
+        maximum_request_length: u16::MAX,
+        image_byte_order: ImageOrder::LSB_FIRST,
+        bitmap_format_bit_order: ImageOrder::LSB_FIRST,
+        bitmap_format_scanline_unit: 32,
+        bitmap_format_scanline_pad: 32,
+        min_keycode: MIN_KEYCODE,
+        max_keycode: 255,
+        vendor: b"goto".to_vec(),
+        pixmap_formats: vec![format(24), format(32)],
+        roots: vec![screen],
+        ..Default::default()
+    };
+    let mut bytes = setup.serialize();
+    let length = (bytes.len() as u16 - 8) / 4;
+    bytes[6..8].copy_from_slice(&length.to_ne_bytes());
+    bytes
+}
+
+pub(crate) const DPI: f32 = 72.0;
+
+pub(crate) fn parse(contents: &str, dpi: f32) -> (Config, Vec<String>) {
+    let mut conf = Config::defaults(dpi);
+    let warnings = conf.parse(contents, dpi);
+    (conf, warnings)
+}
+
+pub(crate) const WINDOWS: [Window; 2] = [0x0040_0001, 0x0040_0002];
+
+/// a fake server listing two terminals, the first one active
+pub(crate) fn desktop() -> (MockServer, Conn) {
+    let (server, conn) = MockServer::start().unwrap();
+    server.set_property(ROOT, "_NET_CLIENT_LIST", "WINDOW", &WINDOWS);
+    server.set_property(ROOT, "_NET_ACTIVE_WINDOW", "WINDOW", &WINDOWS[..1]);
+    for (wid, title) in WINDOWS.iter().zip(["first", "second"]) {
+        server.set_text(*wid, "_NET_WM_NAME", "UTF8_STRING", title);
+        server.set_text(*wid, "WM_CLASS", "STRING", "term\0Term\0");
+    }
+    (server, conn)
+}
@@ -0,0 +1,2137 @@
+use std::collections::HashMap;
+use std::time::Duration;
+
+use fontdue::Font;
+use fontdue::FontSettings;
+use fontdue::Metrics;
+use fontdue::layout::CoordinateSystem;
+use fontdue::layout::GlyphPosition;
+use fontdue::layout::HorizontalAlign;
+use fontdue::layout::Layout;
+use fontdue::layout::LayoutSettings;
+use fontdue::layout::TextStyle;
+use fontdue::layout::VerticalAlign;
+use fontdue::layout::WrapStyle;
+use x11rb::protocol::xproto::ConfigureWindowAux;
+use x11rb::protocol::xproto::ConnectionExt as _;
+use x11rb::protocol::xproto::Gcontext;
+use x11rb::protocol::xproto::Screen;
+use x11rb::protocol::xproto::StackMode;
+use x11rb::protocol::xproto::Visualid;
+use x11rb::protocol::xproto::Window;
+
+use crate::Atoms;
+use crate::Conn;
+use crate::Result;
+use crate::config::Anchor;
+use crate::config::ColorDepth;
+use crate::config::Config;
+use crate::config::Edge;
+use crate::config::EnterAnimation;
+use crate::config::LayoutDirection;
+use crate::config::ListLayout;
+use crate::config::MarkerBg;
+use crate::config::MarkerPosition;
+use crate::config::Overflow;
+use crate::config::SelectionStyle;
+use crate::config::ShowCurrent;
+use crate::config::Size;
+use crate::config::Stacking;
+use crate::config::TaskStyle;
+use crate::config::Thumbnails;
+use crate::config::WindowSize;
+use crate::icons::IconCache;
+use crate::tasks::Row;
+use crate::tasks::STICKY;
+use crate::tasks::Task;
+use crate::tasks::TaskList;
+use crate::x11::create_graphic_context;
+use crate::x11::create_window;
+use crate::x11::request_window_move;
+use crate::x11::send_frame;
+
+// x11 rejects windows without area, smaller lists aren't shown at all
+pub const MIN_WINDOW_SIZE: f32 = 1.0;
+pub const TOOLTIP_DELAY: Duration = Duration::from_millis(800);
+// the proportions of the cell of `thumbnails: beside`, the ones of most screens
+pub const THUMBNAIL_ASPECT: f32 = 16.0 / 9.0;
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Area {
+    pub x: f32,
+    pub y: f32,
+    pub w: f32,
+    pub h: f32,
+}
+impl Area {
+    pub fn new(x: f32, y: f32, w: f32, h: f32) -> Self {
+        Self { x, y, w, h }
+    }
+    /// the whole screen, where the list goes without randr
+    pub fn of_screen(screen: &Screen) -> Self {
+        let (w, h) = (screen.width_in_pixels, screen.height_in_pixels);
+        Self::new(0.0, 0.0, w as f32, h as f32)
+    }
+    /// never yields a negative size, an area shrunk past its center is empty
+    pub fn shrink(mut self, amount: f32) -> Self {
+        self.x += amount;
+        self.y += amount;
+        self.w = (self.w - amount * 2.0).max(0.0);
+        self.h = (self.h - amount * 2.0).max(0.0);
+        self
+    }
+    /// the part shared with `other`, none when they don't overlap
+    pub fn intersection(self, other: Area) -> Option<Self> {
+        let (x, y) = (self.x.max(other.x), self.y.max(other.y));
+        let w = (self.x + self.w).min(other.x + other.w) - x;
+        let h = (self.y + self.h).min(other.y + other.h) - y;
+        (w > 0.0 && h > 0.0).then_some(Self::new(x, y, w, h))
+    }
+    /// reflected across the vertical center line of `outer`
+    pub fn mirrored(self, outer: Area) -> Self {
+        Self {
+            x: outer.x * 2.0 + outer.w - self.x - self.w,
+            ..self
+        }
+    }
+}
+#[derive(Debug, Clone, Copy)]
+pub struct Color {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+    pub a: u8,
+}
+impl Color {
+    pub fn new(r: u8, g: u8, b: u8, a: u8) -> Self {
+        Self { r, g, b, a }
+    }
+    pub fn multiply(&self, factor: f32) -> Self {
+        Self {
+            r: (self.r as f32 * factor) as u8,
+            g: (self.g as f32 * factor) as u8,
+            b: (self.b as f32 * factor) as u8,
+            a: (self.a as f32 * factor) as u8,
+        }
+    }
+    pub fn _from_rgba(color: u32) -> Self {
+        Self {
+            r: ((color >> 0) & 0xFF) as u8,
+            g: ((color >> 8) & 0xFF) as u8,
+            b: ((color >> 16) & 0xFF) as u8,
+            a: ((color >> 24) & 0xFF) as u8,
+        }
+    }
+    /// `self` with `coverage` drawn over a bgra pixel
+    pub fn blend_over(self, dst: u32, coverage: f32) -> u32 {
+        let alpha = self.a as f32 / 255.0 * coverage;
+        let [b, g, r, a] = dst.to_ne_bytes();
+        let mix = |src: u8, dst: u8| (src as f32 * alpha + dst as f32 * (1.0 - alpha)) as u8;
+        u32::from_ne_bytes([mix(self.b, b), mix(self.g, g), mix(self.r, r), mix(255, a)])
+    }
+    pub fn to_bgra(self) -> u32 {
+        u32::from_ne_bytes([self.b, self.g, self.r, self.a])
+    }
+    pub fn _to_argb(self) -> u32 {
+        u32::from_ne_bytes([self.a, self.r, self.g, self.b])
+    }
+    pub fn _to_rgba(self) -> u32 {
+        u32::from_ne_bytes([self.r, self.g, self.b, self.a])
+    }
+}
+#[derive(Clone)]
+pub struct Frame {
+    pub buf: Vec<u8>,
+    pub width: u32,
+    pub height: u32,
+}
+impl Frame {
+    pub const CHANNELS: u32 = 4;
+
+    pub fn len(width: u32, height: u32) -> usize {
+        width as usize * height as usize * Self::CHANNELS as usize
+    }
+    pub fn new(width: u32, height: u32) -> Self {
+        Self {
+            buf: vec![0; Self::len(width, height)],
+            width,
+            height,
+        }
+    }
+    pub fn from_rgba_u8(buf: &[u8], width: u32, height: u32) -> Self {
+        let mut frame = Self::new(width, height);
+        let frame_buf = frame.buf_u32_mut();
+        for (px, rgba) in frame_buf.iter_mut().zip(buf.chunks_exact(4)) {
+            *px = u32::from_ne_bytes([rgba[2], rgba[1], rgba[0], rgba[3]]);
+        }
+        frame
+    }
+    pub fn from_argb_u32(buf: &[u32], width: u32, height: u32) -> Self {
+        let mut frame = Self::new(width, height);
+        let len = (width as usize * height as usize).min(buf.len());
+        for (i, argb) in buf[..len].iter().enumerate() {
+            frame.buf[i * 4 + 0] = ((*argb >> 0) & 0xFF) as u8;
+            frame.buf[i * 4 + 1] = ((*argb >> 8) & 0xFF) as u8;
+            frame.buf[i * 4 + 2] = ((*argb >> 16) & 0xFF) as u8;
+            frame.buf[i * 4 + 3] = ((*argb >> 24) & 0xFF) as u8;
+        }
+        frame
+    }
+    pub fn resize(&mut self, width: u32, height: u32) {
+        self.buf.resize(Self::len(width, height), 0);
+        self.width = width;
+        self.height = height;
+    }
+    pub fn scale_nn(&self, factor: f32) -> Self {
+        if self.buf.is_empty() || !(factor.is_finite() && factor > 0.0) {
+            return Self::new(0, 0);
+        }
+        let (src_width, src_height) = (self.width as usize, self.height as usize);
+        let src_buf = self.buf_u32();
+
+        let dst_width = (src_width as f32 * factor).round().max(1.0) as usize;
+        let dst_height = (src_height as f32 * factor).round().max(1.0) as usize;
+
+        let mut dst = Self::new(dst_width as u32, dst_height as u32);
+        let dst_buf = dst.buf_u32_mut();
+
+        for y in 0..dst_height {
+            let src_y = (((y as f32) / factor).floor() as usize).min(src_height - 1);
+            for x in 0..dst_width {
+                let src_x = (((x as f32) / factor).floor() as usize).min(src_width - 1);
+                dst_buf[y * dst_width + x] = src_buf[src_y * src_width + src_x];
+            }
+        }
+        dst
+    }
+    pub fn scale_bilinear(&self, factor: f32) -> Self {
+        if self.buf.is_empty() || !(factor.is_finite() && factor > 0.0) {
+            return Self::new(0, 0);
+        }
+
+        let (src_width, src_height) = (self.width as usize, self.height as usize);
+        let src_buf = self.buf_u32();
+
+        let dst_width = (src_width as f32 * factor).round().max(1.0) as usize;
+        let dst_height = (src_height as f32 * factor).round().max(1.0) as usize;
+
+        let mut dst = Self::new(dst_width as u32, dst_height as u32);
+        let dst_buf = dst.buf_u32_mut();
+
+        let mut x_map = Vec::with_capacity(dst_width);
+        let mut y_map = Vec::with_capacity(dst_height);
+
+        for x in 0..dst_width {
+            let src_x = (x as f32) * ((src_width - 1) as f32) / ((dst_width - 1).max(1) as f32);
+            let x0 = src_x.floor() as usize;
+            let x1 = (x0 + 1).min(src_width - 1);
+            let dx = src_x - x0 as f32;
+            x_map.push((x0, x1, dx));
+        }
+
+        for y in 0..dst_height {
+            let src_y = (y as f32) * ((src_height - 1) as f32) / ((dst_height - 1).max(1) as f32);
+            let y0 = src_y.floor() as usize;
+            let y1 = (y0 + 1).min(src_height - 1);
+            let dy = src_y - y0 as f32;
+            y_map.push((y0, y1, dy));
+        }
+
+        for (y, &(y0, y1, dy)) in y_map.iter().enumerate() {
+            let row0 = &src_buf[y0 * src_width..(y0 + 1) * src_width];
+            let row1 = &src_buf[y1 * src_width..(y1 + 1) * src_width];
+
+            for (x, &(x0, x1, dx)) in x_map.iter().enumerate() {
+                let p00 = row0[x0];
+                let p10 = row0[x1];
+                let p01 = row1[x0];
+                let p11 = row1[x1];
+
+                let interp = |shift: u32| -> u32 {
+                    let c00 = ((p00 >> shift) & 0xFF) as f32;
+                    let c10 = ((p10 >> shift) & 0xFF) as f32;
+                    let c01 = ((p01 >> shift) & 0xFF) as f32;
+                    let c11 = ((p11 >> shift) & 0xFF) as f32;
+
+                    let c0 = c00 * (1.0 - dx) + c10 * dx;
+                    let c1 = c01 * (1.0 - dx) + c11 * dx;
+                    ((c0 * (1.0 - dy) + c1 * dy).round() as u32) & 0xFF
+                };
+
+                let b = interp(0);
+                let g = interp(8);
+                let r = interp(16);
+                let a = interp(24);
+
+                dst_buf[y * dst_width + x] = (a << 24) | (r << 16) | (g << 8) | b;
+            }
+        }
+        dst
+    }
+    /// box filter, every destination pixel averages the source pixels it covers
+    pub fn scale_area(&self, factor: f32) -> Self {
+        if self.buf.is_empty() || !(factor.is_finite() && factor > 0.0) {
+            return Self::new(0, 0);
+        }
+
+        let (src_width, src_height) = (self.width as usize, self.height as usize);
+        let src_buf = self.buf_u32();
+
+        let dst_width = (src_width as f32 * factor).round().max(1.0) as usize;
+        let dst_height = (src_height as f32 * factor).round().max(1.0) as usize;
+
+        let mut dst = Self::new(dst_width as u32, dst_height as u32);
+        let dst_buf = dst.buf_u32_mut();
+
+        // the source pixels covered by each destination pixel, with their coverage
+        let spans = |src: usize, dst: usize| -> Vec<Vec<(usize, f32)>> {
+            let step = src as f32 / dst as f32;
+            (0..dst)
+                .map(|i| {
+                    let (start, end) = (i as f32 * step, (i + 1) as f32 * step);
+                    let first = start.floor() as usize;
+                    let last = (end.ceil() as usize).min(src).max(first + 1);
+                    (first..last.min(src))
+                        .map(|j| {
+                            let cover = end.min(j as f32 + 1.0) - start.max(j as f32);
+                            (j, cover.max(0.0))
+                        })
+                        .collect()
+                })
+                .collect()
+        };
+        let x_spans = spans(src_width, dst_width);
+        let y_spans = spans(src_height, dst_height);
+
+        for (y, y_span) in y_spans.iter().enumerate() {
+            for (x, x_span) in x_spans.iter().enumerate() {
+                // the colors are weighted by alpha, so transparent pixels don't bleed
+                let (mut weight, mut a, mut r, mut g, mut b) = (0.0, 0.0, 0.0, 0.0, 0.0);
+                for &(sy, wy) in y_span {
+                    let row = &src_buf[sy * src_width..(sy + 1) * src_width];
+                    for &(sx, wx) in x_span {
+                        let px = row[sx];
+                        let w = wx * wy;
+                        let pa = ((px >> 24) & 0xFF) as f32 * w;
+                        weight += w;
+                        a += pa;
+                        r += ((px >> 16) & 0xFF) as f32 * pa;
+                        g += ((px >> 8) & 0xFF) as f32 * pa;
+                        b += (px & 0xFF) as f32 * pa;
+                    }
+                }
+                if a <= 0.0 || weight <= 0.0 {
+                    continue;
+                }
+                let channel = |c: f32| -> u32 { ((c / a).round() as u32).min(0xFF) };
+                let alpha = ((a / weight).round() as u32).min(0xFF);
+                dst_buf[y * dst_width + x] =
+                    (alpha << 24) | (channel(r) << 16) | (channel(g) << 8) | channel(b);
+            }
+        }
+        dst
+    }
+    /// area averaging when shrinking, bilinear when growing
+    pub fn scale_smooth(&self, factor: f32) -> Self {
+        if factor < 1.0 {
+            self.scale_area(factor)
+        } else {
+            self.scale_bilinear(factor)
+        }
+    }
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+    pub fn buf_u8(&self) -> &[u8] {
+        &self.buf
+    }
+    pub fn buf_u32(&self) -> &[u32] {
+        if self.width == 0 || self.height == 0 {
+            return &[];
+        }
+        unsafe {
+            std::slice::from_raw_parts(
+                self.buf.as_ptr() as *const u32,
+                (self.width * self.height) as usize,
+            )
+        }
+    }
+    pub fn _buf_u8_mut(&mut self) -> &mut [u8] {
+        &mut self.buf
+    }
+    pub fn buf_u32_mut(&mut self) -> &mut [u32] {
+        if self.width == 0 || self.height == 0 {
+            return &mut [];
+        }
+        unsafe {
+            std::slice::from_raw_parts_mut(
+                self.buf.as_mut_ptr() as *mut u32,
+                (self.width * self.height) as usize,
+            )
+        }
+    }
+    pub fn blit_frame(&mut self, frame: &Frame, x: i32, y: i32) {
+        let dst_width = self.width as usize;
+        let dst_height = self.height as usize;
+        let src_width = frame.width as usize;
+        let src_height = frame.height as usize;
+
+        let src = frame.buf_u32();
+        let dst = self.buf_u32_mut();
+
+        for sy in 0..src_height {
+            let dy = y as i64 + sy as i64;
+            if dy < 0 || dy >= dst_height as i64 {
+                continue;
+            }
+
+            let dst_row_start = dy as usize * dst_width;
+            let src_row_start = sy * src_width;
+
+            for sx in 0..src_width {
+                let dx = x as i64 + sx as i64;
+                if dx < 0 || dx >= dst_width as i64 {
+                    continue;
+                }
+
+                let dst_idx = dst_row_start + dx as usize;
+                let src_idx = src_row_start + sx;
+
+                dst[dst_idx] = src[src_idx];
+            }
+        }
+    }
+    pub fn draw_rect(&mut self, area: Area, color: &Color) {
+        let color = color.to_bgra();
+
+        // clipped to the frame, the casts saturate negative and nan values to 0
+        let (x, y) = (area.x.floor(), area.y.floor());
+        let x0 = x.max(0.0) as usize;
+        let y0 = y.max(0.0) as usize;
+        let x1 = ((x + area.w.ceil()).max(0.0) as usize).min(self.width as usize);
+        let y1 = ((y + area.h.ceil()).max(0.0) as usize).min(self.height as usize);
+        if x0 >= x1 || y0 >= y1 {
+            return;
+        }
+
+        let width = self.width as usize;
+        let buf = self.buf_u32_mut();
+
+        for row in y0..y1 {
+            buf[row * width + x0..row * width + x1].fill(color);
+        }
+    }
+    /// a disc inscribed in the area, antialiased along its edge
+    pub fn draw_disc(&mut self, area: Area, color: &Color) {
+        let r = area.w.min(area.h) / 2.0;
+        let (cx, cy) = (area.x + area.w / 2.0, area.y + area.h / 2.0);
+        let x0 = (cx - r).floor().max(0.0) as usize;
+        let y0 = (cy - r).floor().max(0.0) as usize;
+        let x1 = ((cx + r).ceil().max(0.0) as usize).min(self.width as usize);
+        let y1 = ((cy + r).ceil().max(0.0) as usize).min(self.height as usize);
+
+        let width = self.width as usize;
+        let buf = self.buf_u32_mut();
+
+        for row in y0..y1 {
+            for col in x0..x1 {
+                let (dx, dy) = (col as f32 + 0.5 - cx, row as f32 + 0.5 - cy);
+                let coverage = (r - (dx * dx + dy * dy).sqrt() + 0.5).clamp(0.0, 1.0);
+                if coverage > 0.0 {
+                    let px = &mut buf[row * width + col];
+                    *px = color.blend_over(*px, coverage);
+                }
+            }
+        }
+    }
+    pub fn draw_rect_outline(&mut self, area: Area, bw: f32, color: &Color) {
+        if bw <= 0.0 {
+            return;
+        }
+
+        let x = area.x;
+        let y = area.y;
+        let w = area.w;
+        let h = area.h;
+
+        let l = Area::new(x, y, bw, h);
+        let t = Area::new(x, y, w, bw);
+        let d = Area::new(x, y + h - bw, w, bw);
+        let r = Area::new(x + w - bw, y, bw, h);
+
+        self.draw_rect(l, color);
+        self.draw_rect(t, color);
+        self.draw_rect(r, color);
+        self.draw_rect(d, color);
+    }
+    pub fn draw_hline(&mut self, width: f32, y: f32, x1: f32, x2: f32, color: &Color) {
+        if width <= 0.0 {
+            return;
+        }
+        let area = Area::new(x1, y, x2 - x1, width);
+        self.draw_rect(area, color);
+    }
+    pub fn draw_vline(&mut self, width: f32, x: f32, y1: f32, y2: f32, color: &Color) {
+        if width <= 0.0 {
+            return;
+        }
+        let area = Area::new(x, y1, width, y2 - y1);
+        self.draw_rect(area, color);
+    }
+    /// quantizes the frame to the levels of `depth`, with a 4x4 bayer pattern when `dither`
+    pub fn reduce_colors(&mut self, depth: ColorDepth, dither: bool) {
+        const BAYER: [[u8; 4]; 4] = [[0, 8, 2, 10], [12, 4, 14, 6], [3, 11, 1, 9], [15, 7, 13, 5]];
+        let levels = match depth {
+            ColorDepth::Full => return,
+            ColorDepth::Grayscale => 16,
+            ColorDepth::Mono => 2,
+        };
+        let step = 255.0 / (levels - 1) as f32;
+        let width = self.width.max(1) as usize;
+        for (i, px) in self.buf.chunks_exact_mut(4).enumerate() {
+            let (b, g, r) = (px[0] as f32, px[1] as f32, px[2] as f32);
+            let luma = 0.2126 * r + 0.7152 * g + 0.0722 * b;
+            let offset = if dither {
+                (BAYER[(i / width) % 4][(i % width) % 4] as f32 + 0.5) / 16.0 - 0.5
+            } else {
+                0.0
+            };
+            let level = (luma / step + offset)
+                .round()
+                .clamp(0.0, (levels - 1) as f32);
+            let gray = (level * step).round() as u8;
+            px[0] = gray;
+            px[1] = gray;
+            px[2] = gray;
+        }
+    }
+}
+
+pub type RasterizedGlyph = (Metrics, Vec<u8>);
+pub struct TextRenderer {
+    pub ascii: [(Metrics, Vec<u8>); 256],
+    pub others: HashMap<char, RasterizedGlyph>,
+    pub fonts: Vec<Font>,
+    pub size: f32,
+    pub layout: Layout,
+    // the rendered task cells, the text is the costly part to draw
+    pub cells: CellCache,
+}
+/// identifies a rendered task cell by everything that affects its pixels
+#[derive(PartialEq, Eq, Hash)]
+pub struct CellKey {
+    pub title: String,
+    pub matched: Vec<usize>,
+    pub colors: [u32; 5],
+    pub effects: [u32; 5],
+    pub border_width: u32,
+    pub selection: u8,
+    pub size: (u32, u32),
+    pub offset: (u32, u32),
+}
+/// the cells drawn in the last frame, the others are dropped
+#[derive(Default)]
+pub struct CellCache {
+    pub current: HashMap<CellKey, (Frame, bool)>,
+    pub previous: HashMap<CellKey, (Frame, bool)>,
+}
+impl CellCache {
+    pub fn new() -> Self {
+        Self {
+            current: HashMap::new(),
+            previous: HashMap::new(),
+        }
+    }
+    pub fn take(&mut self, key: &CellKey) -> Option<(Frame, bool)> {
+        self.previous
+            .remove(key)
+            .or_else(|| self.current.remove(key))
+    }
+    pub fn put(&mut self, key: CellKey, cell: (Frame, bool)) {
+        self.current.insert(key, cell);
+    }
+    /// called once the frame is drawn
+    pub fn sweep(&mut self) {
+        self.previous = std::mem::take(&mut self.current);
+    }
+}
+impl TextRenderer {
+    pub fn new(conf: &Config) -> Result<Self> {
+        let font_paths: Vec<_> = vec![&conf.font_1, &conf.font_2, &conf.font_3]
+            .into_iter()
+            .flatten()
+            .collect();
+
+        let fonts = font_paths
+            .into_iter()
+            .map(|font_path| {
+                let font_bytes = std::fs::read(font_path)
+                    .map_err(|e| format!("failed to read the font `{font_path:?}`: {e}"))?;
+                Font::from_bytes(
+                    font_bytes,
+                    FontSettings {
+                        scale: conf.font_size,
+                        ..Default::default()
+                    },
+                )
+                .map_err(|e| format!("failed to load the font `{font_path:?}`: {e}").into())
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let mut ascii: [RasterizedGlyph; 256] = std::array::from_fn(|_| RasterizedGlyph::default());
+        let font = fonts.first().ok_or("no font is set")?;
+        for c in 0u8..=255 {
+            ascii[c as usize] = Self::rasterize(c as char, font, conf.font_size);
+        }
+
+        Ok(Self {
+            ascii,
+            others: HashMap::new(),
+            fonts,
+            size: conf.font_size,
+            layout: Layout::new(CoordinateSystem::PositiveYDown),
+            cells: CellCache::new(),
+        })
+    }
+    pub fn get(&self, c: char) -> &RasterizedGlyph {
+        self.ascii
+            .get(c as usize)
+            .or_else(|| self.others.get(&c))
+            .unwrap()
+    }
+    /// returns whether the text overflows the area
+    pub fn set_layout(&mut self, text: &str, conf: &Config, area: Area) -> bool {
+        self.set_layout_aligned(text, conf, area, conf.text_halign, conf.text_valign)
+    }
+    pub fn set_layout_aligned(
+        &mut self,
+        text: &str,
+        conf: &Config,
+        area: Area,
+        halign: HorizontalAlign,
+        valign: VerticalAlign,
+    ) -> bool {
+        for c in text.chars() {
+            self.cache(c);
+        }
+        let mut settings = LayoutSettings {
+            x: area.x,
+            y: area.y,
+            max_width: Some(area.w),
+            max_height: Some(area.h),
+            horizontal_align: halign,
+            vertical_align: valign,
+            wrap_style: WrapStyle::Word,
+            wrap_hard_breaks: true,
+            line_height: conf.line_height,
+        };
+        self.layout.reset(&settings);
+
+        // fixme:
+        // a rasterized glyph might not match its computed layout:
+        // - layouts are all computed with a single font (index 0)
+        // - the rasterized glyph is instead computed with the appropriate font
+        self.layout
+            .append(&self.fonts, &TextStyle::new(text, self.size, 0));
+
+        let overflows = self.layout.height() > area.h;
+        if overflows {
+            settings.vertical_align = VerticalAlign::Top;
+            self.layout.reset(&settings);
+            self.layout
+                .append(&self.fonts, &TextStyle::new(text, self.size, 0));
+        }
+        overflows
+    }
+
+    pub fn measure(&mut self, text: &str) -> (f32, f32) {
+        let mut w = 0.0;
+        for c in text.chars() {
+            self.cache(c);
+            w += self.get(c).0.advance_width;
+        }
+        let h = self.fonts[0]
+            .horizontal_line_metrics(self.size)
+            .map(|m| m.new_line_size)
+            .unwrap_or(self.size);
+        (w, h)
+    }
+    pub fn cache(&mut self, c: char) {
+        if c.is_ascii() {
+            return;
+        }
+        if self.others.contains_key(&c) {
+            return;
+        }
+        if let Some(font) = self.font_for_char(c) {
+            let (metrics, bitmap) = Self::rasterize(c, font, self.size);
+            if bitmap.is_empty() {
+                // likely an emoji that fontdue can't rasterize
+                self.others.insert(c, Default::default());
+                return;
+            }
+            self.others.insert(c, (metrics, bitmap));
+            return;
+        }
+        eprintln!("[WARNING] couldn't find a suitable font for `{c}`");
+        self.others.insert(c, Default::default());
+    }
+    pub fn font_for_char(&self, c: char) -> Option<&Font> {
+        self.fonts.iter().find(|font| font.has_glyph(c))
+    }
+    pub fn rasterize(c: char, font: &Font, size: f32) -> RasterizedGlyph {
+        let (metrics, bitmap) = font.rasterize(c, size);
+        (metrics, bitmap)
+    }
+}
+pub fn draw_list(
+    frame: &mut Frame,
+    conf: &Config,
+    tasks: &TaskList,
+    hovered: Option<Window>,
+    tr: &mut TextRenderer,
+    icons: &mut IconCache,
+) -> Option<Area> {
+    let overflowing = match tasks.layout {
+        ListLayout::Rows | ListLayout::Grid => {
+            draw_list_rows(frame, conf, tasks, hovered, tr, icons)
+        }
+        ListLayout::Columns => draw_list_cols(frame, conf, tasks, hovered, tr, icons),
+    };
+    if let Some((page, pages)) = tasks.page() {
+        let area = Area::new(0.0, 0.0, frame.width() as f32, frame.height() as f32);
+        let colors = (&conf.header_fg_color, &conf.header_bg_color);
+        let text = format!("{page}/{pages}");
+        let area = area.shrink(conf.border_width);
+        draw_badge(frame, conf, tr, &text, colors, &Anchor::BOTTOM_RIGHT, area);
+    }
+    tr.cells.sweep();
+    overflowing
+}
+/// the task whose cell contains the position, mirroring the layout of `draw_list`
+pub fn task_at(
+    conf: &Config,
+    tasks: &TaskList,
+    (w, h): (f32, f32),
+    (x, y): (f32, f32),
+) -> Option<Window> {
+    let (rows, _) = tasks.visible_rows();
+    let area = Area::new(0.0, 0.0, w, h).shrink(conf.border_width);
+    let x = match conf.layout_direction {
+        LayoutDirection::Ltr => x,
+        LayoutDirection::Rtl => w - x,
+    };
+    if rows.is_empty() || x < area.x || y < area.y || x >= area.x + area.w || y >= area.y + area.h {
+        return None;
+    }
+    let gap = conf.task_gap;
+    let idx = match tasks.layout {
+        ListLayout::Rows | ListLayout::Grid => {
+            let per_col = tasks.cells().div_ceil(tasks.columns);
+            let col_w = cell_size(area.w, tasks.columns, gap);
+            let col = cell_at(x - area.x, col_w, gap, tasks.columns)?;
+            let row = cell_at(y - area.y, cell_size(area.h, per_col, gap), gap, per_col)?;
+            col * per_col + row
+        }
+        ListLayout::Columns => {
+            let cells = tasks.cells();
+            cell_at(x - area.x, cell_size(area.w, cells, gap), gap, cells)?
+        }
+    };
+    match rows.get(idx) {
+        Some(Row::Task(task)) => Some(task.wid),
+        _ => None,
+    }
+}
+/// the cell at `offset` among `cells` cells of `cell` size, the gaps between them belong to none
+pub fn cell_at(offset: f32, cell: f32, gap: f32, cells: usize) -> Option<usize> {
+    let idx = (offset / (cell + gap)) as usize;
+    (idx < cells && offset - idx as f32 * (cell + gap) < cell).then_some(idx)
+}
+/// the size of each of `cells` cells spread over `size`, with `gap` between them
+pub fn cell_size(size: f32, cells: usize, gap: f32) -> f32 {
+    let gaps = gap * cells.saturating_sub(1) as f32;
+    ((size - gaps) / cells.max(1) as f32).max(0.0)
+}
+pub fn draw_list_rows(
+    frame: &mut Frame,
+    conf: &Config,
+    tasks: &TaskList,
+    hovered: Option<Window>,
+    tr: &mut TextRenderer,
+    icons: &mut IconCache,
+) -> Option<Area> {
+    let (rows, selected_idx) = tasks.visible_rows();
+    if rows.is_empty() {
+        return None;
+    }
+    let mut area = Area::new(0.0, 0.0, frame.width() as f32, frame.height() as f32);
+    frame.draw_rect(area, &conf.bg_color);
+    frame.draw_rect_outline(area, conf.border_width, &conf.border_color);
+    area = area.shrink(conf.border_width);
+    // the cells are laid out left to right, then mirrored
+    let place = |cell: Area| match conf.layout_direction {
+        LayoutDirection::Ltr => cell,
+        LayoutDirection::Rtl => cell.mirrored(area),
+    };
+
+    // the grid fills a column before the next one
+    let columns = tasks.columns;
+    let per_col = tasks.cells().div_ceil(columns);
+    let col_w = cell_size(area.w, columns, conf.task_gap);
+    let task_h = cell_size(area.h, per_col, conf.task_gap);
+
+    let icon_w = if has_icon_cell(conf) { task_h } else { 0.0 };
+    let thumb_w = if conf.thumbnails == Thumbnails::Beside {
+        task_h * THUMBNAIL_ASPECT
+    } else {
+        0.0
+    };
+
+    let marker_w = if conf.show_marker {
+        conf.marker_width.unwrap_or(task_h)
+    } else {
+        0.0
+    };
+    let task_w = col_w - icon_w - thumb_w - marker_w;
+    let style = conf.selected_task_style();
+    let mut overflowing = None;
+
+    if conf.show_marker && conf.marker_bg == MarkerBg::Gutter {
+        for col in 0..columns {
+            let marker_x = area.x + (col_w + conf.task_gap) * col as f32 + col_w - marker_w;
+            draw_marker_bg(
+                frame,
+                conf,
+                &conf.marker_bg_color,
+                place(Area::new(marker_x, area.y, marker_w, area.h)),
+            );
+        }
+    }
+
+    for (i, row) in rows.iter().enumerate() {
+        let x = area.x + (col_w + conf.task_gap) * (i / per_col) as f32;
+        let y = area.y + (task_h + conf.task_gap) * (i % per_col) as f32;
+        let (icon_x, thumb_x) = (x, x + icon_w);
+        let (task_x, marker_x) = (thumb_x + thumb_w, x + col_w - marker_w);
+        let is_selected = Some(i) == selected_idx;
+        let task = match row {
+            Row::Header(name) => {
+                draw_header(frame, conf, tr, name, place(Area::new(x, y, col_w, task_h)));
+                continue;
+            }
+            Row::Task(task) => *task,
+        };
+
+        // left
+        if has_icon_cell(conf) {
+            let icon_area = place(Area::new(icon_x, y, icon_w, icon_w));
+            draw_task_icon(frame, conf, icons, task, icon_area);
+            draw_count_badge(frame, conf, tasks, task, tr, icon_area);
+        }
+        if thumb_w > 0.0 {
+            let thumb_area = place(Area::new(thumb_x, y, thumb_w, task_h));
+            draw_thumbnail(frame, conf, icons, task, thumb_area);
+        }
+
+        // center
+        let task_area = place(Area::new(task_x, y, task_w, task_h));
+        if is_selected {
+            if draw_task(
+                frame,
+                conf,
+                &tasks.label(task),
+                &tasks.query_matches(task),
+                tr,
+                &style,
+                task_area,
+            ) {
+                overflowing = Some(task_area);
+            }
+        } else {
+            let mut style = conf.task_style();
+            let step = 1.0 - (i as f32 / rows.len() as f32);
+            let gradient = Color::new(
+                (step * style.bg_color.r as f32) as u8,
+                (step * style.bg_color.g as f32) as u8,
+                (step * style.bg_color.b as f32) as u8,
+                (step * style.bg_color.a as f32) as u8,
+            );
+            if conf.task_gradient {
+                style.bg_color = &gradient;
+            }
+            if hovered == Some(task.wid) {
+                style.bg_color = &conf.hover_bg_color;
+            }
+            let dimmed = style.fg_color.multiply(0.5);
+            if (conf.show_current == ShowCurrent::Dim && tasks.is_current(task))
+                || task.closed.is_some()
+            {
+                style.fg_color = &dimmed;
+            }
+            draw_task(
+                frame,
+                conf,
+                &tasks.label(task),
+                &tasks.query_matches(task),
+                tr,
+                &style,
+                task_area,
+            );
+        };
+        draw_task_badges(frame, conf, tasks, task, tr, task_area);
+
+        // right
+        if conf.show_marker {
+            let marker_area = place(Area::new(marker_x, y, marker_w, task_h));
+            draw_task_marker(frame, conf, tasks, task, tr, is_selected, marker_area);
+        }
+
+        // row separator
+        if i % per_col != 0 {
+            let sep = place(Area::new(x, y, col_w, 0.0));
+            let (x1, x2) = (sep.x, sep.x + sep.w);
+            frame.draw_hline(conf.row_sep_width, y, x1, x2, &conf.row_sep_color);
+        }
+    }
+    overflowing
+}
+pub fn draw_list_cols(
+    frame: &mut Frame,
+    conf: &Config,
+    tasks: &TaskList,
+    hovered: Option<Window>,
+    tr: &mut TextRenderer,
+    icons: &mut IconCache,
+) -> Option<Area> {
+    let (rows, selected_idx) = tasks.visible_rows();
+    if rows.is_empty() {
+        return None;
+    }
+    let mut area = Area::new(0.0, 0.0, frame.width() as f32, frame.height() as f32);
+    frame.draw_rect(area, &conf.bg_color);
+    frame.draw_rect_outline(area, conf.border_width, &conf.border_color);
+    area = area.shrink(conf.border_width);
+    // the columns are laid out left to right, then mirrored
+    let place = |cell: Area| match conf.layout_direction {
+        LayoutDirection::Ltr => cell,
+        LayoutDirection::Rtl => cell.mirrored(area),
+    };
+
+    let task_w = cell_size(area.w, tasks.cells(), conf.task_gap);
+
+    let icon_y = area.y;
+    let icon_h = if has_icon_cell(conf) { task_w } else { 0.0 };
+    let thumb_h = if conf.thumbnails == Thumbnails::Beside {
+        task_w / THUMBNAIL_ASPECT
+    } else {
+        0.0
+    };
+    // the icon and the thumbnail on top
+    let head_h = icon_h + thumb_h;
+
+    let marker_h = if conf.show_marker {
+        conf.marker_height.or(conf.marker_width).unwrap_or(task_w)
+    } else {
+        0.0
+    };
+    let (marker_y, task_y) = match conf.marker_position {
+        MarkerPosition::Above => (area.y + head_h, area.y + head_h + marker_h),
+        MarkerPosition::Below => (area.y + area.h - marker_h, area.y + head_h),
+    };
+    let task_h = area.h - head_h - marker_h;
+
+    let style = conf.selected_task_style();
+    let mut overflowing = None;
+
+    if conf.show_marker && conf.marker_bg == MarkerBg::Gutter {
+        draw_marker_bg(
+            frame,
+            conf,
+            &conf.marker_bg_color,
+            Area::new(area.x, marker_y, area.w, marker_h),
+        );
+    }
+
+    for (i, row) in rows.iter().enumerate() {
+        let x = area.x + (task_w + conf.task_gap) * i as f32;
+        let is_selected = Some(i) == selected_idx;
+        let task = match row {
+            Row::Header(name) => {
+                draw_header(
+                    frame,
+                    conf,
+                    tr,
+                    name,
+                    place(Area::new(x, area.y, task_w, area.h)),
+                );
+                continue;
+            }
+            Row::Task(task) => *task,
+        };
+
+        // left
+        if has_icon_cell(conf) {
+            let icon_area = place(Area::new(x, icon_y, icon_h, icon_h));
+            draw_task_icon(frame, conf, icons, task, icon_area);
+            draw_count_badge(frame, conf, tasks, task, tr, icon_area);
+        }
+        if thumb_h > 0.0 {
+            let thumb_area = place(Area::new(x, icon_y + icon_h, task_w, thumb_h));
+            draw_thumbnail(frame, conf, icons, task, thumb_area);
+        }
+
+        // center
+        let task_area = place(Area::new(x, task_y, task_w, task_h));
+        if is_selected {
+            if draw_task(
+                frame,
+                conf,
+                &tasks.label(task),
+                &tasks.query_matches(task),
+                tr,
+                &style,
+                task_area,
+            ) {
+                overflowing = Some(task_area);
+            }
+        } else {
+            let mut style = conf.task_style();
+            let step = 1.0 - (i as f32 / rows.len() as f32);
+            let gradient = Color::new(
+                (step * style.bg_color.r as f32) as u8,
+                (step * style.bg_color.g as f32) as u8,
+                (step * style.bg_color.b as f32) as u8,
+                (step * style.bg_color.a as f32) as u8,
+            );
+            if conf.task_gradient {
+                style.bg_color = &gradient;
+            }
+            if hovered == Some(task.wid) {
+                style.bg_color = &conf.hover_bg_color;
+            }
+            let dimmed = style.fg_color.multiply(0.5);
+            if (conf.show_current == ShowCurrent::Dim && tasks.is_current(task))
+                || task.closed.is_some()
+            {
+                style.fg_color = &dimmed;
+            }
+            draw_task(
+                frame,
+                conf,
+                &tasks.label(task),
+                &tasks.query_matches(task),
+                tr,
+                &style,
+                task_area,
+            );
+        };
+        draw_task_badges(frame, conf, tasks, task, tr, task_area);
+
+        // right
+        if conf.show_marker {
+            let marker_area = place(Area::new(x, marker_y, task_w, marker_h));
+            draw_task_marker(frame, conf, tasks, task, tr, is_selected, marker_area);
+        }
+
+        // row separator
+        // if i != 0 {
+        //     draw_vline(
+        //         pm,
+        //         &conf.row_sep_color,
+        //         conf.row_sep_width,
+        //         y,
+        //         area.x,
+        //         area.x + area.w,
+        //     );
+        // }
+    }
+    overflowing
+}
+pub fn draw_header(
+    frame: &mut Frame,
+    conf: &Config,
+    tr: &mut TextRenderer,
+    name: &str,
+    area: Area,
+) {
+    frame.draw_rect(area, &conf.header_bg_color);
+    tr.set_layout_aligned(
+        name,
+        conf,
+        area.shrink(conf.task_border_width),
+        HorizontalAlign::Center,
+        VerticalAlign::Middle,
+    );
+    draw_text(frame, conf, &conf.header_fg_color, tr);
+}
+pub fn draw_task_marker(
+    frame: &mut Frame,
+    conf: &Config,
+    tasks: &TaskList,
+    task: &Task,
+    tr: &mut TextRenderer,
+    is_selected: bool,
+    area: Area,
+) {
+    let bg_color = (conf.marker_bg == MarkerBg::Cell).then_some(&conf.marker_bg_color);
+    let colors = (&conf.marker_fg_color, bg_color);
+    if conf.numbered_marker {
+        let rank = tasks.rank(task).map_or(0, |rank| rank + 1).to_string();
+        // the selected rank stands out with swapped colors
+        let colors = if is_selected {
+            (&conf.marker_bg_color, Some(&conf.marker_fg_color))
+        } else {
+            colors
+        };
+        draw_marker(frame, conf, tr, &rank, colors, area);
+    } else if is_selected {
+        let mut buf = [0u8; 4];
+        let marker = conf.marker.encode_utf8(&mut buf);
+        draw_marker(frame, conf, tr, marker, colors, area);
+    }
+}
+pub fn draw_marker(
+    frame: &mut Frame,
+    conf: &Config,
+    tr: &mut TextRenderer,
+    text: &str,
+    (fg_color, bg_color): (&Color, Option<&Color>),
+    area: Area,
+) {
+    tr.set_layout(text, conf, area);
+    if let Some(bg_color) = bg_color {
+        draw_marker_bg(frame, conf, bg_color, area);
+    }
+    draw_text(frame, conf, fg_color, tr);
+}
+pub fn draw_marker_bg(frame: &mut Frame, conf: &Config, color: &Color, area: Area) {
+    frame.draw_rect(area, color);
+    frame.draw_rect_outline(area, conf.marker_border_width, &conf.marker_border_color);
+}
+pub fn draw_task_badges(
+    frame: &mut Frame,
+    conf: &Config,
+    tasks: &TaskList,
+    task: &Task,
+    tr: &mut TextRenderer,
+    area: Area,
+) {
+    if conf.show_new_badge && tasks.is_new(task) {
+        let colors = (&conf.new_badge_fg_color, &conf.new_badge_bg_color);
+        draw_badge(frame, conf, tr, "new", colors, &Anchor::TOP_RIGHT, area);
+    }
+    if !conf.sticky_badge.is_empty() && task.desktop == Some(STICKY) {
+        let colors = (&conf.sticky_badge_fg_color, &conf.sticky_badge_bg_color);
+        draw_badge(
+            frame,
+            conf,
+            tr,
+            &conf.sticky_badge,
+            colors,
+            &Anchor::TOP_LEFT,
+            area,
+        );
+    }
+    if !task.responsive {
+        let colors = (
+            &conf.unresponsive_badge_fg_color,
+            &conf.unresponsive_badge_bg_color,
+        );
+        let text = "not responding";
+        draw_badge(frame, conf, tr, text, colors, &Anchor::BOTTOM_RIGHT, area);
+    }
+}
+/// the number of windows of the class, over the corner of the icon
+pub fn draw_count_badge(
+    frame: &mut Frame,
+    conf: &Config,
+    tasks: &TaskList,
+    task: &Task,
+    tr: &mut TextRenderer,
+    area: Area,
+) {
+    if !tasks.group_by_class {
+        return;
+    }
+    let text = tasks.group_len(task).to_string();
+    let anchor = match conf.layout_direction {
+        LayoutDirection::Ltr => Anchor::BOTTOM_RIGHT,
+        LayoutDirection::Rtl => Anchor::BOTTOM_RIGHT.mirrored(),
+    };
+    let (text_w, text_h) = tr.measure(&text);
+    let size = text_w.max(text_h);
+    if size > area.w || size > area.h {
+        return;
+    }
+    let (x, y) = anchor.resolve((size, size), (area.w, area.h));
+    let badge = Area::new(area.x + x, area.y + y, size, size);
+    frame.draw_disc(badge, &conf.count_badge_bg_color);
+    tr.set_layout_aligned(
+        &text,
+        conf,
+        badge,
+        HorizontalAlign::Center,
+        VerticalAlign::Middle,
+    );
+    draw_text(frame, conf, &conf.count_badge_fg_color, tr);
+}
+pub fn draw_badge(
+    frame: &mut Frame,
+    conf: &Config,
+    tr: &mut TextRenderer,
+    text: &str,
+    (fg_color, bg_color): (&Color, &Color),
+    anchor: &Anchor,
+    area: Area,
+) {
+    let area = area.shrink(conf.task_border_width.max(conf.selected_task_border_width));
+    let anchor = match conf.layout_direction {
+        LayoutDirection::Ltr => anchor.clone(),
+        LayoutDirection::Rtl => anchor.mirrored(),
+    };
+    let (text_w, text_h) = tr.measure(text);
+    let pad = text_h / 4.0;
+    let (w, h) = (text_w + pad * 2.0, text_h);
+    if w > area.w || h > area.h {
+        return;
+    }
+    let (x, y) = anchor.resolve((w, h), (area.w, area.h));
+    let badge = Area::new(area.x + x, area.y + y, w, h);
+    frame.draw_rect(badge, bg_color);
+    tr.set_layout_aligned(
+        text,
+        conf,
+        badge,
+        HorizontalAlign::Center,
+        VerticalAlign::Middle,
+    );
+    draw_text(frame, conf, fg_color, tr);
+}
+/// the draw time of the previous frame and the time spent on the events before this one
+pub fn draw_frame_times(
+    frame: &mut Frame,
+    conf: &Config,
+    tr: &mut TextRenderer,
+    draw_time: Duration,
+    events_time: Duration,
+) {
+    let area = Area::new(0.0, 0.0, frame.width() as f32, frame.height() as f32);
+    let area = area.shrink(conf.border_width);
+    let text = format!(
+        "draw {:.1}ms, events {:.1}ms",
+        draw_time.as_secs_f32() * 1000.0,
+        events_time.as_secs_f32() * 1000.0
+    );
+    let colors = (&conf.header_fg_color, &conf.header_bg_color);
+    draw_badge(frame, conf, tr, &text, colors, &Anchor::TOP_RIGHT, area);
+}
+/// a popup with the full title of the selected task, for titles that don't fit their cell
+pub struct Tooltip {
+    pub window: Window,
+    pub gc: Gcontext,
+    pub frame: Frame,
+    pub is_mapped: bool,
+}
+impl Tooltip {
+    pub fn new(
+        conn: &Conn,
+        screen: &Screen,
+        atoms: &Atoms,
+        depth: u8,
+        visual: Visualid,
+    ) -> Result<Self> {
+        let window = create_window(
+            conn,
+            screen,
+            atoms,
+            Area::new(0.0, 0.0, 1.0, 1.0),
+            depth,
+            visual,
+            Stacking::Above,
+        )?;
+        let gc = create_graphic_context(conn, window)?;
+        Ok(Self {
+            window,
+            gc,
+            frame: Frame::new(1, 1),
+            is_mapped: false,
+        })
+    }
+    /// shows the text below the anchor, or above it if there's no room left on the screen
+    #[allow(clippy::too_many_arguments)]
+    pub fn show(
+        &mut self,
+        conn: &Conn,
+        screen: &Screen,
+        conf: &Config,
+        tr: &mut TextRenderer,
+        text: &str,
+        anchor: Area,
+        depth: u8,
+    ) -> Result<()> {
+        let (screen_w, screen_h) = (
+            screen.width_in_pixels as f32,
+            screen.height_in_pixels as f32,
+        );
+        let (text_w, text_h) = tr.measure(text);
+        let pad = text_h / 4.0 + conf.border_width;
+        let max_text_w = (screen_w / 2.0 - pad * 2.0).max(1.0);
+        let text_area = Area::new(pad, pad, text_w.min(max_text_w).ceil(), screen_h);
+        tr.set_layout_aligned(
+            text,
+            conf,
+            text_area,
+            HorizontalAlign::Left,
+            VerticalAlign::Top,
+        );
+        let w = (text_area.w + pad * 2.0).ceil();
+        let h = (tr.layout.height().min(screen_h) + pad * 2.0).ceil();
+
+        let x = anchor.x.min(screen_w - w).max(0.0);
+        let y = if anchor.y + anchor.h + h <= screen_h {
+            anchor.y + anchor.h
+        } else {
+            (anchor.y - h).max(0.0)
+        };
+
+        self.frame.resize(w as u32, h as u32);
+        let area = Area::new(0.0, 0.0, w, h);
+        self.frame.draw_rect(area, &conf.tooltip_bg_color);
+        self.frame
+            .draw_rect_outline(area, conf.border_width, &conf.border_color);
+        draw_text(&mut self.frame, conf, &conf.tooltip_fg_color, tr);
+        self.frame.reduce_colors(conf.color_depth, conf.dither);
+
+        request_window_move(conn, self.window, Area::new(x, y, w, h))?;
+        let aux = ConfigureWindowAux::new().stack_mode(StackMode::ABOVE);
+        conn.configure_window(self.window, &aux)?;
+        conn.map_window(self.window)?;
+        self.is_mapped = true;
+        self.redraw(conn, depth)
+    }
+    pub fn redraw(&self, conn: &Conn, depth: u8) -> Result<()> {
+        if self.is_mapped {
+            send_frame(conn, self.window, self.gc, &self.frame, depth)?;
+        }
+        Ok(())
+    }
+    pub fn hide(&mut self, conn: &Conn) -> Result<()> {
+        if self.is_mapped {
+            conn.unmap_window(self.window)?;
+            self.is_mapped = false;
+        }
+        Ok(())
+    }
+}
+/// the icon inside the border and the padding
+pub fn icon_inner_area(conf: &Config, area: Area) -> Area {
+    let area = area.shrink(conf.icon_border_width);
+    area.shrink(conf.icon_padding.resolve(area.h))
+}
+/// the size an icon is rendered at to fill its area
+pub fn icon_size(conf: &Config, area: Area) -> u32 {
+    icon_inner_area(conf, area).w as u32
+}
+/// the icons are shown, or the thumbnails in their place
+pub fn has_icon_cell(conf: &Config) -> bool {
+    conf.show_icons || conf.thumbnails == Thumbnails::Instead
+}
+/// the icon of the task, or its thumbnail with `thumbnails: instead`
+pub fn draw_task_icon(
+    frame: &mut Frame,
+    conf: &Config,
+    icons: &mut IconCache,
+    task: &Task,
+    area: Area,
+) {
+    if conf.thumbnails == Thumbnails::Instead && icons.thumbnails.contains_key(&task.wid) {
+        draw_thumbnail(frame, conf, icons, task, area);
+    } else if conf.show_icons {
+        let icon = icons.get(task, icon_size(conf, area), conf.icon_scaling);
+        draw_icon(frame, conf, icon, area);
+    } else {
+        frame.draw_rect(area, &conf.icon_bg_color);
+    }
+}
+/// the contents of the window centered in the area, the background of the icons without one
+pub fn draw_thumbnail(
+    frame: &mut Frame,
+    conf: &Config,
+    icons: &mut IconCache,
+    task: &Task,
+    area: Area,
+) {
+    frame.draw_rect(area, &conf.icon_bg_color);
+    frame.draw_rect_outline(area, conf.icon_border_width, &conf.icon_border_color);
+    let area = area.shrink(conf.icon_border_width);
+    let size = (area.w as u32, area.h as u32);
+    if let Some(thumbnail) = icons.get_thumbnail(task.wid, size) {
+        let x = area.x + (area.w - thumbnail.width() as f32) / 2.0;
+        let y = area.y + (area.h - thumbnail.height() as f32) / 2.0;
+        frame.blit_frame(thumbnail, x as i32, y as i32);
+    }
+}
+/// the icon is expected to be rendered at `icon_size`
+pub fn draw_icon(frame: &mut Frame, conf: &Config, icon: &Frame, area: Area) {
+    frame.draw_rect(area, &conf.icon_bg_color);
+    frame.draw_rect_outline(area, conf.icon_border_width, &conf.icon_border_color);
+    let area = icon_inner_area(conf, area);
+    frame.blit_frame(icon, area.x as i32, area.y as i32);
+}
+/// draws the cell from the cache, so that only the cells that changed are rendered again
+pub fn draw_task(
+    frame: &mut Frame,
+    conf: &Config,
+    title: &str,
+    matched: &[usize],
+    tr: &mut TextRenderer,
+    style: &TaskStyle,
+    area: Area,
+) -> bool {
+    // rendered at the same subpixel offset, the cell is identical to drawing in place
+    let (x, y) = (area.x.floor(), area.y.floor());
+    let local = Area::new(area.x - x, area.y - y, area.w, area.h);
+    let key = CellKey {
+        title: title.to_string(),
+        matched: matched.to_vec(),
+        colors: [
+            style.fg_color.to_bgra(),
+            style.bg_color.to_bgra(),
+            style.border_color.to_bgra(),
+            conf.task_fg_color.to_bgra(),
+            conf.task_bg_color.to_bgra(),
+        ],
+        effects: text_effects_key(conf),
+        border_width: style.border_width.to_bits(),
+        selection: style.selection as u8,
+        size: (area.w.to_bits(), area.h.to_bits()),
+        offset: (local.x.to_bits(), local.y.to_bits()),
+    };
+    let (cell, overflows) = tr.cells.take(&key).unwrap_or_else(|| {
+        let mut cell = Frame::new(area.w.ceil() as u32, area.h.ceil() as u32);
+        let full = Area::new(0.0, 0.0, cell.width() as f32, cell.height() as f32);
+        cell.draw_rect(full, &conf.bg_color);
+        let overflows = render_task(&mut cell, conf, title, matched, tr, style, local);
+        (cell, overflows)
+    });
+    frame.blit_frame(&cell, x as i32, y as i32);
+    tr.cells.put(key, (cell, overflows));
+    overflows
+}
+pub fn text_effects_key(conf: &Config) -> [u32; 5] {
+    let shadow = conf.text_shadow.map_or([0; 3], |s| {
+        [s.x.to_bits(), s.y.to_bits(), s.color.to_bgra()]
+    });
+    let outline = conf
+        .text_outline
+        .map_or([0; 2], |o| [o.width.to_bits(), o.color.to_bgra()]);
+    [shadow[0], shadow[1], shadow[2], outline[0], outline[1]]
+}
+pub fn render_task(
+    frame: &mut Frame,
+    conf: &Config,
+    title: &str,
+    matched: &[usize],
+    tr: &mut TextRenderer,
+    style: &TaskStyle,
+    area: Area,
+) -> bool {
+    let (x1, y1) = (area.x, area.y);
+    let (x2, y2) = (area.x + area.w, area.y + area.h);
+    let bw = style.border_width;
+    let fg_color = match style.selection {
+        SelectionStyle::Box => {
+            frame.draw_rect(area, style.bg_color);
+            frame.draw_rect_outline(area, bw, style.border_color);
+            style.fg_color
+        }
+        SelectionStyle::Underline => {
+            frame.draw_rect(area, &conf.task_bg_color);
+            frame.draw_hline(bw, y2 - bw, x1, x2, style.border_color);
+            style.fg_color
+        }
+        SelectionStyle::LeftBar => {
+            frame.draw_rect(area, &conf.task_bg_color);
+            frame.draw_vline(bw, x1, y1, y2, style.border_color);
+            style.fg_color
+        }
+        SelectionStyle::Invert => {
+            frame.draw_rect(area, &conf.task_fg_color);
+            &conf.task_bg_color
+        }
+    };
+
+    let bw = conf.task_border_width.max(conf.selected_task_border_width);
+    let overflows = tr.set_layout(title, conf, area.shrink(bw));
+    draw_text(frame, conf, fg_color, tr);
+    if !matched.is_empty() {
+        // the chars matched by the query are drawn again over the others
+        let color = &conf.match_fg_color;
+        draw_glyphs_where(
+            frame,
+            tr,
+            (0, 0),
+            |glyph| matched.contains(&glyph.byte_offset),
+            |px, a| *px = color.multiply(a).to_bgra(),
+        );
+    }
+    overflows
+}
+pub fn draw_text(frame: &mut Frame, conf: &Config, color: &Color, tr: &TextRenderer) {
+    if let Some(shadow) = conf.text_shadow {
+        let offset = (shadow.x.round() as i64, shadow.y.round() as i64);
+        draw_glyphs(frame, tr, offset, |px, a| {
+            *px = shadow.color.blend_over(*px, a)
+        });
+    }
+    if let Some(outline) = conf.text_outline {
+        let r = outline.width.round() as i64;
+        for dy in -r..=r {
+            for dx in -r..=r {
+                if (dx, dy) != (0, 0) && dx * dx + dy * dy <= r * r {
+                    draw_glyphs(frame, tr, (dx, dy), |px, a| {
+                        *px = outline.color.blend_over(*px, a)
+                    });
+                }
+            }
+        }
+    }
+    draw_glyphs(frame, tr, (0, 0), |px, a| *px = color.multiply(a).to_bgra());
+}
+/// calls `plot` with the coverage of every glyph pixel, moved by `offset`
+pub fn draw_glyphs(
+    frame: &mut Frame,
+    tr: &TextRenderer,
+    offset: (i64, i64),
+    plot: impl FnMut(&mut u32, f32),
+) {
+    draw_glyphs_where(frame, tr, offset, |_| true, plot);
+}
+/// `draw_glyphs` for the glyphs that pass `filter`
+pub fn draw_glyphs_where(
+    frame: &mut Frame,
+    tr: &TextRenderer,
+    (dx, dy): (i64, i64),
+    filter: impl Fn(&GlyphPosition) -> bool,
+    mut plot: impl FnMut(&mut u32, f32),
+) {
+    let frame_width = frame.width() as usize;
+    let frame_height = frame.height() as usize;
+    let frame = frame.buf_u32_mut();
+
+    for glyph_pos in tr.layout.glyphs().iter().filter(|glyph| filter(glyph)) {
+        let (metrics, bitmap) = tr.get(glyph_pos.parent);
+        for row in 0..metrics.height {
+            for col in 0..metrics.width {
+                let b_offset = row * metrics.width + col;
+                let a = bitmap[b_offset] as f32 / 255.0;
+                if a == 0.0 {
+                    continue;
+                }
+                // glyphs can overflow the frame on any side
+                let px = glyph_pos.x as i64 + col as i64 + dx;
+                let py = glyph_pos.y as i64 + row as i64 + dy;
+                if px < 0 || py < 0 || px as usize >= frame_width || py as usize >= frame_height {
+                    continue;
+                }
+                let p_offset = py as usize * frame_width + px as usize;
+                plot(&mut frame[p_offset], a);
+            }
+        }
+    }
+}
+
+/// where the pointer was when the list was shown, see `location: pointer`
+#[derive(Debug, Clone, Copy)]
+pub struct PointerOrigin {
+    pub x: f32,
+    pub y: f32,
+    // the list is kept inside of it, the screen when the wm doesn't set `_NET_WORKAREA`
+    pub work_area: Area,
+}
+impl PointerOrigin {
+    /// the position of a `w` by `h` window centered on the pointer, clamped to the work area
+    pub fn resolve(&self, (w, h): (f32, f32)) -> (f32, f32) {
+        let area = self.work_area;
+        let x = (self.x - w / 2.0).min(area.x + area.w - w).max(area.x);
+        let y = (self.y - h / 2.0).min(area.y + area.h - h).max(area.y);
+        (x, y)
+    }
+}
+pub fn compute_window_geometry(
+    conf: &Config,
+    output: Area,
+    origin: Option<PointerOrigin>,
+    tasks: usize,
+) -> Option<Area> {
+    let area = match (conf.window_size, compute_layout(conf, output, tasks).0) {
+        (WindowSize::Fixed, _) => compute_window_geometry_fixed(conf, output, tasks),
+        (WindowSize::Grow, ListLayout::Rows) => compute_window_geometry_row(conf, output, tasks),
+        (WindowSize::Grow, ListLayout::Columns) => compute_window_geometry_col(conf, output, tasks),
+        (WindowSize::Grow, ListLayout::Grid) => compute_window_geometry_grid(conf, output, tasks),
+    }?;
+    match origin {
+        Some(origin) if conf.follow_pointer => {
+            let (x, y) = origin.resolve((area.w, area.h));
+            Some(Area::new(x, y, area.w, area.h))
+        }
+        _ => Some(area),
+    }
+}
+pub fn compute_window_geometry_fixed(conf: &Config, output: Area, tasks: usize) -> Option<Area> {
+    if tasks == 0 {
+        return None;
+    }
+    let (output_w, output_h) = (output.w, output.h);
+    let w = conf.width.resolve(output_w).min(output_w);
+    let h = conf.height.resolve(output_h).min(output_h);
+    if !(w >= MIN_WINDOW_SIZE && h >= MIN_WINDOW_SIZE) {
+        return None;
+    }
+    Some(place_window(conf, output, (w, h)))
+}
+pub fn compute_window_geometry_row(conf: &Config, output: Area, tasks: usize) -> Option<Area> {
+    if tasks == 0 {
+        return None;
+    }
+    let screen_size = output.h;
+    let task_h = compute_task_size(conf, screen_size, conf.task_height, tasks);
+    let (output_w, output_h) = (output.w, output.h);
+    let tasks = compute_page_size(conf, output, tasks).unwrap_or(tasks);
+    let gaps = conf.task_gap * (tasks - 1) as f32;
+    let w = conf.width.resolve(output_w).min(output_w);
+    let h = (task_h * tasks as f32 + gaps).min(output_h);
+    if !(w >= MIN_WINDOW_SIZE && h >= MIN_WINDOW_SIZE) {
+        return None;
+    }
+    Some(place_window(conf, output, (w, h)))
+}
+pub fn compute_window_geometry_col(conf: &Config, output: Area, tasks: usize) -> Option<Area> {
+    if tasks == 0 {
+        return None;
+    }
+    let screen_size = output.w;
+    let task_size = compute_task_size(conf, screen_size, conf.task_width, tasks);
+    let (output_w, output_h) = (output.w, output.h);
+    let tasks = compute_page_size(conf, output, tasks).unwrap_or(tasks);
+    let gaps = conf.task_gap * (tasks - 1) as f32;
+    let w = (task_size * tasks as f32 + gaps).min(output_w);
+    let h = conf.height.resolve(output_h).min(output_h);
+    if !(w >= MIN_WINDOW_SIZE && h >= MIN_WINDOW_SIZE) {
+        return None;
+    }
+    Some(place_window(conf, output, (w, h)))
+}
+pub fn compute_window_geometry_grid(conf: &Config, output: Area, tasks: usize) -> Option<Area> {
+    if tasks == 0 {
+        return None;
+    }
+    let (output_w, output_h) = (output.w, output.h);
+    let bw = conf.border_width * 2.0;
+    let task_w = conf.task_width.resolve(output_w - bw);
+    let task_h = conf.task_height.resolve(output_h - bw);
+    let (_, columns) = compute_layout(conf, output, tasks);
+    let tasks = compute_page_size(conf, output, tasks).unwrap_or(tasks);
+    let per_col = tasks.div_ceil(columns);
+    let w = (task_w * columns as f32 + conf.task_gap * (columns - 1) as f32).min(output_w);
+    let h = (task_h * per_col as f32 + conf.task_gap * (per_col - 1) as f32).min(output_h);
+    if !(w >= MIN_WINDOW_SIZE && h >= MIN_WINDOW_SIZE) {
+        return None;
+    }
+    Some(place_window(conf, output, (w, h)))
+}
+/// the list at its `location` on the output, nudged away from the edges it's anchored to by the
+/// offsets, the center ones move it right and down
+pub fn place_window(conf: &Config, output: Area, (w, h): (f32, f32)) -> Area {
+    let (x, y) = conf.anchor.resolve((w, h), (output.w, output.h));
+    let dx = conf.location_x_offset.resolve(output.w);
+    let dy = conf.location_y_offset.resolve(output.h);
+    let x = if conf.anchor.x > 0.5 { x - dx } else { x + dx };
+    let y = if conf.anchor.y > 0.5 { y - dy } else { y + dy };
+    let x = x.min(output.w - w).max(0.0);
+    let y = y.min(output.h - h).max(0.0);
+    Area::new(output.x + x, output.y + y, w, h)
+}
+/// the list `t` of the way, from 0 to 1, through `enter_animation`, with its position and opacity
+pub fn enter_frame(conf: &Config, output: Area, g: Area, t: f32) -> (Area, f32) {
+    // eased out, fast at first then settling in place
+    let t = 1.0 - (1.0 - t.clamp(0.0, 1.0)).powi(3);
+    let edge = match conf.enter_animation {
+        EnterAnimation::None => return (g, 1.0),
+        EnterAnimation::Fade => return (g, t),
+        EnterAnimation::Slide(edge) => edge.unwrap_or_else(|| conf.anchor.nearest_edge()),
+    };
+    // from just past the edge of the output
+    let start = match edge {
+        Edge::Top => Area {
+            y: output.y - g.h,
+            ..g
+        },
+        Edge::Bottom => Area {
+            y: output.y + output.h,
+            ..g
+        },
+        Edge::Left => Area {
+            x: output.x - g.w,
+            ..g
+        },
+        Edge::Right => Area {
+            x: output.x + output.w,
+            ..g
+        },
+    };
+    let lerp = |a: f32, b: f32| (a + (b - a) * t).round();
+    (
+        Area::new(lerp(start.x, g.x), lerp(start.y, g.y), g.w, g.h),
+        1.0,
+    )
+}
+/// the room the list can take, the output when it grows
+pub fn compute_bounds(conf: &Config, output: Area) -> (f32, f32) {
+    let (output_w, output_h) = (output.w, output.h);
+    match conf.window_size {
+        WindowSize::Grow => (output_w, output_h),
+        WindowSize::Fixed => (
+            conf.width.resolve(output_w).min(output_w),
+            conf.height.resolve(output_h).min(output_h),
+        ),
+    }
+}
+/// the layout of `rows` rows and the columns of the grid, `layout: auto` picks the rows while they
+/// fit in `height`, the columns while they fit in the width of the list, then the grid
+pub fn compute_layout(conf: &Config, output: Area, rows: usize) -> (ListLayout, usize) {
+    let (w, h) = compute_bounds(conf, output);
+    let bw = conf.border_width * 2.0;
+    let height = conf.height.resolve(output.h).min(h);
+    let layout = if !conf.auto_layout {
+        conf.layout
+    } else if rows <= compute_capacity(conf, height, conf.task_height.resolve(h - bw)) {
+        ListLayout::Rows
+    } else if rows <= compute_capacity(conf, w, conf.task_width.resolve(w - bw)) {
+        ListLayout::Columns
+    } else {
+        ListLayout::Grid
+    };
+    let columns = match (layout, conf.window_size) {
+        (ListLayout::Grid, WindowSize::Grow) => {
+            let (per_col, columns) = compute_grid(conf, output);
+            rows.div_ceil(per_col).clamp(1, columns)
+        }
+        (ListLayout::Grid, WindowSize::Fixed) => compute_grid(conf, output).1,
+        _ => 1,
+    };
+    (layout, columns)
+}
+/// the rows of a column of the grid and the columns that fit
+pub fn compute_grid(conf: &Config, output: Area) -> (usize, usize) {
+    let (w, h) = compute_bounds(conf, output);
+    let bw = conf.border_width * 2.0;
+    let task_w = conf.task_width.resolve(w - bw);
+    let task_h = conf.task_height.resolve(h - bw);
+    (
+        compute_capacity(conf, h, task_h),
+        compute_capacity(conf, w, task_w),
+    )
+}
+pub fn compute_task_size(conf: &Config, screen_size: f32, task_size: Size, tasks: usize) -> f32 {
+    let bw = conf.border_width * 2.0;
+    let gaps = conf.task_gap * tasks.saturating_sub(1) as f32;
+    let screen_size = screen_size - bw;
+    let task_size = task_size.resolve(screen_size);
+    let content_h = task_size * tasks as f32 + gaps + bw;
+    if content_h <= screen_size {
+        return task_size;
+    }
+    match conf.overflow {
+        Overflow::Shrink => {
+            let min_size = conf.task_min_height.min(task_size);
+            ((screen_size - bw - gaps) / tasks as f32).max(min_size)
+        }
+        Overflow::Scroll | Overflow::Paginate => task_size,
+    }
+}
+/// the number of tasks of `task_size` that fit in `size`, borders and gaps included
+pub fn compute_capacity(conf: &Config, size: f32, task_size: f32) -> usize {
+    let available = size - conf.border_width * 2.0 + conf.task_gap;
+    (available / (task_size + conf.task_gap).max(MIN_WINDOW_SIZE)).max(1.0) as usize
+}
+/// the number of rows shown at once, none when all of them fit
+pub fn compute_page_size(conf: &Config, output: Area, rows: usize) -> Option<usize> {
+    let (screen_size, task_size, window_size) = match compute_layout(conf, output, rows) {
+        (ListLayout::Rows, _) => (output.h, conf.task_height, conf.height),
+        (ListLayout::Columns, _) => (output.w, conf.task_width, conf.width),
+        (ListLayout::Grid, columns) => {
+            let capacity = compute_grid(conf, output).0 * columns;
+            return (rows > capacity).then_some(capacity);
+        }
+    };
+    let capacity = match conf.window_size {
+        WindowSize::Grow => {
+            let task_size = compute_task_size(conf, screen_size, task_size, rows);
+            compute_capacity(conf, screen_size, task_size)
+        }
+        WindowSize::Fixed => {
+            let window_size = window_size.resolve(screen_size).min(screen_size);
+            let inner = window_size - conf.border_width * 2.0;
+            let task_size = task_size.resolve(inner);
+            let task_size = match conf.overflow {
+                Overflow::Shrink => {
+                    cell_size(inner, rows, conf.task_gap).max(conf.task_min_height.min(task_size))
+                }
+                Overflow::Scroll | Overflow::Paginate => task_size,
+            };
+            compute_capacity(conf, window_size, task_size)
+        }
+    };
+    (rows > capacity).then_some(capacity)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Area;
+    use super::CellCache;
+    use super::CellKey;
+    use super::Color;
+    use super::Frame;
+    use super::PointerOrigin;
+    use super::compute_layout;
+    use super::compute_page_size;
+    use super::compute_window_geometry;
+    use super::enter_frame;
+    use super::place_window;
+    use super::task_at;
+    use crate::config::Anchor;
+    use crate::config::ColorDepth;
+    use crate::config::Edge;
+    use crate::config::ListLayout;
+    use crate::config::SelectionStyle;
+    use crate::config::str_to_enter_animation;
+    use crate::mock::DPI;
+    use crate::mock::parse;
+    use crate::tasks::Row;
+    use crate::tasks::TaskList;
+    use crate::tasks::virtual_task;
+
+    /// xorshift, enough to fuzz the drawing primitives deterministically
+    struct Rng(u64);
+
+    impl Rng {
+        fn next(&mut self) -> u64 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            self.0
+        }
+        fn below(&mut self, n: u32) -> u32 {
+            (self.next() % n as u64) as u32
+        }
+        /// mostly small values around the frame, with the odd degenerate one
+        fn coord(&mut self) -> f32 {
+            match self.below(16) {
+                0 => f32::NAN,
+                1 => f32::INFINITY,
+                2 => f32::NEG_INFINITY,
+                3 => -1e9,
+                4 => 1e9,
+                _ => self.below(160) as f32 / 2.0 - 40.0,
+            }
+        }
+        fn area(&mut self) -> Area {
+            Area::new(self.coord(), self.coord(), self.coord(), self.coord())
+        }
+        fn frame(&mut self) -> Frame {
+            let mut frame = Frame::new(self.below(24), self.below(24));
+            let color = self.next() as u32;
+            frame.buf_u32_mut().fill(color);
+            frame
+        }
+    }
+
+    #[test]
+    fn mirrored_areas() {
+        let outer = Area::new(10.0, 0.0, 100.0, 50.0);
+        let cell = Area::new(10.0, 5.0, 20.0, 10.0).mirrored(outer);
+        assert_eq!((cell.x, cell.y, cell.w, cell.h), (90.0, 5.0, 20.0, 10.0));
+        let back = cell.mirrored(outer);
+        assert_eq!(back.x, 10.0);
+        assert_eq!(Anchor::TOP_RIGHT.mirrored().x, Anchor::TOP_LEFT.x);
+    }
+
+    #[test]
+    fn work_area_intersection() {
+        let output = Area::new(1920.0, 0.0, 1920.0, 1080.0);
+        let work_area = Area::new(0.0, 30.0, 3840.0, 1050.0);
+        assert_eq!(
+            output.intersection(work_area),
+            Some(Area::new(1920.0, 30.0, 1920.0, 1050.0))
+        );
+        let left = Area::new(0.0, 0.0, 1920.0, 1080.0);
+        assert_eq!(left.intersection(output), None);
+    }
+
+    #[test]
+    fn location_offsets() {
+        let output = Area::new(1920.0, 0.0, 1000.0, 800.0);
+        let (conf, _) = parse("location: 9\nlocation_y_offset: 20", DPI);
+        let g = place_window(&conf, output, (200.0, 100.0));
+        assert_eq!(g, Area::new(2720.0, 680.0, 200.0, 100.0));
+        let (conf, _) = parse("location: 1\nlocation_x_offset: 10%", DPI);
+        assert_eq!(place_window(&conf, output, (200.0, 100.0)).x, 2020.0);
+        // kept on the output
+        let (conf, _) = parse("location: 5\nlocation_x_offset: 900", DPI);
+        assert_eq!(place_window(&conf, output, (200.0, 100.0)).x, 2720.0);
+    }
+
+    #[test]
+    fn enter_animation_frames() {
+        let output = Area::new(1920.0, 0.0, 1000.0, 800.0);
+        let g = Area::new(2720.0, 680.0, 200.0, 100.0);
+        let (conf, _) = parse("location: 9\nenter_animation: slide", DPI);
+        assert_eq!(conf.anchor.nearest_edge(), Edge::Bottom);
+        assert_eq!(enter_frame(&conf, output, g, 0.0).0.y, 800.0);
+        let (half, _) = enter_frame(&conf, output, g, 0.5);
+        assert!(half.y > g.y && half.y < 800.0 && half.x == g.x);
+        assert_eq!(enter_frame(&conf, output, g, 1.0), (g, 1.0));
+        let (conf, _) = parse("location: 4\nenter_animation: slide", DPI);
+        assert_eq!(enter_frame(&conf, output, g, 0.0).0.x, 1720.0);
+        let (conf, _) = parse("enter_animation: slide-top", DPI);
+        assert_eq!(enter_frame(&conf, output, g, 0.0).0.y, -100.0);
+        let (conf, _) = parse("enter_animation: fade", DPI);
+        assert_eq!(enter_frame(&conf, output, g, 0.0), (g, 0.0));
+        assert_eq!(Anchor::CENTER.nearest_edge(), Edge::Bottom);
+        assert_eq!(Anchor::TOP_CENTER.nearest_edge(), Edge::Top);
+        assert!(str_to_enter_animation("slide-up").is_err());
+    }
+
+    #[test]
+    fn pointer_placement() {
+        let work_area = Area::new(0.0, 30.0, 1000.0, 770.0);
+        let origin = |x, y| PointerOrigin { x, y, work_area };
+        assert_eq!(origin(500.0, 400.0).resolve((200.0, 100.0)), (400.0, 350.0));
+        // kept inside of the work area, away from the panel
+        assert_eq!(origin(10.0, 40.0).resolve((200.0, 100.0)), (0.0, 30.0));
+        assert_eq!(origin(990.0, 790.0).resolve((200.0, 100.0)), (800.0, 700.0));
+        // too big to fit, the top left corner stays visible
+        assert_eq!(origin(500.0, 400.0).resolve((1200.0, 100.0)).0, 0.0);
+
+        let (conf, warnings) = parse("location: pointer", DPI);
+        assert!(warnings.is_empty() && conf.follow_pointer);
+        let (conf, _) = parse("location: pointer\nlocation: 3", DPI);
+        assert!(!conf.follow_pointer && conf.anchor == Anchor::TOP_RIGHT);
+        let (conf, _) = parse("location: pointer\nlocation: 10", DPI);
+        assert!(conf.follow_pointer);
+    }
+
+    #[test]
+    fn auto_layout() {
+        let screen = Area::new(0.0, 0.0, 1000.0, 1000.0);
+        let (conf, warnings) = parse(
+            "layout: auto\nborder_width: 0\ntask_gap: 0\nheight: 30%\ntask_height: 100\ntask_width: 250",
+            DPI,
+        );
+        assert!(warnings.is_empty() && conf.auto_layout);
+        assert_eq!(compute_layout(&conf, screen, 3), (ListLayout::Rows, 1));
+        assert_eq!(compute_layout(&conf, screen, 4), (ListLayout::Columns, 1));
+        // 10 rows fit in a column of the grid, 4 columns in the screen
+        assert_eq!(compute_layout(&conf, screen, 15), (ListLayout::Grid, 2));
+        assert_eq!(compute_page_size(&conf, screen, 40), None);
+        assert_eq!(compute_page_size(&conf, screen, 41), Some(40));
+        let g = compute_window_geometry(&conf, screen, None, 15).unwrap();
+        assert_eq!((g.w, g.h), (500.0, 800.0));
+
+        // the grid fills a column before the next one
+        let mut tasks = TaskList::new();
+        let provided = (0..15).map(|i| {
+            let i = i.to_string();
+            virtual_task("test", &i, "term", &i, String::new())
+        });
+        tasks.update_virtual(provided.collect());
+        tasks.set_layout(compute_layout(&conf, screen, tasks.rows()));
+        let (rows, _) = tasks.visible_rows();
+        let wid = |i: usize| match rows[i] {
+            Row::Task(task) => Some(task.wid),
+            Row::Header(_) => None,
+        };
+        assert_eq!(task_at(&conf, &tasks, (500.0, 800.0), (10.0, 10.0)), wid(0));
+        assert_eq!(
+            task_at(&conf, &tasks, (500.0, 800.0), (10.0, 750.0)),
+            wid(7)
+        );
+        assert_eq!(
+            task_at(&conf, &tasks, (500.0, 800.0), (260.0, 10.0)),
+            wid(8)
+        );
+        assert_eq!(task_at(&conf, &tasks, (500.0, 800.0), (260.0, 750.0)), None);
+
+        let (conf, _) = parse("layout: auto\nlayout: grid", DPI);
+        assert!(!conf.auto_layout && conf.layout == ListLayout::Grid);
+    }
+
+    #[test]
+    fn shrinking_never_yields_a_negative_size() {
+        let mut rng = Rng(0x9e3779b97f4a7c15);
+        for _ in 0..10_000 {
+            let area = rng.area().shrink(rng.coord());
+            assert!(area.w >= 0.0 && area.h >= 0.0, "{area:?}");
+        }
+    }
+
+    #[test]
+    fn drawing_never_panics() {
+        let mut rng = Rng(0x2545f4914f6cdd1d);
+        let color = Color::new(1, 2, 3, 4);
+        for _ in 0..10_000 {
+            let mut frame = rng.frame();
+            let area = rng.area();
+            frame.draw_rect(area, &color);
+            frame.draw_rect_outline(area, rng.coord(), &color);
+            frame.draw_disc(area, &color);
+            frame.draw_hline(rng.coord(), area.y, area.x, area.w, &color);
+            frame.draw_vline(rng.coord(), area.x, area.y, area.h, &color);
+            let icon = rng.frame();
+            frame.blit_frame(&icon, area.x as i32, area.y as i32);
+            assert_eq!(
+                frame.buf_u8().len(),
+                Frame::len(frame.width(), frame.height())
+            );
+        }
+    }
+
+    #[test]
+    fn drawing_stays_inside_the_area() {
+        let mut rng = Rng(0xda942042e4dd58b5);
+        for _ in 0..2_000 {
+            let mut frame = Frame::new(1 + rng.below(24), 1 + rng.below(24));
+            let area = Area::new(
+                rng.below(32) as f32 - 4.0,
+                rng.below(32) as f32 - 4.0,
+                rng.below(32) as f32,
+                rng.below(32) as f32,
+            );
+            frame.draw_rect(area, &Color::new(255, 255, 255, 255));
+            let width = frame.width();
+            for (i, px) in frame.buf_u32().iter().enumerate() {
+                let (x, y) = ((i as u32 % width) as f32, (i as u32 / width) as f32);
+                let inside =
+                    x >= area.x && x < area.x + area.w && y >= area.y && y < area.y + area.h;
+                assert_eq!(*px != 0, inside, "{area:?} at ({x}, {y})");
+            }
+        }
+    }
+
+    #[test]
+    fn scaling_never_panics() {
+        let mut rng = Rng(0x853c49e6748fea9b);
+        for _ in 0..2_000 {
+            let frame = rng.frame();
+            let factor = match rng.below(8) {
+                0 => 0.0,
+                1 => -1.0,
+                2 => f32::NAN,
+                3 => f32::INFINITY,
+                _ => rng.below(400) as f32 / 100.0,
+            };
+            for scaled in [
+                frame.scale_bilinear(factor),
+                frame.scale_nn(factor),
+                frame.scale_area(factor),
+            ] {
+                assert_eq!(
+                    scaled.buf_u8().len(),
+                    Frame::len(scaled.width(), scaled.height())
+                );
+                if frame.buf_u8().is_empty() || !(factor.is_finite() && factor > 0.0) {
+                    assert!(scaled.buf_u8().is_empty());
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn area_scaling_averages() {
+        // black and white stripes shrink to gray, transparent pixels don't darken
+        let stripes: Vec<u32> = (0..16)
+            .map(|i| if i % 2 == 0 { 0xFF000000 } else { 0xFFFFFFFF })
+            .collect();
+        let scaled = Frame::from_argb_u32(&stripes, 4, 4).scale_area(0.5);
+        assert_eq!((scaled.width(), scaled.height()), (2, 2));
+        assert!(scaled.buf_u32().iter().all(|&px| px == 0xFF808080));
+
+        let half: Vec<u32> = (0..4).map(|i| if i < 2 { 0 } else { 0xFFFFFFFF }).collect();
+        let scaled = Frame::from_argb_u32(&half, 2, 2).scale_area(0.5);
+        assert_eq!(scaled.buf_u32(), [0x80FFFFFF]);
+    }
+
+    #[test]
+    fn reduced_colors() {
+        let gray = Frame::from_argb_u32(&[0xFF808080; 16], 4, 4);
+        let mut full = Frame::from_argb_u32(&[0xFF336699; 16], 4, 4);
+        full.reduce_colors(ColorDepth::Full, true);
+        assert!(full.buf_u32().iter().all(|&px| px == 0xFF336699));
+
+        // without dithering a flat gray rounds to one level, with it half the pixels are lit
+        let mut mono = Frame::from_argb_u32(gray.buf_u32(), 4, 4);
+        mono.reduce_colors(ColorDepth::Mono, false);
+        assert!(mono.buf_u32().iter().all(|&px| px == 0xFFFFFFFF));
+        let mut mono = Frame::from_argb_u32(gray.buf_u32(), 4, 4);
+        mono.reduce_colors(ColorDepth::Mono, true);
+        let lit = mono
+            .buf_u32()
+            .iter()
+            .filter(|&&px| px == 0xFFFFFFFF)
+            .count();
+        assert_eq!(lit, 8);
+        assert!(
+            mono.buf_u32()
+                .iter()
+                .all(|&px| px == 0xFFFFFFFF || px == 0xFF000000)
+        );
+
+        let mut grayscale = Frame::from_argb_u32(&[0x80FF0000; 4], 2, 2);
+        grayscale.reduce_colors(ColorDepth::Grayscale, false);
+        let px = grayscale.buf_u32()[0];
+        assert_eq!(px >> 24, 0x80);
+        assert!(px & 0xFF == (px >> 8) & 0xFF && px & 0xFF == (px >> 16) & 0xFF);
+        assert_eq!((px & 0xFF) % 17, 0);
+    }
+
+    #[test]
+    fn frames_from_mismatched_buffers() {
+        let frame = Frame::from_rgba_u8(&[255; 7], 2, 2);
+        assert_eq!(frame.buf_u32()[0], u32::MAX);
+        assert_eq!(frame.buf_u32()[1], 0);
+        let frame = Frame::from_argb_u32(&[u32::MAX; 9], 2, 2);
+        assert_eq!(frame.buf_u8().len(), 16);
+        let mut frame = Frame::new(0, 0);
+        frame.resize(0, 5);
+        assert!(frame.buf_u32().is_empty());
+        frame.draw_rect(Area::new(0.0, 0.0, 10.0, 10.0), &Color::new(1, 1, 1, 1));
+    }
+
+    #[test]
+    fn cells_live_for_one_frame() {
+        let key = |title: &str| CellKey {
+            title: title.to_string(),
+            matched: vec![],
+            colors: [0; 5],
+            effects: [0; 5],
+            border_width: 0,
+            selection: SelectionStyle::Box as u8,
+            size: (1, 1),
+            offset: (0, 0),
+        };
+        let mut cells = CellCache::new();
+        cells.put(key("a"), (Frame::new(1, 1), false));
+        cells.put(key("b"), (Frame::new(1, 1), true));
+        cells.sweep();
+        // only `a` is drawn in the next frame
+        let a = cells.take(&key("a")).unwrap();
+        cells.put(key("a"), a);
+        cells.sweep();
+        assert!(cells.take(&key("b")).is_none());
+        assert!(cells.take(&key("a")).is_some());
+    }
+}
@@ -0,0 +1,1654 @@
+//! the state of a running goto and the transitions of the event loop: the list is shown by
+//! `key_next` and `key_prev`, hidden when the modifier is released or `key_quit` is pressed and
+//! the selection is activated on the way. the x requests are made by the transitions themselves
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::path::Path;
+use std::process::ExitCode;
+use std::time::Duration;
+use std::time::Instant;
+use std::time::SystemTime;
+
+use x11rb::connection::Connection;
+use x11rb::protocol::Event;
+use x11rb::protocol::damage::ConnectionExt as _;
+use x11rb::protocol::damage::Damage;
+use x11rb::protocol::randr;
+use x11rb::protocol::xproto::Atom;
+use x11rb::protocol::xproto::ChangeWindowAttributesAux;
+use x11rb::protocol::xproto::ConfigureWindowAux;
+use x11rb::protocol::xproto::ConnectionExt as _;
+use x11rb::protocol::xproto::Cursor;
+use x11rb::protocol::xproto::Gcontext;
+use x11rb::protocol::xproto::GrabMode;
+use x11rb::protocol::xproto::GrabStatus;
+use x11rb::protocol::xproto::InputFocus;
+use x11rb::protocol::xproto::KeyButMask;
+use x11rb::protocol::xproto::KeyPressEvent;
+use x11rb::protocol::xproto::PropertyNotifyEvent;
+use x11rb::protocol::xproto::Screen;
+use x11rb::protocol::xproto::StackMode;
+use x11rb::protocol::xproto::Window;
+use x11rb::resource_manager::Database;
+use xkbcommon::xkb::Keysym;
+
+use crate::ACTIVATION_TIMEOUT;
+use crate::APP_NAME;
+use crate::Atoms;
+use crate::CLOCK_INTERVAL;
+use crate::Conn;
+use crate::ENTER_DURATION;
+use crate::ENTER_FRAME;
+use crate::EXIT_CANCELLED;
+use crate::EXIT_NO_CANDIDATES;
+use crate::NOTICE_DURATION;
+use crate::PING_TIMEOUT;
+use crate::PROVIDER_POLL;
+use crate::RESUME_THRESHOLD;
+use crate::Result;
+use crate::THUMBNAIL_INTERVAL;
+use crate::THUMBNAIL_SIZE;
+use crate::TICK_INTERVAL;
+use crate::cli::Args;
+use crate::cli::Mode;
+use crate::cli::load_aliases;
+use crate::cli::print_event;
+use crate::config::ColorScheme;
+use crate::config::Config;
+use crate::config::EnterAnimation;
+use crate::config::MonitorChoice;
+use crate::config::Stacking;
+use crate::config::State;
+use crate::config::Thumbnails;
+use crate::config::desktop_font_size;
+use crate::icons::IconCache;
+use crate::keys::Keymap;
+use crate::render::Area;
+use crate::render::Frame;
+use crate::render::PointerOrigin;
+use crate::render::TOOLTIP_DELAY;
+use crate::render::TextRenderer;
+use crate::render::Tooltip;
+use crate::render::compute_layout;
+use crate::render::compute_page_size;
+use crate::render::compute_window_geometry;
+use crate::render::draw_frame_times;
+use crate::render::draw_list;
+use crate::render::enter_frame;
+use crate::render::task_at;
+use crate::tasks::Action;
+use crate::tasks::Sort;
+use crate::tasks::TaskList;
+use crate::tasks::VirtualQuery;
+use crate::tasks::VirtualRefresh;
+use crate::tasks::build_palette;
+use crate::tasks::class_matches;
+use crate::tasks::palette_action;
+use crate::x11::Reactor;
+use crate::x11::Teardown;
+use crate::x11::Timer;
+use crate::x11::WindowSource;
+use crate::x11::XSetting;
+use crate::x11::color_scheme;
+use crate::x11::confirm_activation;
+use crate::x11::get_active_window;
+use crate::x11::get_current_desktop;
+use crate::x11::get_desktop_names;
+use crate::x11::get_dpi;
+use crate::x11::get_frame_client;
+use crate::x11::get_monitors;
+use crate::x11::get_pointer_origin;
+use crate::x11::get_pointer_position;
+use crate::x11::get_suspended;
+use crate::x11::get_window_alias;
+use crate::x11::get_window_class;
+use crate::x11::get_window_desktop;
+use crate::x11::get_window_geometry;
+use crate::x11::get_window_icon_name;
+use crate::x11::get_window_role;
+use crate::x11::get_window_thumbnail;
+use crate::x11::get_window_title;
+use crate::x11::get_window_visible_name;
+use crate::x11::get_work_area;
+use crate::x11::get_xsettings;
+use crate::x11::request_damage_subtract;
+use crate::x11::request_damage_watch;
+use crate::x11::request_pointer_warp;
+use crate::x11::request_selection_publish;
+use crate::x11::request_suspended_publish;
+use crate::x11::request_task_activation;
+use crate::x11::request_window_close;
+use crate::x11::request_window_move;
+use crate::x11::request_window_opacity;
+use crate::x11::request_window_redirect;
+use crate::x11::restore_focus;
+use crate::x11::send_frame;
+use crate::x11::wait_for_activation;
+use crate::x11::watch_xsettings;
+
+/// what the events of one iteration changed, the list is laid out and drawn once after them
+#[derive(Default)]
+pub(crate) struct Changes {
+    pub(crate) title: bool,
+    pub(crate) icons: bool,
+    pub(crate) size: bool,
+    pub(crate) focus: bool,
+    pub(crate) window: bool,
+    pub(crate) xsettings: bool,
+    pub(crate) resources: bool,
+    pub(crate) screen: bool,
+}
+impl Changes {
+    fn redraw(&self) -> bool {
+        self.focus || self.title || self.icons || self.window
+    }
+}
+/// where the list is in a switch
+#[derive(Default)]
+pub(crate) enum ListState {
+    #[default]
+    Hidden,
+    /// `key_next` or `key_prev` started a switch that isn't shown yet, see `quick_switch`
+    Pending { presses: u32 },
+    /// the presses of `key_next` and `key_prev` since the switch started, and when
+    /// `enter_animation` started, while it runs
+    Shown {
+        presses: u32,
+        entering: Option<Instant>,
+    },
+    /// the command palette is shown instead of the tasks
+    Palette {
+        palette: Box<TaskList>,
+        entering: Option<Instant>,
+    },
+}
+impl ListState {
+    pub(crate) fn is_shown(&self) -> bool {
+        matches!(self, Self::Shown { .. } | Self::Palette { .. })
+    }
+    /// whether a switch is under way, shown or not, the release of `key_mod` ends it
+    pub(crate) fn is_switching(&self) -> bool {
+        !matches!(self, Self::Hidden)
+    }
+    pub(crate) fn presses(&self) -> u32 {
+        match self {
+            Self::Pending { presses } | Self::Shown { presses, .. } => *presses,
+            Self::Hidden | Self::Palette { .. } => 0,
+        }
+    }
+    pub(crate) fn palette(&self) -> Option<&TaskList> {
+        match self {
+            Self::Palette { palette, .. } => Some(palette.as_ref()),
+            _ => None,
+        }
+    }
+    pub(crate) fn palette_mut(&mut self) -> Option<&mut TaskList> {
+        match self {
+            Self::Palette { palette, .. } => Some(palette.as_mut()),
+            _ => None,
+        }
+    }
+    pub(crate) fn entering(&self) -> Option<Instant> {
+        match self {
+            Self::Shown { entering, .. } | Self::Palette { entering, .. } => *entering,
+            Self::Hidden | Self::Pending { .. } => None,
+        }
+    }
+    pub(crate) fn set_entering(&mut self, start: Option<Instant>) {
+        if let Self::Shown { entering, .. } | Self::Palette { entering, .. } = self {
+            *entering = start;
+        }
+    }
+    /// one more press of `key_next` or `key_prev`, the first one starts a switch
+    pub(crate) fn press(&mut self) {
+        match self {
+            Self::Hidden => *self = Self::Pending { presses: 1 },
+            Self::Pending { presses } | Self::Shown { presses, .. } => *presses += 1,
+            Self::Palette { .. } => {}
+        }
+    }
+    /// false when the list was already shown
+    pub(crate) fn show(&mut self, entering: Option<Instant>) -> bool {
+        let presses = match self {
+            Self::Hidden => 0,
+            Self::Pending { presses } => *presses,
+            Self::Shown { .. } | Self::Palette { .. } => return false,
+        };
+        *self = Self::Shown { presses, entering };
+        true
+    }
+    pub(crate) fn open_palette(&mut self, palette: TaskList) {
+        if let Self::Shown { entering, .. } = *self {
+            *self = Self::Palette {
+                palette: Box::new(palette),
+                entering,
+            };
+        }
+    }
+    /// back to the tasks, with the palette that was shown
+    pub(crate) fn close_palette(&mut self) -> Option<TaskList> {
+        let Self::Palette { entering, .. } = *self else {
+            return None;
+        };
+        match std::mem::replace(
+            self,
+            Self::Shown {
+                presses: 0,
+                entering,
+            },
+        ) {
+            Self::Palette { palette, .. } => Some(*palette),
+            _ => None,
+        }
+    }
+}
+
+/// the themes of both color schemes, loaded upfront since the desktop can switch between them
+pub(crate) fn load_themes(
+    config_path: Option<&Path>,
+    state_path: Option<&Path>,
+    screen: &Screen,
+    res_db: &Database,
+    xsettings: &HashMap<String, XSetting>,
+    has_render: bool,
+) -> [Config; 2] {
+    let base = Config::new(config_path, state_path, screen, res_db, xsettings);
+    [ColorScheme::Light, ColorScheme::Dark]
+        .map(|scheme| base.themed(scheme, screen, res_db, xsettings))
+        .map(|theme| theme.with_bandwidth(has_render))
+}
+
+pub(crate) struct Switcher<'a> {
+    pub(crate) args: &'a Args,
+    pub(crate) conn: &'a Conn,
+    pub(crate) screen: Screen,
+    pub(crate) atoms: &'a Atoms,
+    pub(crate) oneshot: bool,
+    pub(crate) source: WindowSource,
+    pub(crate) kb: Keymap,
+
+    pub(crate) res_db: Database,
+    pub(crate) has_composite: bool,
+    pub(crate) has_damage: bool,
+    pub(crate) xsettings_selection: Atom,
+    pub(crate) xsettings_owner: Option<Window>,
+    pub(crate) xsettings: HashMap<String, XSetting>,
+    pub(crate) themes: [Config; 2],
+    // the dpi the themes are scaled for, the screen may have been resized since
+    pub(crate) dpi: f32,
+    // whether the config sets a light or a dark theme, which the color scheme then picks
+    pub(crate) themed: bool,
+    pub(crate) scheme: ColorScheme,
+
+    pub(crate) tasks: TaskList,
+    // a lazy daemon doesn't query the windows until the first keypress
+    pub(crate) populated: bool,
+    pub(crate) icons: IconCache,
+    pub(crate) tr: TextRenderer,
+    // the windows whose contents are kept offscreen for `thumbnails`
+    pub(crate) redirected: HashSet<Window>,
+    // while the list is shown, the windows are captured again only when their contents change
+    pub(crate) damages: HashMap<Window, Damage>,
+    pub(crate) damaged: HashSet<Window>,
+    // the providers and tmux run off the event loop
+    pub(crate) virtual_refresh: VirtualRefresh,
+
+    pub(crate) window: Window,
+    pub(crate) gc: Gcontext,
+    pub(crate) depth: u8,
+    pub(crate) frame: Frame,
+    pub(crate) geometry: Area,
+    // the monitor the list is placed on, updated each time it's shown
+    pub(crate) output: Area,
+    pub(crate) origin: Option<PointerOrigin>,
+    pub(crate) tooltip: Tooltip,
+    pub(crate) hand_cursor: Cursor,
+    pub(crate) reactor: Reactor,
+    pub(crate) _teardown: Teardown<'a>,
+
+    pub(crate) state: ListState,
+    pub(crate) hovered: Option<Window>,
+    pub(crate) overflowing: Option<Area>,
+    pub(crate) selected_at: Instant,
+    // the window being activated, and whether the fallback was already tried
+    pub(crate) activating: Option<(Window, bool)>,
+    // the focus before a oneshot list, given back when the list doesn't activate a window
+    pub(crate) prior_focus: Window,
+    // without `raise_on_show` the list is only raised the first time
+    pub(crate) raised: bool,
+    // the switching keys are let through to the focused window, see `key_suspend`
+    pub(crate) suspended: bool,
+    // the focused window matches `passthrough_class`
+    pub(crate) passthrough: bool,
+    // whether the switching keys are ungrabbed, for either of the above
+    pub(crate) released: bool,
+    // reparenting wms report the moves of their frames, mapped to the client inside, if any
+    pub(crate) frames: HashMap<Window, Option<Window>>,
+    pub(crate) clock: (Instant, SystemTime),
+    // how long the last frame took to draw, see `debug_overlay`
+    pub(crate) draw_time: Duration,
+    pub(crate) sent_at: Instant,
+    // the selection last published on the root window, see `publish_selection`
+    pub(crate) published: Option<(Window, String)>,
+    pub(crate) changed: Changes,
+}
+impl Switcher<'_> {
+    pub(crate) fn conf(&self) -> &Config {
+        &self.themes[self.scheme as usize]
+    }
+    /// handles the events until goto exits, with the exit code of a oneshot list
+    pub(crate) fn run(mut self) -> Result<ExitCode> {
+        if self.oneshot
+            && let Some(code) = self.start_oneshot()?
+        {
+            return Ok(code);
+        }
+        if !self.oneshot
+            && let Ok(focused) = get_active_window(self.conn, &self.screen, self.atoms)
+        {
+            self.update_passthrough(focused)?;
+        }
+        loop {
+            self.changed = Changes::default();
+            self.conn.flush()?;
+            // queued events are handled right away, the timers that expired meanwhile too
+            let mut event_option = self.conn.poll_for_event()?;
+            let expired = self.reactor.wait(self.conn, event_option.is_none())?;
+            let handling = Instant::now();
+            self.check_resume()?;
+            for timer in expired {
+                self.tick(timer)?;
+            }
+            if event_option.is_none() {
+                event_option = self.conn.poll_for_event()?;
+            }
+            while let Some(event) = event_option {
+                if let Some(code) = self.handle_event(event)? {
+                    return Ok(code);
+                }
+                event_option = self.conn.poll_for_event()?;
+            }
+            self.apply_settings()?;
+            if let Some(code) = self.relayout()? {
+                return Ok(code);
+            }
+            self.publish_selection()?;
+            self.redraw(handling)?;
+        }
+    }
+    /// compares the clocks since the last wakeup, the monotonic clock doesn't advance while
+    /// suspended and the wall clock does
+    fn check_resume(&mut self) -> Result<()> {
+        let now = (Instant::now(), SystemTime::now());
+        let monotonic = now.0 - self.clock.0;
+        let wall = now.1.duration_since(self.clock.1).unwrap_or_default();
+        self.clock = now;
+        // some servers drop passive grabs on suspend
+        if wall > monotonic + RESUME_THRESHOLD && !self.oneshot {
+            self.kb.grab(self.conn, &self.screen, self.released)?;
+            eprintln!(
+                "[INFO] resumed from suspend, re-grabbed `key_next`, `key_prev`, `key_kill`, `key_quit` and re-selected the `key_mod` release events"
+            );
+        }
+        Ok(())
+    }
+    /// shows the list with the keyboard grabbed, none when it can be shown
+    fn start_oneshot(&mut self) -> Result<Option<ExitCode>> {
+        if self.tasks.is_empty() {
+            return Ok(Some(ExitCode::from(EXIT_NO_CANDIDATES)));
+        }
+        self.prior_focus = self.conn.get_input_focus()?.reply()?.focus;
+        self.tasks.select_older();
+        self.show()?;
+        // the wm doesn't focus override redirect windows, the list takes the focus itself and
+        // the grab keeps the keys from other clients meanwhile
+        let (conn, window) = (self.conn, self.window);
+        conn.set_input_focus(InputFocus::PARENT, window, x11rb::CURRENT_TIME)?;
+        let mode = GrabMode::ASYNC;
+        let grab = conn
+            .grab_keyboard(false, window, x11rb::CURRENT_TIME, mode, mode)?
+            .reply()?;
+        if grab.status != GrabStatus::SUCCESS {
+            eprintln!("[WARNING] failed to grab the keyboard, relying on the input focus");
+        }
+        Ok(None)
+    }
+    pub(crate) fn show(&mut self) -> Result<()> {
+        if self.state.is_shown() {
+            return Ok(());
+        }
+        let (conn, screen, atoms) = (self.conn, &self.screen, self.atoms);
+        let current = self.choose_output();
+        let moved = self.output != current;
+        self.output = current;
+        let conf = &self.themes[self.scheme as usize];
+        if moved {
+            let rows = self.tasks.rows();
+            self.tasks
+                .set_layout(compute_layout(conf, self.output, rows));
+            self.tasks
+                .set_page_size(compute_page_size(conf, self.output, rows));
+        }
+        if conf.follow_pointer {
+            self.origin = get_pointer_origin(conn, screen, atoms).ok();
+        }
+        if (moved || conf.follow_pointer)
+            && let Some(g) =
+                compute_window_geometry(conf, self.output, self.origin, self.tasks.rows())
+        {
+            self.geometry = g;
+            request_window_move(conn, self.window, self.geometry)?;
+            // mapping exposes the window, the frame is drawn again anyway
+            self.frame.resize(g.w as u32, g.h as u32);
+        }
+        if conf.stacking == Stacking::Above && (conf.raise_on_show || !self.raised) {
+            let aux = ConfigureWindowAux::new().stack_mode(StackMode::ABOVE);
+            conn.configure_window(self.window, &aux)?;
+            self.raised = true;
+        }
+        let (ping, animated) = (conf.ping, conf.enter_animation != EnterAnimation::None);
+        self.state.show(animated.then(Instant::now));
+        if animated {
+            // mapped at the first frame
+            self.step_enter()?;
+        }
+        conn.map_window(self.window)?;
+        self.capture_thumbnails();
+        if !self.oneshot {
+            // the in-list keys aren't grabbed on the root window, so that the other applications
+            // keep them while the list is hidden
+            let mode = GrabMode::ASYNC;
+            let root = self.screen.root;
+            conn.grab_keyboard(false, root, x11rb::CURRENT_TIME, mode, mode)?;
+        }
+        self.reactor.set_timer(Timer::Tick, TICK_INTERVAL);
+        if ping {
+            self.tasks.ping(conn, atoms);
+        }
+        Ok(())
+    }
+    /// moves or fades the list to the next frame of `enter_animation`, the last one leaves it
+    /// where it belongs
+    fn step_enter(&mut self) -> Result<()> {
+        let Some(start) = self.state.entering() else {
+            return Ok(());
+        };
+        let t = start.elapsed().as_secs_f32() / ENTER_DURATION.as_secs_f32();
+        let (mut area, mut opacity) = enter_frame(self.conf(), self.output, self.geometry, t);
+        if t >= 1.0 {
+            self.state.set_entering(None);
+            (area, opacity) = (self.geometry, 1.0);
+        } else {
+            self.reactor.set_timer(Timer::Enter, ENTER_FRAME);
+        }
+        match self.conf().enter_animation {
+            EnterAnimation::None => {}
+            EnterAnimation::Fade => {
+                request_window_opacity(self.conn, self.atoms, self.window, opacity)?
+            }
+            EnterAnimation::Slide(_) => request_window_move(self.conn, self.window, area)?,
+        }
+        Ok(())
+    }
+    /// hides the list, which ends the switch
+    pub(crate) fn hide(&mut self) -> Result<()> {
+        if !self.state.is_shown() {
+            self.state = ListState::Hidden;
+            return Ok(());
+        }
+        self.conn.unmap_window(self.window)?;
+        if !self.oneshot {
+            self.conn.ungrab_keyboard(x11rb::CURRENT_TIME)?;
+        }
+        if self.state.entering().is_some() {
+            // hidden halfway, back in place for the next time
+            self.state
+                .set_entering(Some(Instant::now() - ENTER_DURATION));
+            self.step_enter()?;
+            self.reactor.cancel_timer(Timer::Enter);
+        }
+        self.tooltip.hide(self.conn)?;
+        self.unwatch_damage()?;
+        self.tasks.mark_seen();
+        self.tasks.set_query(None);
+        self.reactor.cancel_timer(Timer::Tick);
+        self.state = ListState::Hidden;
+        self.hovered = None;
+        Ok(())
+    }
+    /// hides the list and activates the selection
+    pub(crate) fn switch(&mut self) -> Result<()> {
+        self.tasks.thaw();
+        self.hide()?;
+        let (conn, screen, atoms) = (self.conn, &self.screen, self.atoms);
+        let conf = &self.themes[self.scheme as usize];
+        if let Some(task) = self.tasks.selected()
+            && let Ok(target) = request_task_activation(conn, screen, atoms, conf, task)
+        {
+            if conf.focus_follows_mouse && !task.is_virtual() {
+                let _ = request_pointer_warp(conn, target.unwrap_or(task.wid));
+            }
+            if let Some(wid) = target {
+                self.activating = Some((wid, false));
+                self.reactor
+                    .set_timer(Timer::Activation, ACTIVATION_TIMEOUT);
+            }
+            // the reopened window will be tracked on its own
+            if task.closed.is_some() {
+                let wid = task.wid;
+                self.tasks.untrack(wid);
+            } else {
+                self.tasks.focus_by_selection();
+            }
+        }
+        Ok(())
+    }
+    /// lets the switching keys through to the focused window, like a vm or a remote desktop,
+    /// until `key_suspend` or `--suspend` resumes the grabs
+    fn set_suspended(&mut self, suspended: bool) -> Result<()> {
+        if suspended == self.suspended || self.oneshot {
+            return Ok(());
+        }
+        self.suspended = suspended;
+        self.sync_grabs()?;
+        if !self.released {
+            self.show_notice(&format!("{APP_NAME} resumed"))?;
+        }
+        request_suspended_publish(self.conn, &self.screen, self.atoms, suspended)
+    }
+    /// the focus moved, the keys are let through while a `passthrough_class` window has it
+    fn update_passthrough(&mut self, focused: Option<Window>) -> Result<()> {
+        let classes = &self.conf().passthrough_class;
+        if self.oneshot || (classes.is_empty() && !self.passthrough) {
+            return Ok(());
+        }
+        self.passthrough = focused
+            .and_then(|wid| get_window_class(self.conn, self.atoms, wid).ok())
+            .is_some_and(|class| class_matches(&class, classes));
+        self.sync_grabs()
+    }
+    /// grabs or ungrabs the switching keys when suspending or the passthrough changed
+    fn sync_grabs(&mut self) -> Result<()> {
+        let release = self.suspended || self.passthrough;
+        if release == self.released {
+            return Ok(());
+        }
+        self.released = release;
+        if release {
+            if self.state.is_switching() {
+                self.cancel()?;
+            }
+            self.kb.ungrab(self.conn, &self.screen)
+        } else {
+            self.kb.grab(self.conn, &self.screen, false)
+        }
+    }
+    /// the text in a tooltip in the middle of the monitor, for a moment
+    fn show_notice(&mut self, text: &str) -> Result<()> {
+        let output = self.choose_output();
+        let (w, h) = self.tr.measure(text);
+        let anchor = Area::new(
+            output.x + (output.w - w) / 2.0,
+            output.y + (output.h - h) / 2.0,
+            0.0,
+            0.0,
+        );
+        let conf = &self.themes[self.scheme as usize];
+        let (tr, depth) = (&mut self.tr, self.depth);
+        self.tooltip
+            .show(self.conn, &self.screen, conf, tr, text, anchor, depth)?;
+        self.reactor.set_timer(Timer::Notice, NOTICE_DURATION);
+        Ok(())
+    }
+    /// hides the list and keeps the focus where it is
+    pub(crate) fn cancel(&mut self) -> Result<()> {
+        self.tasks.thaw();
+        if let Ok(Some(_)) = get_active_window(self.conn, &self.screen, self.atoms) {
+            self.tasks.select_end();
+        } else {
+            self.tasks.unfocus();
+        }
+        self.hide()
+    }
+    /// runs the action selected in the palette and closes it
+    fn run_palette_action(&mut self) {
+        let action = self.state.close_palette().as_ref().and_then(palette_action);
+        self.run_action(action);
+    }
+    fn run_action(&mut self, action: Option<Action>) {
+        let Some(action) = action else {
+            return;
+        };
+        action.apply(&mut self.themes, &mut self.tasks);
+        let conf = &self.themes[self.scheme as usize];
+        if conf.sort == Sort::Stacking {
+            let wids = self.list_stacking().unwrap_or_default();
+            self.tasks.restack(wids);
+        }
+        let conf = &self.themes[self.scheme as usize];
+        if conf.show_icons {
+            self.icons.set_icons(self.conn, self.atoms, &self.tasks);
+        }
+        if conf.persist_toggles {
+            let (key, val) = action.setting(conf, &self.tasks);
+            let mut state = State::load(self.args.state_path());
+            state.set(&format!("config.{key}"), val);
+            if let Err(e) = state.save() {
+                eprintln!("[WARNING] failed to save the state: {e}");
+            }
+        }
+    }
+    fn list_windows(&self) -> Result<Vec<Window>> {
+        let mut wids = self.source.list(self.conn, &self.screen, self.atoms)?;
+        // with `stacking: normal` the wm manages the list too
+        wids.retain(|wid| *wid != self.window);
+        Ok(wids)
+    }
+    fn list_stacking(&self) -> Result<Vec<Window>> {
+        self.source
+            .list_stacking(self.conn, &self.screen, self.atoms)
+    }
+    /// the monitor chosen by `monitor`, without the panels reserved in the work area
+    fn choose_output(&self) -> Area {
+        let conf = self.conf();
+        let pointer = (conf.monitor == MonitorChoice::Pointer)
+            .then(|| get_pointer_position(self.conn, &self.screen).ok())
+            .flatten();
+        let output = self.tasks.output(&self.screen, &conf.monitor, pointer);
+        get_work_area(self.conn, &self.screen, self.atoms)
+            .ok()
+            .flatten()
+            .and_then(|work_area| output.intersection(work_area))
+            .unwrap_or(output)
+    }
+    fn print_changes(&self, removed: &[Window], added: &[Window]) {
+        if self.args.events {
+            removed
+                .iter()
+                .for_each(|wid| print_event("removed", *wid, None));
+            added
+                .iter()
+                .for_each(|wid| print_event("added", *wid, self.tasks.get_task_by_id(*wid)));
+        }
+    }
+    /// follows the windows listed by the wm
+    pub(crate) fn sync_tasks(&mut self) {
+        let (conn, atoms) = (self.conn, self.atoms);
+        if !self.populated {
+            return;
+        }
+        let Ok(wids) = self.list_windows() else {
+            return;
+        };
+        let before_len = self.tasks.rows();
+        let (removed, added) = self.tasks.diff_update(wids, conn, atoms);
+        if self.conf().sort == Sort::Stacking
+            && let Ok(wids) = self.list_stacking()
+        {
+            self.tasks.restack(wids);
+        }
+        self.print_changes(&removed, &added);
+        self.changed.size |= before_len != self.tasks.rows();
+        self.changed.focus |= true;
+        if self.conf().show_icons {
+            self.icons.set_icons(conn, atoms, &self.tasks);
+            self.changed.icons |= true;
+        }
+        self.redirect_windows();
+    }
+    /// redirects the new windows, so that their contents are there by the time they are captured
+    fn redirect_windows(&mut self) {
+        if !self.has_composite || self.conf().thumbnails == Thumbnails::Off {
+            return;
+        }
+        self.redirected.retain(|wid| self.tasks.contains(*wid));
+        // the server frees the damage of the destroyed windows
+        self.damages.retain(|wid, _| self.redirected.contains(wid));
+        for task in self.tasks.list_ascending().0 {
+            if !task.is_virtual()
+                && !self.redirected.contains(&task.wid)
+                && request_window_redirect(self.conn, task.wid).is_ok()
+            {
+                self.redirected.insert(task.wid);
+            }
+        }
+    }
+    /// reads the contents of the windows listed for `thumbnails` right after the list is
+    /// drawn, so that showing it doesn't wait on a round trip per window, the thumbnails of
+    /// the last time are drawn meanwhile
+    fn capture_thumbnails(&mut self) {
+        if !self.has_composite || self.conf().thumbnails == Thumbnails::Off {
+            return;
+        }
+        self.redirect_windows();
+        self.icons
+            .thumbnails
+            .retain(|wid, _| self.redirected.contains(wid));
+        // the unmapped windows can't be read, they keep their last contents
+        self.damaged.extend(&self.redirected);
+        self.reactor.set_timer(Timer::Thumbnails, Duration::ZERO);
+        if self.has_damage {
+            for &wid in &self.redirected {
+                if !self.damages.contains_key(&wid)
+                    && let Ok(damage) = request_damage_watch(self.conn, wid)
+                {
+                    self.damages.insert(wid, damage);
+                }
+            }
+        }
+    }
+    /// the contents of the window changed, it's captured again with the others that change
+    /// meanwhile
+    fn damage(&mut self, wid: Window, damage: Damage) -> Result<()> {
+        request_damage_subtract(self.conn, damage)?;
+        if self.damaged.is_empty() {
+            self.reactor
+                .set_timer(Timer::Thumbnails, THUMBNAIL_INTERVAL);
+        }
+        self.damaged.insert(wid);
+        Ok(())
+    }
+    fn recapture_damaged(&mut self) {
+        for wid in std::mem::take(&mut self.damaged) {
+            if let Ok(thumbnail) = get_window_thumbnail(self.conn, wid, THUMBNAIL_SIZE) {
+                self.icons.set_thumbnail(wid, thumbnail);
+                self.changed.icons |= true;
+            }
+        }
+    }
+    /// stops the damage reports while the list is hidden, the windows are captured when it's shown
+    fn unwatch_damage(&mut self) -> Result<()> {
+        for (_, damage) in self.damages.drain() {
+            self.conn.damage_destroy(damage)?;
+        }
+        self.damaged.clear();
+        self.reactor.cancel_timer(Timer::Thumbnails);
+        Ok(())
+    }
+    /// queries the providers and tmux again in the background, see `apply_virtual`
+    pub(crate) fn sync_providers(&mut self) {
+        let (conn, screen, atoms) = (self.conn, &self.screen, self.atoms);
+        let conf = &self.themes[self.scheme as usize];
+        if !self.populated || (conf.providers.is_empty() && !conf.tmux) {
+            return;
+        }
+        self.virtual_refresh
+            .start(VirtualQuery::new(conn, screen, atoms, conf));
+        self.reactor.set_timer(Timer::Providers, PROVIDER_POLL);
+    }
+    /// updates the list with the results of the last query, if it finished
+    fn apply_virtual(&mut self) {
+        let (conn, atoms) = (self.conn, self.atoms);
+        let Some(provided) = self.virtual_refresh.take() else {
+            return;
+        };
+        let before_len = self.tasks.rows();
+        let (removed, added) = self.tasks.update_virtual(provided);
+        self.print_changes(&removed, &added);
+        self.changed.size |= before_len != self.tasks.rows();
+        self.changed.focus |= true;
+        if self.conf().show_icons {
+            self.icons.set_icons(conn, atoms, &self.tasks);
+            self.changed.icons |= true;
+        }
+    }
+    pub(crate) fn tick(&mut self, timer: Timer) -> Result<()> {
+        let (conn, screen, atoms) = (self.conn, &self.screen, self.atoms);
+        match timer {
+            Timer::Tick => {
+                self.reactor.set_timer(Timer::Tick, TICK_INTERVAL);
+                let conf = &self.themes[self.scheme as usize];
+                if conf.ping {
+                    self.changed.focus |= self.tasks.expire_pings(PING_TIMEOUT);
+                    self.tasks.ping(conn, atoms);
+                }
+                // the selection has rested long enough on a title that doesn't fit its cell
+                if conf.show_tooltips
+                    && matches!(self.state, ListState::Shown { .. })
+                    && !self.tooltip.is_mapped
+                    && self.selected_at.elapsed() >= TOOLTIP_DELAY
+                    && let Some(cell) = self.overflowing
+                    && let Some(task) = self.tasks.selected()
+                {
+                    let g = self.geometry;
+                    let anchor = Area::new(g.x + cell.x, g.y + cell.y, cell.w, cell.h);
+                    let (tr, depth) = (&mut self.tr, self.depth);
+                    self.tooltip
+                        .show(conn, screen, conf, tr, &task.title, anchor, depth)?;
+                }
+            }
+            Timer::Clock => self.reactor.set_timer(Timer::Clock, CLOCK_INTERVAL),
+            Timer::Redraw => self.changed.window = true,
+            Timer::Enter => self.step_enter()?,
+            Timer::Thumbnails => self.recapture_damaged(),
+            Timer::Notice if !self.state.is_shown() => self.tooltip.hide(conn)?,
+            Timer::Notice => {}
+            Timer::Providers => {
+                self.apply_virtual();
+                if self.virtual_refresh.is_running() {
+                    self.reactor.set_timer(Timer::Providers, PROVIDER_POLL);
+                }
+            }
+            Timer::Activation => {
+                if let Some((wid, retried)) = self.activating.take()
+                    && !confirm_activation(conn, screen, atoms, wid, retried)?
+                {
+                    self.activating = Some((wid, true));
+                    self.reactor
+                        .set_timer(Timer::Activation, ACTIVATION_TIMEOUT);
+                }
+            }
+        }
+        Ok(())
+    }
+    /// the exit code when the event ends a oneshot list
+    pub(crate) fn handle_event(&mut self, event: Event) -> Result<Option<ExitCode>> {
+        let (conn, screen, atoms) = (self.conn, &self.screen, self.atoms);
+        match event {
+            Event::Expose(e) if e.window == self.tooltip.window => {
+                self.tooltip.redraw(conn, self.depth)?
+            }
+            Event::Expose(_) => self.changed.window |= true,
+            Event::ClientMessage(e)
+                if e.type_ == atoms.WM_PROTOCOLS && e.data.as_data32()[0] == atoms._NET_WM_PING =>
+            {
+                self.changed.focus |= self.tasks.pong(e.data.as_data32()[2]);
+            }
+            Event::ClientMessage(e)
+                if e.type_ == atoms.MANAGER
+                    && e.data.as_data32()[1] == self.xsettings_selection =>
+            {
+                self.xsettings_owner = watch_xsettings(conn, self.xsettings_selection)
+                    .ok()
+                    .flatten();
+                self.changed.xsettings |= true;
+            }
+            Event::DestroyNotify(e) if Some(e.window) == self.xsettings_owner => {
+                self.xsettings_owner = None;
+            }
+            Event::Error(e) => {
+                if e.request_name == Some("GrabKey") {
+                    eprintln!();
+                    return Err(
+                        "failed to grab keys, another program is probably grabbing them".into(),
+                    );
+                }
+                eprintln!("[WARNING] {e:?}")
+            }
+            Event::MapNotify(_) | Event::UnmapNotify(_) | Event::DestroyNotify(_)
+                if self.source.use_tree || self.source.tracks_override_redirect() =>
+            {
+                self.sync_tasks();
+            }
+            Event::ConfigureNotify(e) => {
+                if self.source.stacking_tree
+                    && e.event == screen.root
+                    && let Ok(wids) = self.list_stacking()
+                {
+                    self.tasks.restack(wids);
+                    self.changed.focus |= true;
+                }
+                let client = if !self.tasks.tracks_geometry() || self.tasks.contains(e.window) {
+                    Some(e.window)
+                } else {
+                    *self
+                        .frames
+                        .entry(e.window)
+                        .or_insert_with(|| get_frame_client(conn, atoms, e.window).ok().flatten())
+                };
+                if self.tasks.tracks_geometry()
+                    && let Some(wid) = client.filter(|wid| self.tasks.contains(*wid))
+                {
+                    let before_len = self.tasks.rows();
+                    let geometry = get_window_geometry(conn, atoms, wid).ok();
+                    self.tasks.update_geometry(wid, geometry);
+                    self.changed.size |= before_len != self.tasks.rows();
+                    self.changed.focus |= true;
+                }
+            }
+            Event::DamageNotify(e) if self.damages.get(&e.drawable) == Some(&e.damage) => {
+                self.damage(e.drawable, e.damage)?;
+            }
+            Event::ReparentNotify(_) => {
+                // the frames are created, or reused, by reparenting clients
+                self.frames.clear();
+            }
+            Event::RandrScreenChangeNotify(e) => {
+                // the sizes are reported before the rotation
+                let rotated = e
+                    .rotation
+                    .intersects(randr::Rotation::ROTATE90 | randr::Rotation::ROTATE270);
+                let (w, h, mw, mh) = if rotated {
+                    (e.height, e.width, e.mheight, e.mwidth)
+                } else {
+                    (e.width, e.height, e.mwidth, e.mheight)
+                };
+                let size = (w, h, mw, mh);
+                let screen = &mut self.screen;
+                let before = (
+                    screen.width_in_pixels,
+                    screen.height_in_pixels,
+                    screen.width_in_millimeters,
+                    screen.height_in_millimeters,
+                );
+                if size != before {
+                    screen.width_in_pixels = w;
+                    screen.height_in_pixels = h;
+                    screen.width_in_millimeters = mw;
+                    screen.height_in_millimeters = mh;
+                    self.changed.screen = true;
+                }
+                self.update_monitors();
+            }
+            Event::RandrNotify(_) => self.update_monitors(),
+            Event::PropertyNotify(e) => self.handle_property(e)?,
+            Event::XinputKeyRelease(e)
+                if e.detail == self.kb.key_mod.into()
+                    && self.state.is_switching()
+                    && !self.oneshot =>
+            {
+                if self.state.palette().is_some() {
+                    self.run_palette_action();
+                    self.tasks.thaw();
+                    self.hide()?;
+                } else {
+                    self.switch()?;
+                }
+            }
+            Event::MotionNotify(e) if e.event == self.window => {
+                let size = (self.geometry.w, self.geometry.h);
+                let pos = (e.event_x as f32, e.event_y as f32);
+                let shown = self.state.palette().unwrap_or(&self.tasks);
+                let wid = task_at(self.conf(), shown, size, pos);
+                if wid != self.hovered {
+                    if wid.is_some() != self.hovered.is_some() {
+                        let cursor = if wid.is_some() {
+                            self.hand_cursor
+                        } else {
+                            x11rb::NONE
+                        };
+                        let aux = ChangeWindowAttributesAux::new().cursor(cursor);
+                        conn.change_window_attributes(self.window, &aux)?;
+                    }
+                    self.hovered = wid;
+                    self.changed.focus |= true;
+                }
+            }
+            Event::LeaveNotify(e) if e.event == self.window && self.hovered.is_some() => {
+                let aux = ChangeWindowAttributesAux::new().cursor(x11rb::NONE);
+                conn.change_window_attributes(self.window, &aux)?;
+                self.hovered = None;
+                self.changed.focus |= true;
+            }
+            Event::ButtonPress(e) if e.event == self.window && e.detail == 1 => {
+                let size = (self.geometry.w, self.geometry.h);
+                let pos = (e.event_x as f32, e.event_y as f32);
+                let conf = &self.themes[self.scheme as usize];
+                if let Some(p) = self.state.palette_mut() {
+                    if let Some(wid) = task_at(conf, p, size, pos) {
+                        p.select_by_wid(wid);
+                        self.run_palette_action();
+                        self.hovered = None;
+                        self.changed.size |= true;
+                        self.changed.focus |= true;
+                    }
+                } else if let Some(wid) = task_at(conf, &self.tasks, size, pos) {
+                    self.tasks.select_by_wid(wid);
+                    self.selected_at = Instant::now();
+                    self.tooltip.hide(conn)?;
+                    self.changed.focus |= true;
+                    // in oneshot mode the click only selects, `key_select` confirms
+                    if !self.oneshot {
+                        self.switch()?;
+                    }
+                }
+            }
+            Event::KeyPress(e)
+                if self.oneshot || e.state & self.kb.modifier.bits() != KeyButMask::from(0u16) =>
+            {
+                return self.handle_key(e);
+            }
+            _ => {}
+        }
+        Ok(None)
+    }
+    /// the changes of the windows, of the desktop and of the settings
+    pub(crate) fn handle_property(&mut self, e: PropertyNotifyEvent) -> Result<()> {
+        let (conn, screen, atoms) = (self.conn, &self.screen, self.atoms);
+        let conf = &self.themes[self.scheme as usize];
+        if Some(e.window) == self.xsettings_owner && e.atom == atoms._XSETTINGS_SETTINGS {
+            self.changed.xsettings |= true;
+        } else if e.window == screen.root && e.atom == atoms.RESOURCE_MANAGER {
+            self.changed.resources |= true;
+        } else if e.atom == atoms._NET_CLIENT_LIST {
+            self.sync_tasks();
+        } else if e.atom == atoms._NET_CLIENT_LIST_STACKING && conf.sort == Sort::Stacking {
+            if let Ok(wids) = self.list_stacking() {
+                self.tasks.restack(wids);
+                self.changed.focus |= true;
+            }
+        } else if e.atom == atoms._NET_ACTIVE_WINDOW {
+            match get_active_window(conn, screen, atoms) {
+                Ok(Some(wid)) => {
+                    if self.args.events {
+                        print_event("focused", wid, self.tasks.get_task_by_id(wid));
+                    }
+                    self.tasks.focus_by_wid(wid);
+                    self.changed.focus |= true;
+                    self.update_passthrough(Some(wid))?;
+                    // the tmux windows follow the focused terminal, they're ready before the next
+                    // switch starts
+                    if self.conf().tmux && !self.state.is_shown() {
+                        self.sync_providers();
+                    }
+                }
+                Ok(None) => {
+                    self.tasks.unfocus();
+                    self.update_passthrough(None)?;
+                }
+                Err(_) => {}
+            }
+        } else if e.window == screen.root && e.atom == atoms._NET_WORKAREA {
+            // a panel was added, removed or resized
+            self.output = self.choose_output();
+            self.changed.size |= true;
+        } else if e.atom == atoms._NET_CURRENT_DESKTOP {
+            let desktop = get_current_desktop(conn, screen, atoms).ok().flatten();
+            self.tasks.set_desktop(desktop);
+            self.changed.size |= true;
+            self.changed.focus |= true;
+        } else if e.atom == atoms._NET_DESKTOP_NAMES && conf.desktop_sections {
+            let names = get_desktop_names(conn, screen, atoms).unwrap_or_default();
+            self.tasks.set_desktop_names(names, true);
+            self.changed.focus |= true;
+        } else if e.atom == atoms._NET_WM_DESKTOP && self.tasks.contains(e.window) {
+            let desktop = get_window_desktop(conn, atoms, e.window).ok().flatten();
+            self.tasks.update_desktop(e.window, desktop);
+            self.changed.size |= true;
+            self.changed.focus |= true;
+        } else if e.window == screen.root
+            && e.atom == atoms._GOTO_SUSPENDED
+            && let Ok(Some(suspended)) = get_suspended(conn, screen, atoms)
+        {
+            // written by `--suspend`, or by goto itself
+            self.set_suspended(suspended)?;
+        } else if e.window == screen.root && e.atom == atoms._GOTO_ALIAS {
+            // `--alias-class` saved an alias
+            self.tasks
+                .set_saved_aliases(load_aliases(&State::load(self.args.state_path())));
+            self.changed.title |= true;
+        } else if e.atom == atoms._GOTO_ALIAS {
+            let alias = get_window_alias(conn, atoms, e.window).ok().flatten();
+            self.tasks.update_alias(e.window, alias);
+            self.changed.title |= true;
+        } else if (e.atom == atoms._NET_WM_NAME || e.atom == atoms.WM_NAME)
+            && let Ok(title) = get_window_title(conn, atoms, e.window)
+        {
+            self.tasks.update_title(e.window, title);
+            self.changed.title |= true;
+            if self.args.events
+                && let Some(task) = self.tasks.get_task_by_id(e.window)
+            {
+                print_event("retitled", e.window, Some(task));
+            }
+        } else if e.atom == atoms._NET_FRAME_EXTENTS
+            && self.tasks.tracks_geometry()
+            && self.tasks.contains(e.window)
+        {
+            let geometry = get_window_geometry(conn, atoms, e.window).ok();
+            self.tasks.update_geometry(e.window, geometry);
+            self.changed.focus |= true;
+        } else if e.atom == atoms.WM_CLASS
+            && let Ok(class) = get_window_class(conn, atoms, e.window)
+            && self.tasks.update_class(e.window, class)
+        {
+            // terminals and wine can change it once mapped, the icon follows
+            if conf.show_icons {
+                self.icons.set_icons(conn, atoms, &self.tasks);
+                self.changed.icons |= true;
+            }
+            self.changed.title |= true;
+        } else if (e.atom == atoms._NET_WM_ICON_NAME || e.atom == atoms.WM_ICON_NAME)
+            && self.tasks.contains(e.window)
+        {
+            let icon_name = get_window_icon_name(conn, atoms, e.window).ok().flatten();
+            self.tasks.update_icon_name(e.window, icon_name);
+            self.changed.title |= true;
+        } else if e.atom == atoms.WM_WINDOW_ROLE && self.tasks.contains(e.window) {
+            let role = get_window_role(conn, atoms, e.window).ok().flatten();
+            self.tasks.update_role(e.window, role);
+            self.changed.title |= true;
+        } else if e.atom == atoms._NET_WM_VISIBLE_NAME && self.tasks.contains(e.window) {
+            let visible_name = get_window_visible_name(conn, atoms, e.window)
+                .ok()
+                .flatten();
+            self.tasks.update_visible_name(e.window, visible_name);
+            self.changed.title |= true;
+        } else if e.atom == atoms._NET_WM_ICON
+            && conf.show_icons
+            && let Some(task) = self.tasks.get_task_by_id(e.window)
+        {
+            self.icons.set_window_icon(conn, atoms, task);
+            self.changed.icons |= true;
+        }
+        Ok(())
+    }
+    /// the bindings, pressed with `key_mod` in the daemon, the exit code when they end a oneshot
+    /// list
+    pub(crate) fn handle_key(&mut self, e: KeyPressEvent) -> Result<Option<ExitCode>> {
+        let (conn, atoms) = (self.conn, self.atoms);
+        let key = e.detail;
+        if !self.populated {
+            self.populated = true;
+            self.sync_tasks();
+            if let Ok(Some(wid)) = get_active_window(conn, &self.screen, atoms) {
+                self.tasks.focus_by_wid(wid);
+            }
+            self.tasks.mark_seen();
+        }
+        // the providers are queried when a new switch starts, the list shows the last results
+        // until they're back
+        if (key == self.kb.key_next || key == self.kb.key_prev) && !self.state.is_switching() {
+            self.sync_providers();
+        }
+        let kb = &self.kb;
+        if Some(key) == kb.key_suspend {
+            self.set_suspended(!self.suspended)?;
+        } else if let Some(p) = self.state.palette_mut() {
+            if key == kb.key_next {
+                p.select_older();
+            } else if key == kb.key_prev {
+                p.select_newer();
+            } else if key == kb.key_palette || (key == kb.key_select && self.oneshot) {
+                // back to the tasks, to see the change
+                self.run_palette_action();
+                self.changed.size |= true;
+            } else if key == kb.key_quit {
+                self.state.close_palette();
+                self.changed.size |= true;
+            }
+            self.hovered = None;
+            self.changed.focus |= true;
+        } else if let Some(mut query) = self.tasks.query().map(str::to_string)
+            && ![kb.key_next, kb.key_prev, kb.key_select].contains(&key)
+        {
+            // the keys type the query, `key_quit` goes back to the whole list
+            if key == kb.key_quit {
+                self.tasks.set_query(None);
+            } else if kb.keysym(key, e.state) == Keysym::BackSpace {
+                query.pop();
+                self.tasks.set_query(Some(query));
+            } else if let Some(c) = kb.key_char(key, e.state) {
+                query.push(c);
+                self.tasks.set_query(Some(query));
+            }
+            self.tooltip.hide(conn)?;
+            self.hovered = None;
+            self.changed.size |= true;
+            self.changed.focus |= true;
+        } else if key == kb.key_search && self.state.is_shown() {
+            self.tasks.set_query(Some(String::new()));
+            self.changed.focus |= true;
+        } else if key == kb.key_move_overlay && self.state.is_shown() {
+            self.run_action(Some(Action::MoveList));
+            self.tooltip.hide(conn)?;
+            self.changed.size |= true;
+            self.changed.focus |= true;
+        } else if key == kb.key_palette && self.state.is_shown() {
+            let p = build_palette(self.conf(), &self.tasks);
+            if self.conf().show_icons {
+                self.icons.set_icons(conn, atoms, &p);
+            }
+            self.state.open_palette(p);
+            self.tooltip.hide(conn)?;
+            self.hovered = None;
+            self.changed.size |= true;
+            self.changed.focus |= true;
+        } else if key == kb.key_next || key == kb.key_prev {
+            self.state.press();
+            self.tasks.freeze();
+            if key == kb.key_next {
+                self.tasks.select_older();
+            } else {
+                self.tasks.select_newer();
+            }
+            self.selected_at = Instant::now();
+            self.tooltip.hide(conn)?;
+            self.changed.focus |= true;
+            if !self.conf().quick_switch || self.state.presses() > 1 {
+                self.show()?;
+            }
+        } else if key == kb.key_kill && self.state.is_shown() {
+            // closed windows are forgotten
+            if let Some(t) = self.tasks.selected()
+                && t.closed.is_some()
+            {
+                let wid = t.wid;
+                self.tasks.untrack(wid);
+                self.changed.focus |= true;
+                self.changed.size |= true;
+            } else if let Some(t) = self.tasks.selected()
+                && !t.is_virtual()
+                // hung windows won't handle WM_DELETE_WINDOW
+                && if t.responsive {
+                    request_window_close(conn, atoms, t.wid).is_ok()
+                } else {
+                    conn.kill_client(t.wid).is_ok()
+                }
+            {
+                self.changed.focus |= true;
+                self.changed.size |= true;
+            }
+        } else if key == kb.key_toggle_scope && self.state.is_shown() {
+            self.tasks.toggle_scope();
+            self.changed.focus |= true;
+            self.changed.size |= true;
+        } else if key == kb.key_toggle_monitor && self.state.is_shown() {
+            self.tasks.toggle_monitor();
+            self.changed.focus |= true;
+            self.changed.size |= true;
+        } else if key == kb.key_select && self.oneshot {
+            return self.confirm().map(Some);
+        } else if key == kb.key_quit && self.oneshot {
+            restore_focus(conn, &self.screen, atoms, self.prior_focus)?;
+            return Ok(Some(ExitCode::from(EXIT_CANCELLED)));
+        } else if key == kb.key_quit && self.state.is_shown() {
+            self.cancel()?;
+        }
+        Ok(None)
+    }
+    /// ends a oneshot list by activating the selection, or printing it for dmenu
+    fn confirm(&mut self) -> Result<ExitCode> {
+        let (conn, screen, atoms) = (self.conn, &self.screen, self.atoms);
+        let conf = &self.themes[self.scheme as usize];
+        let Some(task) = self.tasks.selected() else {
+            restore_focus(conn, screen, atoms, self.prior_focus)?;
+            return Ok(ExitCode::from(EXIT_NO_CANDIDATES));
+        };
+        conn.ungrab_keyboard(x11rb::CURRENT_TIME)?;
+        conn.unmap_window(self.window)?;
+        if self.args.mode == Mode::Dmenu {
+            restore_focus(conn, screen, atoms, self.prior_focus)?;
+        } else {
+            let target = request_task_activation(conn, screen, atoms, conf, task)?;
+            if conf.focus_follows_mouse && !task.is_virtual() {
+                let _ = request_pointer_warp(conn, target.unwrap_or(task.wid));
+            }
+            conn.flush()?;
+            if let Some(wid) = target {
+                wait_for_activation(conn, screen, atoms, wid)?;
+            }
+        }
+        println!("{:#x} {}", task.wid, task.class.1);
+        Ok(ExitCode::SUCCESS)
+    }
+    /// rescales the themes when the desktop changed the scaling or the font, or the screen was
+    /// resized, which changes the relative sizes and the dpi without one set, and switches them
+    /// when it changed the color scheme
+    fn apply_settings(&mut self) -> Result<()> {
+        let conn = self.conn;
+        let mut rescaled = self.changed.screen;
+        let desktop_size = desktop_font_size(&self.xsettings);
+        let mut new_scheme = self.scheme;
+        // `xrdb` loading another `Xft.dpi`, the xsettings dpi still wins when there is one
+        if self.changed.resources
+            && let Ok(db) = x11rb::resource_manager::new_from_default(conn)
+        {
+            let xft_dpi = |db: &Database| db.get_value::<f32>("Xft.dpi", "").ok().flatten();
+            rescaled |= xft_dpi(&db) != xft_dpi(&self.res_db);
+            self.res_db = db;
+        }
+        if self.changed.xsettings
+            && let Some(owner) = self.xsettings_owner
+            && let Ok(settings) = get_xsettings(conn, self.atoms, owner)
+        {
+            rescaled |= ["Xft/DPI", "Gtk/FontName"]
+                .iter()
+                .any(|key| settings.get(*key) != self.xsettings.get(*key));
+            if self.themed {
+                new_scheme = color_scheme(&settings);
+            }
+            self.xsettings = settings;
+        }
+        if rescaled {
+            let dpi = get_dpi(&self.res_db, &self.screen, &self.xsettings)?;
+            let from = (self.dpi, desktop_size);
+            let to = (dpi, desktop_font_size(&self.xsettings));
+            let mut themes = self.themes.clone();
+            for theme in &mut themes {
+                theme.rescale(from, to);
+            }
+            // the fonts may be gone since, the old ones are kept at the old size then
+            match TextRenderer::new(&themes[new_scheme as usize]) {
+                Ok(tr) => {
+                    self.tr = tr;
+                    self.themes = themes;
+                    self.dpi = dpi;
+                }
+                Err(e) => eprintln!("[WARNING] failed to rescale the text: {e}"),
+            }
+        }
+        if rescaled || new_scheme != self.scheme {
+            self.scheme = new_scheme;
+            self.tooltip.hide(conn)?;
+            self.changed.size = true;
+            self.changed.window = true;
+        }
+        Ok(())
+    }
+    /// outputs were connected, disconnected or moved
+    fn update_monitors(&mut self) {
+        let monitors = get_monitors(self.conn, &self.screen).unwrap_or_default();
+        let labels = self.conf().monitor_labels;
+        self.tasks.set_monitors(monitors, labels);
+        self.output = self.choose_output();
+        self.changed.size |= true;
+        self.changed.focus |= true;
+    }
+    /// moves and resizes the list after the changes, it's hidden when there is nothing left to
+    /// show, which ends a oneshot list
+    fn relayout(&mut self) -> Result<Option<ExitCode>> {
+        if !self.changed.size {
+            return Ok(None);
+        }
+        let conf = &self.themes[self.scheme as usize];
+        let shown = self.state.palette_mut().unwrap_or(&mut self.tasks);
+        let Some(g) = compute_window_geometry(conf, self.output, self.origin, shown.rows()) else {
+            self.hide()?;
+            if self.oneshot {
+                let (conn, screen, atoms) = (self.conn, &self.screen, self.atoms);
+                restore_focus(conn, screen, atoms, self.prior_focus)?;
+                return Ok(Some(ExitCode::from(EXIT_NO_CANDIDATES)));
+            }
+            return Ok(None);
+        };
+        self.geometry = g;
+        shown.set_layout(compute_layout(conf, self.output, shown.rows()));
+        shown.set_page_size(compute_page_size(conf, self.output, shown.rows()));
+        request_window_move(self.conn, self.window, g)?;
+        self.frame.resize(g.w as u32, g.h as u32);
+        self.changed.window = true;
+        Ok(None)
+    }
+    /// the selected task while the list is shown, on `_GOTO_SELECTION` of the root window
+    fn publish_selection(&mut self) -> Result<()> {
+        let selection = self
+            .tasks
+            .selected()
+            .filter(|_| self.state.is_shown())
+            .map(|task| (task.wid, self.tasks.label(task).into_owned()));
+        if selection != self.published {
+            request_selection_publish(self.conn, &self.screen, self.atoms, selection.as_ref())?;
+            self.published = selection;
+        }
+        Ok(())
+    }
+    fn redraw(&mut self, handling: Instant) -> Result<()> {
+        let conf = &self.themes[self.scheme as usize];
+        if !self.state.is_shown() {
+            return Ok(());
+        }
+        let shown = self.state.palette_mut().unwrap_or(&mut self.tasks);
+        if shown.is_empty() || !self.changed.redraw() {
+            return Ok(());
+        }
+        // throttled, the last change is drawn when the interval expires
+        let wait = conf.redraw_interval.saturating_sub(self.sent_at.elapsed());
+        if !wait.is_zero() {
+            self.reactor.set_timer(Timer::Redraw, wait);
+            return Ok(());
+        }
+        shown.scroll_into_view();
+        let events_time = handling.elapsed();
+        let start = Instant::now();
+        let (frame, tr) = (&mut self.frame, &mut self.tr);
+        self.overflowing = draw_list(frame, conf, shown, self.hovered, tr, &mut self.icons);
+        if self.overflowing.is_none() {
+            self.tooltip.hide(self.conn)?;
+        }
+        if conf.debug_overlay {
+            draw_frame_times(frame, conf, tr, self.draw_time, events_time);
+        }
+        frame.reduce_colors(conf.color_depth, conf.dither);
+        send_frame(self.conn, self.window, self.gc, frame, self.depth)?;
+        self.draw_time = start.elapsed();
+        self.sent_at = Instant::now();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::process::ExitCode;
+    use std::time::Instant;
+
+    use x11rb::protocol::xproto::KeyButMask;
+    use xkbcommon::xkb::Keysym;
+
+    use super::ListState;
+    use crate::EXIT_CANCELLED;
+    use crate::cli::Mode;
+    use crate::mock;
+    use crate::mock::WINDOWS;
+    use crate::mock::desktop;
+    use crate::tasks::TaskList;
+
+    fn is_activation(server: &mock::MockServer) -> impl Fn(&mock::Call) -> bool {
+        let active = server.atom("_NET_ACTIVE_WINDOW");
+        move |call| matches!(call, mock::Call::ClientMessage(_, type_, _) if *type_ == active)
+    }
+
+    #[test]
+    fn daemon_switches_on_modifier_release() {
+        let (server, conn) = desktop();
+        let daemon = mock::spawn(Mode::Daemon, conn);
+        let window = match server.wait_for(|call| matches!(call, mock::Call::CreateWindow(_))) {
+            mock::Call::CreateWindow(window) => window,
+            _ => unreachable!(),
+        };
+        server.wait_for(|call| matches!(call, mock::Call::GrabKey(_)));
+        assert!(!server.calls().contains(&mock::Call::MapWindow(window)));
+
+        server.key_press(Keysym::Tab, KeyButMask::MOD1);
+        server.wait_for(|call| *call == mock::Call::MapWindow(window));
+        server.wait_for(|call| *call == mock::Call::PutImage(window));
+        server.key_release(Keysym::Alt_L);
+        let activation = server.wait_for(is_activation(&server));
+        let mock::Call::ClientMessage(target, _, _) = activation else {
+            unreachable!()
+        };
+        assert_eq!(target, WINDOWS[1]);
+        server.wait_for(|call| *call == mock::Call::UnmapWindow(window));
+        assert_eq!(
+            server.property(mock::ROOT, "_NET_ACTIVE_WINDOW"),
+            Some(WINDOWS[1])
+        );
+
+        // the list follows the new focus, the next switch goes back
+        server.clear_calls();
+        server.key_press(Keysym::Tab, KeyButMask::MOD1);
+        server.key_release(Keysym::Alt_L);
+        let activation = server.wait_for(is_activation(&server));
+        assert!(matches!(activation, mock::Call::ClientMessage(wid, _, _) if wid == WINDOWS[0]));
+
+        drop(server);
+        assert!(daemon.join().unwrap().is_err());
+    }
+
+    #[test]
+    fn selection_published_while_shown() {
+        let (server, conn) = desktop();
+        let daemon = mock::spawn(Mode::Daemon, conn);
+        server.wait_for(|call| matches!(call, mock::Call::GrabKey(_)));
+        let selection = server.atom("_GOTO_SELECTION");
+        server.key_press(Keysym::Tab, KeyButMask::MOD1);
+        server.wait_for(|call| *call == mock::Call::ChangeProperty(mock::ROOT, selection));
+        let published = server.text(mock::ROOT, "_GOTO_SELECTION");
+        assert_eq!(published, Some(format!("{:#x}\tsecond", WINDOWS[1])));
+        server.key_press(Keysym::Escape, KeyButMask::MOD1);
+        server.wait_for(|call| *call == mock::Call::DeleteProperty(mock::ROOT, selection));
+        assert_eq!(server.text(mock::ROOT, "_GOTO_SELECTION"), None);
+
+        drop(server);
+        assert!(daemon.join().unwrap().is_err());
+    }
+
+    #[test]
+    fn iconless_windows_are_captured() {
+        let (server, conn) = desktop();
+        server.set_text(
+            WINDOWS[1],
+            "WM_CLASS",
+            "STRING",
+            "goto-no-icon\0GotoNoIcon\0",
+        );
+        server.set_property(WINDOWS[0], "_NET_WM_ICON", "CARDINAL", &[1, 1, 0xff00ff00]);
+        // the mock server has no render extension, which turns the icons off
+        let daemon = mock::spawn_with(Mode::Daemon, conn, "low_bandwidth: false\n");
+        server.wait_for(|call| matches!(call, mock::Call::GrabKey(_)));
+        server.key_press(Keysym::Tab, KeyButMask::MOD1);
+        server.wait_for(|call| matches!(call, mock::Call::PutImage(_)));
+        let calls = server.calls();
+        assert!(calls.contains(&mock::Call::GetImage(WINDOWS[1])));
+        assert!(!calls.contains(&mock::Call::GetImage(WINDOWS[0])));
+
+        drop(server);
+        assert!(daemon.join().unwrap().is_err());
+    }
+
+    #[test]
+    fn typed_query_selects_the_best_match() {
+        let (server, conn) = desktop();
+        let daemon = mock::spawn(Mode::Daemon, conn);
+        server.wait_for(|call| matches!(call, mock::Call::GrabKey(_)));
+        server.key_press(Keysym::Tab, KeyButMask::MOD1);
+        server.wait_for(|call| *call == mock::Call::GrabKeyboard(mock::ROOT));
+        // `key_kill` types a `K` in the query instead of closing the window
+        for key in [
+            Keysym::slash,
+            Keysym::f,
+            Keysym::K,
+            Keysym::BackSpace,
+            Keysym::i,
+        ] {
+            server.key_press(key, KeyButMask::MOD1);
+        }
+        server.key_release(Keysym::Alt_L);
+        let activation = server.wait_for(is_activation(&server));
+        assert!(matches!(activation, mock::Call::ClientMessage(wid, _, _) if wid == WINDOWS[0]));
+        assert!(
+            !server
+                .calls()
+                .iter()
+                .any(|call| matches!(call, mock::Call::ClientMessage(_, _, _))
+                    && *call != activation)
+        );
+
+        drop(server);
+        assert!(daemon.join().unwrap().is_err());
+    }
+
+    #[test]
+    fn list_states() {
+        // shown by the second press with `quick_switch`, ended by the release of the modifier
+        let mut state = ListState::Hidden;
+        state.press();
+        assert!(state.is_switching() && !state.is_shown());
+        state.press();
+        assert!(state.show(None));
+        assert!(!state.show(None));
+        assert_eq!(state.presses(), 2);
+        state.press();
+        assert_eq!(state.presses(), 3);
+        state = ListState::Hidden;
+        assert!(!state.is_switching() && state.presses() == 0);
+
+        // the palette replaces the tasks and gives them back, with the animation still running
+        let start = Instant::now();
+        state.press();
+        state.show(Some(start));
+        state.open_palette(TaskList::new());
+        assert!(state.is_shown() && state.palette().is_some());
+        assert_eq!(state.entering(), Some(start));
+        // the switching keys move in the palette
+        state.press();
+        assert_eq!(state.presses(), 0);
+        assert!(state.close_palette().is_some());
+        assert!(state.palette().is_none() && state.is_shown());
+        assert!(state.close_palette().is_none());
+        state.set_entering(None);
+        assert_eq!(state.entering(), None);
+
+        // nothing to open a palette on while hidden
+        let mut state = ListState::Hidden;
+        state.open_palette(TaskList::new());
+        assert!(!state.is_shown());
+        state.set_entering(Some(start));
+        assert_eq!(state.entering(), None);
+    }
+
+    #[test]
+    fn daemon_quit_activates_nothing() {
+        let (server, conn) = desktop();
+        let daemon = mock::spawn(Mode::Daemon, conn);
+        server.wait_for(|call| matches!(call, mock::Call::GrabKey(_)));
+        server.key_press(Keysym::Tab, KeyButMask::MOD1);
+        let window = match server.wait_for(|call| matches!(call, mock::Call::MapWindow(_))) {
+            mock::Call::MapWindow(window) => window,
+            _ => unreachable!(),
+        };
+        server.key_press(Keysym::Escape, KeyButMask::MOD1);
+        server.wait_for(|call| *call == mock::Call::UnmapWindow(window));
+        server.key_release(Keysym::Alt_L);
+
+        // a later switch still works, so the release above went through
+        server.key_press(Keysym::Tab, KeyButMask::MOD1);
+        server.wait_for(|call| *call == mock::Call::MapWindow(window));
+        assert!(!server.calls().iter().any(is_activation(&server)));
+
+        drop(server);
+        assert!(daemon.join().unwrap().is_err());
+    }
+
+    #[test]
+    fn oneshot_gives_the_focus_back_when_cancelled() {
+        let (server, conn) = desktop();
+        let oneshot = mock::spawn(Mode::Oneshot, conn);
+        let window = match server.wait_for(|call| matches!(call, mock::Call::GrabKeyboard(_))) {
+            mock::Call::GrabKeyboard(window) => window,
+            _ => unreachable!(),
+        };
+        assert!(server.calls().contains(&mock::Call::MapWindow(window)));
+        assert!(server.calls().contains(&mock::Call::SetInputFocus(window)));
+        assert!(
+            !server
+                .calls()
+                .iter()
+                .any(|call| matches!(call, mock::Call::GrabKey(_)))
+        );
+
+        server.key_press(Keysym::Escape, KeyButMask::from(0u16));
+        let code = oneshot.join().unwrap().unwrap();
+        assert_eq!(code, ExitCode::from(EXIT_CANCELLED));
+        assert!(!server.calls().iter().any(is_activation(&server)));
+        let calls = server.calls();
+        let grabbed = calls
+            .iter()
+            .position(|call| matches!(call, mock::Call::GrabKeyboard(_)));
+        assert!(calls[grabbed.unwrap()..].contains(&mock::Call::SetInputFocus(mock::ROOT)));
+    }
+
+    #[test]
+    fn oneshot_activates_the_selection() {
+        let (server, conn) = desktop();
+        let oneshot = mock::spawn(Mode::Oneshot, conn);
+        server.wait_for(|call| matches!(call, mock::Call::GrabKeyboard(_)));
+        // the list opens on the previous window, the next one wraps around to the current
+        server.key_press(Keysym::Tab, KeyButMask::from(0u16));
+        server.key_press(Keysym::Return, KeyButMask::from(0u16));
+        assert_eq!(oneshot.join().unwrap().unwrap(), ExitCode::SUCCESS);
+        let activation = server.wait_for(is_activation(&server));
+        assert!(matches!(activation, mock::Call::ClientMessage(wid, _, _) if wid == WINDOWS[0]));
+        assert!(server.calls().contains(&mock::Call::UngrabKeyboard));
+    }
+}
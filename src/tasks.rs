@@ -0,0 +1,2126 @@
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::fs::read_to_string;
+use std::os::unix::process::CommandExt;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::sync::OnceLock;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering;
+use std::sync::mpsc;
+use std::time::Duration;
+use std::time::Instant;
+
+use rustix::process::Pid;
+use rustix::process::Signal;
+use rustix::process::kill_process_group;
+use x11rb::protocol::xproto::ChangeWindowAttributesAux;
+use x11rb::protocol::xproto::ConnectionExt as _;
+use x11rb::protocol::xproto::EventMask;
+use x11rb::protocol::xproto::Screen;
+use x11rb::protocol::xproto::Window;
+
+use crate::APP_NAME;
+use crate::Atoms;
+use crate::Conn;
+use crate::PROVIDER_POLL;
+use crate::PROVIDER_TIMEOUT;
+use crate::Result;
+use crate::config::Anchor;
+use crate::config::Config;
+use crate::config::ListLayout;
+use crate::config::MonitorChoice;
+use crate::config::Overflow;
+use crate::icons::desktop_entries;
+use crate::icons::match_desktop_entry;
+use crate::render::Area;
+use crate::x11::get_active_window;
+use crate::x11::get_window_alias;
+use crate::x11::get_window_class;
+use crate::x11::get_window_client_machine;
+use crate::x11::get_window_desktop;
+use crate::x11::get_window_geometry;
+use crate::x11::get_window_icon_name;
+use crate::x11::get_window_parent;
+use crate::x11::get_window_pid;
+use crate::x11::get_window_protocols;
+use crate::x11::get_window_role;
+use crate::x11::get_window_title;
+use crate::x11::get_window_visible_name;
+use crate::x11::request_window_ping;
+
+/// desktop index of windows visible on all desktops
+pub const STICKY: u32 = 0xFFFFFFFF;
+/// x resource ids never set the top bit, the ids of provided tasks always do
+pub const VIRTUAL_WID: Window = 1 << 31;
+pub const ICONIC_STATE: u32 = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Filter {
+    All,
+    CurrentDesktop,
+    /// the monitor of the focused window, see `key_toggle_monitor`
+    CurrentMonitor,
+}
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Sort {
+    Recent,
+    Stacking,
+    Position,
+}
+/// what the command palette can do, see `key_palette`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Action {
+    ToggleIcons,
+    SwitchLayout,
+    ChangeSort,
+    ToggleScope,
+    /// also bound to `key_move_overlay`
+    MoveList,
+}
+pub const ACTIONS: [Action; 5] = [
+    Action::ToggleIcons,
+    Action::SwitchLayout,
+    Action::ChangeSort,
+    Action::ToggleScope,
+    Action::MoveList,
+];
+impl Action {
+    /// the entry in the palette, showing the current value
+    pub fn label(self, conf: &Config, tasks: &TaskList) -> String {
+        match self {
+            Self::ToggleIcons => format!("toggle icons ({})", conf.show_icons),
+            Self::SwitchLayout => format!("switch layout ({})", layout_name(conf)),
+            Self::ChangeSort => format!("change sort ({:?})", conf.sort).to_lowercase(),
+            Self::ToggleScope => match tasks.filter {
+                Filter::All => "toggle scope (all desktops)".to_string(),
+                Filter::CurrentDesktop => "toggle scope (current desktop)".to_string(),
+                Filter::CurrentMonitor => "toggle scope (current monitor)".to_string(),
+            },
+            Self::MoveList => format!("move list (location {})", conf.anchor.location()),
+        }
+    }
+    /// the config key and value set by the action, see `persist_toggles`
+    pub fn setting(self, conf: &Config, tasks: &TaskList) -> (&'static str, String) {
+        match self {
+            Self::ToggleIcons => ("show_icons", conf.show_icons.to_string()),
+            Self::SwitchLayout => ("layout", layout_name(conf)),
+            Self::ChangeSort => ("sort", format!("{:?}", conf.sort).to_lowercase()),
+            Self::ToggleScope => match tasks.filter {
+                Filter::All => ("filter", "all".to_string()),
+                Filter::CurrentDesktop => ("filter", "current_desktop".to_string()),
+                Filter::CurrentMonitor => ("filter", "current_monitor".to_string()),
+            },
+            Self::MoveList => ("location", conf.anchor.location().to_string()),
+        }
+    }
+    /// changes every theme, so that the change survives a theme switch
+    pub fn apply(self, themes: &mut [Config], tasks: &mut TaskList) {
+        for conf in themes.iter_mut() {
+            match self {
+                Self::ToggleIcons => conf.show_icons = !conf.show_icons,
+                Self::SwitchLayout => {
+                    (conf.layout, conf.auto_layout) = match (conf.auto_layout, conf.layout) {
+                        (true, _) => (ListLayout::Rows, false),
+                        (false, ListLayout::Rows) => (ListLayout::Columns, false),
+                        (false, ListLayout::Columns) => (ListLayout::Grid, false),
+                        (false, ListLayout::Grid) => (ListLayout::Grid, true),
+                    }
+                }
+                Self::ChangeSort => {
+                    conf.sort = match conf.sort {
+                        Sort::Recent => Sort::Stacking,
+                        Sort::Stacking => Sort::Position,
+                        Sort::Position => Sort::Recent,
+                    }
+                }
+                Self::ToggleScope => {}
+                Self::MoveList => {
+                    conf.anchor = Anchor::LOCATIONS[conf.anchor.location() % 9].clone();
+                    conf.follow_pointer = false;
+                }
+            }
+        }
+        match self {
+            Self::ChangeSort => tasks.set_sort(themes[0].sort),
+            Self::ToggleScope => tasks.toggle_scope(),
+            _ => {}
+        }
+    }
+}
+pub fn layout_name(conf: &Config) -> String {
+    if conf.auto_layout {
+        return "auto".to_string();
+    }
+    format!("{:?}", conf.layout).to_lowercase()
+}
+/// the actions as a list of entries, the selected one runs with `key_palette`
+pub fn build_palette(conf: &Config, tasks: &TaskList) -> TaskList {
+    let mut palette = TaskList::new();
+    // the first action goes on top
+    for (i, action) in ACTIONS.iter().enumerate().rev() {
+        let label = action.label(conf, tasks);
+        palette.track(Task {
+            wid: VIRTUAL_WID | i as Window,
+            ..virtual_task("palette", "", APP_NAME, &label, String::new())
+        });
+    }
+    palette.set_paginate(conf.overflow == Overflow::Paginate);
+    palette.mark_seen();
+    palette.select_end();
+    palette
+}
+pub fn palette_action(palette: &TaskList) -> Option<Action> {
+    let task = palette.selected()?;
+    ACTIONS.get((task.wid & !VIRTUAL_WID) as usize).copied()
+}
+/// window geometry in root coordinates
+#[derive(Debug, Clone, Copy)]
+pub struct Geometry {
+    pub x: i32,
+    pub y: i32,
+    pub w: u32,
+    pub h: u32,
+}
+impl Geometry {
+    pub fn center(&self) -> (i32, i32) {
+        (self.x + self.w as i32 / 2, self.y + self.h as i32 / 2)
+    }
+    pub fn area(&self) -> Area {
+        Area::new(self.x as f32, self.y as f32, self.w as f32, self.h as f32)
+    }
+    pub fn contains(&self, (x, y): (i32, i32)) -> bool {
+        (self.x..self.x + self.w as i32).contains(&x)
+            && (self.y..self.y + self.h as i32).contains(&y)
+    }
+    /// grown by the `_NET_FRAME_EXTENTS` of the wm decorations: left, right, top, bottom
+    pub fn framed(self, [left, right, top, bottom]: [u32; 4]) -> Self {
+        Self {
+            x: self.x - left as i32,
+            y: self.y - top as i32,
+            w: self.w + left + right,
+            h: self.h + top + bottom,
+        }
+    }
+}
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MonitorLabels {
+    Off,
+    Annotate,
+    Group,
+}
+/// what is shown instead of an empty title, tried in order
+#[derive(Debug, Clone, PartialEq)]
+pub enum TitleFallback {
+    IconName,
+    Class,
+    Instance,
+    Text(Cow<'static, str>),
+}
+pub const DEFAULT_TITLE_FALLBACK: [TitleFallback; 3] = [
+    TitleFallback::IconName,
+    TitleFallback::Class,
+    TitleFallback::Text(Cow::Borrowed("untitled")),
+];
+#[derive(Debug)]
+pub struct Monitor {
+    pub name: String,
+    pub geometry: Geometry,
+    pub primary: bool,
+}
+/// a rendered cell of the list
+pub enum Row<'a> {
+    Header(String),
+    Task(&'a Task),
+}
+#[derive(Debug)]
+pub struct Task {
+    pub wid: Window,
+    // pid: Option<u32>,
+    pub title: String,
+    // the short title of the window, shown when the title is empty
+    pub icon_name: Option<String>,
+    pub role: Option<String>,
+    pub visible_name: Option<String>,
+    // the `_GOTO_ALIAS` set with `--alias`, shown instead of the title
+    pub alias: Option<String>,
+    pub class: (String, String),
+    pub created: Instant,
+    pub desktop: Option<u32>,
+    pub parent: Option<Window>,
+    pub geometry: Option<Geometry>,
+    pub pingable: bool,
+    // when the pending _NET_WM_PING was sent
+    pub ping: Option<Instant>,
+    pub responsive: bool,
+    // the command activating a task injected by a provider
+    pub activate: Option<String>,
+    // the window showing a provided task, focused along with the activation
+    pub host: Option<Window>,
+    // the command line of the process, to reopen the window once closed
+    pub command: Option<String>,
+    // when the window was closed, see `closed_history`
+    pub closed: Option<Instant>,
+}
+impl Task {
+    /// not an x window, see `providers` and `closed_history`
+    pub fn is_virtual(&self) -> bool {
+        self.wid & VIRTUAL_WID != 0
+    }
+    /// the x window that should end up focused once the task is activated
+    pub fn focus_target(&self) -> Option<Window> {
+        self.host.or(self.activate.is_none().then_some(self.wid))
+    }
+}
+impl PartialEq for Task {
+    fn eq(&self, other: &Self) -> bool {
+        self.wid == other.wid
+    }
+}
+#[derive(Debug)]
+pub struct TaskList {
+    pub tasks: Vec<Task>,
+    pub selected: Option<usize>,
+    // while cycling the order is frozen, focus changes are applied when thawing
+    pub frozen: bool,
+    pub pending_focus: Option<Window>,
+    // tasks created after this instant haven't been shown yet
+    pub seen: Instant,
+    pub filter: Filter,
+    pub desktop: Option<u32>,
+    pub override_redirect_classes: Vec<String>,
+    pub collapse_transients: bool,
+    pub title_fallback: Vec<TitleFallback>,
+    pub title_format: String,
+    // the `aliases` of the config, and the ones set with `--alias`, which win
+    pub aliases: Vec<(String, String)>,
+    pub saved_aliases: HashMap<(String, String), String>,
+    pub sort: Sort,
+    // bottom to top, as reported by the wm
+    pub stacking: Vec<Window>,
+    pub monitors: Vec<Monitor>,
+    pub monitor_labels: MonitorLabels,
+    pub desktop_names: Vec<String>,
+    pub desktop_sections: bool,
+    pub group_by_class: bool,
+    pub focused: Option<Window>,
+    pub hide_current: bool,
+    // the rows that fit in the window, none when they all do
+    pub page_size: Option<usize>,
+    // the layout the rows are drawn with, `layout: auto` resolved
+    pub layout: ListLayout,
+    // the columns of the grid layout, one otherwise
+    pub columns: usize,
+    // the first row shown
+    pub scroll: usize,
+    // whether the rows are shown a page at a time instead of scrolling
+    pub paginate: bool,
+    // how many closed windows are kept at the bottom of the list
+    pub history: usize,
+    // the query typed after `key_search`, none while not searching
+    pub query: Option<String>,
+}
+impl TaskList {
+    pub fn new() -> Self {
+        Self {
+            tasks: Vec::with_capacity(64),
+            selected: None,
+            frozen: false,
+            pending_focus: None,
+            seen: Instant::now(),
+            filter: Filter::All,
+            desktop: None,
+            override_redirect_classes: vec![],
+            collapse_transients: false,
+            title_fallback: DEFAULT_TITLE_FALLBACK.to_vec(),
+            title_format: "{title}".into(),
+            aliases: vec![],
+            saved_aliases: HashMap::new(),
+            sort: Sort::Recent,
+            stacking: vec![],
+            monitors: vec![],
+            monitor_labels: MonitorLabels::Off,
+            desktop_names: vec![],
+            desktop_sections: false,
+            group_by_class: false,
+            focused: None,
+            hide_current: false,
+            page_size: None,
+            layout: ListLayout::Rows,
+            columns: 1,
+            scroll: 0,
+            paginate: false,
+            history: 0,
+            query: None,
+        }
+    }
+}
+impl Default for TaskList {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+impl TaskList {
+    /// pings the visible tasks that have no pending ping
+    pub fn ping(&mut self, conn: &Conn, atoms: &Atoms) {
+        let now = Instant::now();
+        for idx in 0..self.tasks.len() {
+            let task = &self.tasks[idx];
+            if task.pingable && task.ping.is_none() && self.is_visible(task) {
+                let wid = task.wid;
+                if request_window_ping(conn, atoms, wid).is_ok() {
+                    self.tasks[idx].ping = Some(now);
+                }
+            }
+        }
+    }
+    /// returns whether the task was considered unresponsive
+    pub fn pong(&mut self, wid: Window) -> bool {
+        let Some(task) = self.tasks.iter_mut().find(|task| task.wid == wid) else {
+            return false;
+        };
+        let changed = !task.responsive;
+        task.ping = None;
+        task.responsive = true;
+        changed
+    }
+    /// marks as unresponsive the tasks that didn't answer in time, returns whether any changed
+    pub fn expire_pings(&mut self, timeout: Duration) -> bool {
+        let mut changed = false;
+        for task in &mut self.tasks {
+            if task.ping.is_some_and(|sent| sent.elapsed() > timeout) {
+                changed |= task.responsive;
+                task.ping = None;
+                task.responsive = false;
+            }
+        }
+        changed
+    }
+    pub fn mark_seen(&mut self) {
+        self.seen = Instant::now();
+    }
+    pub fn is_new(&self, task: &Task) -> bool {
+        task.created > self.seen
+    }
+    pub fn freeze(&mut self) {
+        self.frozen = true;
+    }
+    pub fn thaw(&mut self) {
+        self.frozen = false;
+        if let Some(wid) = self.pending_focus.take() {
+            let selected = self.selected().map(|task| task.wid);
+            self.focus_by_wid(wid);
+            if let Some(wid) = selected {
+                self.selected = self.tasks.iter().position(|task| task.wid == wid);
+            }
+        }
+        self.apply_order();
+    }
+    pub fn set_sort(&mut self, sort: Sort) {
+        self.sort = sort;
+        self.apply_order();
+    }
+    pub fn restack(&mut self, stacking: Vec<Window>) {
+        self.stacking = stacking;
+        self.apply_order();
+    }
+    pub fn tracks_geometry(&self) -> bool {
+        // with several monitors the positions are kept up to date for `current_monitor`
+        self.sort == Sort::Position
+            || self.monitor_labels != MonitorLabels::Off
+            || self.monitors.len() > 1
+    }
+    pub fn update_geometry(&mut self, wid: Window, geometry: Option<Geometry>) {
+        if let Some(task) = self.tasks.iter_mut().find(|task| task.wid == wid) {
+            task.geometry = geometry;
+        }
+        self.apply_order();
+    }
+    /// reorders the tasks according to the sort, the most recent order is kept by focusing
+    pub fn apply_order(&mut self) {
+        if self.frozen || self.sort == Sort::Recent {
+            return;
+        }
+        let selected = self.selected().map(|task| task.wid);
+        match self.sort {
+            Sort::Recent => {}
+            Sort::Stacking => {
+                let stacking = &self.stacking;
+                // windows unknown to the wm (e.g. override redirect) stay at the bottom
+                self.tasks.sort_by_key(|task| {
+                    stacking
+                        .iter()
+                        .position(|wid| *wid == task.wid)
+                        .map_or(0, |pos| pos + 1)
+                });
+            }
+            Sort::Position => {
+                // the last task is listed first: left to right, then top to bottom.
+                // centers are compared so that overlapping floating windows sort sensibly
+                self.tasks
+                    .sort_by_key(|task| task.geometry.map(|g| std::cmp::Reverse(g.center())));
+            }
+        }
+        if let Some(wid) = selected {
+            self.selected = self.tasks.iter().position(|task| task.wid == wid);
+        }
+    }
+    pub fn selected(&self) -> Option<&Task> {
+        self.selected.map(|sel| &self.tasks[sel])
+    }
+    pub fn get_task_by_id(&self, wid: Window) -> Option<&Task> {
+        self.tasks.iter().find(|task| task.wid == wid)
+    }
+    pub fn list_ascending(&self) -> (impl Iterator<Item = &Task>, Option<usize>) {
+        (self.tasks.iter(), self.selected)
+    }
+    pub fn list_descending(&self) -> (impl Iterator<Item = &Task>, Option<usize>) {
+        let selected = self.selected.map(|sel| {
+            self.tasks[sel + 1..]
+                .iter()
+                .filter(|task| self.is_visible(task))
+                .count()
+        });
+        let list = self.tasks.iter().rev().filter(|task| self.is_visible(task));
+        (list, selected)
+    }
+    /// the visible tasks, preceded by a header for each group, and the selected row
+    pub fn rows_descending(&self) -> (Vec<Row<'_>>, Option<usize>) {
+        let selected = self.selected().map(|task| task.wid);
+        let mut list: Vec<_> = self.list_descending().0.collect();
+        if !self.is_grouped() {
+            let selected = list.iter().position(|task| Some(task.wid) == selected);
+            return (list.into_iter().map(Row::Task).collect(), selected);
+        }
+
+        // groups are listed by their most recent task, the order within a group is kept
+        let mut groups = vec![];
+        for task in &list {
+            let group = self.group_of(task);
+            if !groups.contains(&group) {
+                groups.push(group);
+            }
+        }
+        list.sort_by_key(|task| {
+            let group = self.group_of(task);
+            groups.iter().position(|g| *g == group)
+        });
+
+        let mut rows = Vec::with_capacity(list.len() + groups.len());
+        let mut current = None;
+        let mut selected_row = None;
+        for task in list {
+            let group = self.group_of(task);
+            if current.as_ref() != Some(&group) {
+                let header = group.clone().unwrap_or_else(|| "other".to_string());
+                rows.push(Row::Header(header));
+                current = Some(group);
+            }
+            if Some(task.wid) == selected {
+                selected_row = Some(rows.len());
+            }
+            rows.push(Row::Task(task));
+        }
+        (rows, selected_row)
+    }
+    /// the rows that fit in the window and the selected one among them
+    pub fn visible_rows(&self) -> (Vec<Row<'_>>, Option<usize>) {
+        let (rows, selected) = self.rows_descending();
+        let Some(page_size) = self.page_size else {
+            return (rows, selected);
+        };
+        let selected = selected
+            .and_then(|sel| sel.checked_sub(self.scroll))
+            .filter(|sel| *sel < page_size);
+        let rows = rows.into_iter().skip(self.scroll).take(page_size).collect();
+        (rows, selected)
+    }
+    pub fn set_page_size(&mut self, page_size: Option<usize>) {
+        self.page_size = page_size;
+        self.scroll_into_view();
+    }
+    pub fn set_layout(&mut self, (layout, columns): (ListLayout, usize)) {
+        self.layout = layout;
+        self.columns = columns.max(1);
+    }
+    pub fn set_paginate(&mut self, paginate: bool) {
+        self.paginate = paginate;
+        self.scroll_into_view();
+    }
+    /// the number of cells the window is split in
+    pub fn cells(&self) -> usize {
+        self.page_size.unwrap_or_else(|| self.rows())
+    }
+    /// the current page and the number of pages, starting from 1
+    pub fn page(&self) -> Option<(usize, usize)> {
+        let page_size = self.page_size.filter(|_| self.paginate)?;
+        Some((self.scroll / page_size + 1, self.rows().div_ceil(page_size)))
+    }
+    /// scrolls as little as possible to show the selected row
+    pub fn scroll_into_view(&mut self) {
+        let Some(page_size) = self.page_size else {
+            self.scroll = 0;
+            return;
+        };
+        let (rows, selected) = self.rows_descending();
+        let len = rows.len();
+        if self.paginate {
+            // the last page can be partially filled
+            self.scroll = selected.map_or(0, |sel| sel / page_size * page_size);
+            return;
+        }
+        if let Some(sel) = selected {
+            // keep the header of the selected task in view
+            let top = match sel.checked_sub(1).map(|prev| &rows[prev]) {
+                Some(Row::Header(_)) => sel - 1,
+                _ => sel,
+            };
+            if top < self.scroll {
+                self.scroll = top;
+            } else if sel >= self.scroll + page_size {
+                self.scroll = sel + 1 - page_size;
+            }
+        }
+        self.scroll = self.scroll.min(len.saturating_sub(page_size));
+    }
+    /// position of the task in the list, the first being the most recent
+    pub fn rank(&self, task: &Task) -> Option<usize> {
+        self.list_descending().0.position(|t| t.wid == task.wid)
+    }
+    /// the number of rendered cells, headers included
+    pub fn rows(&self) -> usize {
+        if !self.is_grouped() {
+            return self.len();
+        }
+        let mut groups = vec![];
+        for task in self.tasks.iter().filter(|task| self.is_visible(task)) {
+            let group = self.group_of(task);
+            if !groups.contains(&group) {
+                groups.push(group);
+            }
+        }
+        self.len() + groups.len()
+    }
+    pub fn is_grouped(&self) -> bool {
+        self.desktop_sections || self.group_by_class || self.groups_monitors()
+    }
+    pub fn groups_monitors(&self) -> bool {
+        self.monitor_labels == MonitorLabels::Group && self.spans_monitors()
+    }
+    /// the header of the section the task is listed under
+    pub fn group_of(&self, task: &Task) -> Option<String> {
+        let desktop = self
+            .desktop_sections
+            .then(|| self.desktop_name(task))
+            .flatten();
+        let monitor = self
+            .groups_monitors()
+            .then(|| self.monitor_of(task))
+            .flatten()
+            .map(|monitor| monitor.name.as_str());
+        let place = match (desktop, monitor) {
+            (Some(desktop), Some(monitor)) => Some(format!("{desktop} ({monitor})")),
+            (desktop, monitor) => desktop.or(monitor.map(str::to_string)),
+        };
+        let class = (self.group_by_class && !task.class.1.is_empty()).then(|| task.class.1.clone());
+        match (class, place) {
+            (Some(class), Some(place)) => Some(format!("{class} on {place}")),
+            (class, place) => class.or(place),
+        }
+    }
+    pub fn set_group_by_class(&mut self, group: bool) {
+        self.group_by_class = group;
+        self.scroll_into_view();
+    }
+    /// the visible tasks in the group of the task, counted when grouped by class
+    pub fn group_len(&self, task: &Task) -> usize {
+        let group = self.group_of(task);
+        self.tasks
+            .iter()
+            .filter(|t| self.is_visible(t) && self.group_of(t) == group)
+            .count()
+    }
+    pub fn set_desktop_names(&mut self, names: Vec<String>, sections: bool) {
+        self.desktop_names = names;
+        self.desktop_sections = sections;
+    }
+    pub fn desktop_name(&self, task: &Task) -> Option<String> {
+        match task.desktop? {
+            STICKY => Some("all desktops".to_string()),
+            desktop => Some(
+                self.desktop_names
+                    .get(desktop as usize)
+                    .filter(|name| !name.is_empty())
+                    .cloned()
+                    .unwrap_or_else(|| format!("desktop {}", desktop + 1)),
+            ),
+        }
+    }
+    pub fn set_monitors(&mut self, monitors: Vec<Monitor>, labels: MonitorLabels) {
+        self.monitors = monitors;
+        self.monitor_labels = labels;
+    }
+    pub fn monitor_of(&self, task: &Task) -> Option<&Monitor> {
+        let center = task.geometry?.center();
+        self.monitors
+            .iter()
+            .find(|monitor| monitor.geometry.contains(center))
+    }
+    /// whether the visible tasks are on more than one monitor
+    pub fn spans_monitors(&self) -> bool {
+        let mut monitors = self
+            .tasks
+            .iter()
+            .filter(|task| self.is_visible(task))
+            .filter_map(|task| self.monitor_of(task));
+        monitors
+            .next()
+            .is_some_and(|first| monitors.any(|monitor| !std::ptr::eq(monitor, first)))
+    }
+    pub fn is_visible(&self, task: &Task) -> bool {
+        if self.is_collapsed(task) || (self.hide_current && self.is_current(task)) {
+            return false;
+        }
+        if self.pattern().is_some() && self.query_score(task).is_none() {
+            return false;
+        }
+        match self.filter {
+            Filter::All => true,
+            Filter::CurrentDesktop => match (task.desktop, self.desktop) {
+                (Some(STICKY), _) | (None, _) | (_, None) => true,
+                (Some(desktop), Some(current)) => desktop == current,
+            },
+            Filter::CurrentMonitor => match (self.monitor_of(task), self.current_monitor()) {
+                (Some(monitor), Some(current)) => std::ptr::eq(monitor, current),
+                _ => true,
+            },
+        }
+    }
+    /// the monitor of the focused window
+    pub fn current_monitor(&self) -> Option<&Monitor> {
+        let focused = self.get_task_by_id(self.focused?)?;
+        self.monitor_of(focused)
+    }
+    /// where the list goes, the chosen monitor or the one of the focused window when it isn't
+    /// found, else the whole screen. `pointer` is only needed for `monitor: pointer`
+    pub fn output(
+        &self,
+        screen: &Screen,
+        choice: &MonitorChoice,
+        pointer: Option<(i32, i32)>,
+    ) -> Area {
+        let chosen = match choice {
+            MonitorChoice::Focused => None,
+            MonitorChoice::Pointer => pointer.and_then(|point| {
+                self.monitors
+                    .iter()
+                    .find(|monitor| monitor.geometry.contains(point))
+            }),
+            MonitorChoice::Primary => self.monitors.iter().find(|monitor| monitor.primary),
+            MonitorChoice::Named(name) => self.monitors.iter().find(|m| &m.name == name),
+        };
+        chosen
+            .or_else(|| self.current_monitor())
+            .map_or(Area::of_screen(screen), |monitor| monitor.geometry.area())
+    }
+    pub fn is_current(&self, task: &Task) -> bool {
+        self.focused == Some(task.wid)
+    }
+    pub fn set_hide_current(&mut self, hide: bool) {
+        self.hide_current = hide;
+        self.fix_selection();
+    }
+    pub fn is_empty(&self) -> bool {
+        !self.tasks.iter().any(|task| self.is_visible(task))
+    }
+    pub fn len(&self) -> usize {
+        self.tasks
+            .iter()
+            .filter(|task| self.is_visible(task))
+            .count()
+    }
+    pub fn set_filter(&mut self, filter: Filter) {
+        self.filter = filter;
+        self.fix_selection();
+    }
+    /// switches between the tasks of the current desktop and all of them
+    pub fn toggle_scope(&mut self) {
+        self.set_filter(match self.filter {
+            Filter::All => Filter::CurrentDesktop,
+            Filter::CurrentDesktop | Filter::CurrentMonitor => Filter::All,
+        });
+    }
+    /// switches between the tasks of the current monitor and all of them
+    pub fn toggle_monitor(&mut self) {
+        self.set_filter(match self.filter {
+            Filter::CurrentMonitor => Filter::All,
+            Filter::All | Filter::CurrentDesktop => Filter::CurrentMonitor,
+        });
+    }
+    pub fn query(&self) -> Option<&str> {
+        self.query.as_deref()
+    }
+    /// the query, unless it's empty
+    fn pattern(&self) -> Option<&str> {
+        self.query.as_deref().filter(|query| !query.is_empty())
+    }
+    /// lists the tasks whose label or class match the query and selects the best match, none
+    /// ends the search
+    pub fn set_query(&mut self, query: Option<String>) {
+        self.query = query;
+        if self.pattern().is_none() {
+            self.fix_selection();
+            return;
+        }
+        // on a tie the most recent task wins
+        self.selected = self
+            .tasks
+            .iter()
+            .enumerate()
+            .filter_map(|(i, task)| Some((self.query_score(task)?, i)))
+            .max()
+            .map(|(_, i)| i);
+    }
+    /// the score of the best match of the query among the label and the class of the task
+    pub fn query_score(&self, task: &Task) -> Option<i64> {
+        let pattern = self.pattern()?;
+        [&*self.label(task), &task.class.1, &task.class.0]
+            .into_iter()
+            .filter_map(|text| fuzzy_match(pattern, text))
+            .map(|(score, _)| score)
+            .max()
+    }
+    /// the byte offsets of the chars of the label matched by the query
+    pub fn query_matches(&self, task: &Task) -> Vec<usize> {
+        self.pattern()
+            .and_then(|pattern| fuzzy_match(pattern, &self.label(task)))
+            .map(|(_, matched)| matched)
+            .unwrap_or_default()
+    }
+    /// transients are shown as part of their parent entry
+    pub fn is_collapsed(&self, task: &Task) -> bool {
+        self.collapse_transients && task.parent.is_some_and(|parent| self.contains(parent))
+    }
+    pub fn set_collapse_transients(&mut self, collapse: bool) {
+        self.collapse_transients = collapse;
+        self.fix_selection();
+    }
+    pub fn label<'a>(&self, task: &'a Task) -> Cow<'a, str> {
+        let mut label = self.format_title(task);
+        if self.collapse_transients {
+            match self
+                .tasks
+                .iter()
+                .filter(|t| t.parent == Some(task.wid))
+                .count()
+            {
+                0 => {}
+                1 => label.to_mut().push_str(" (+1 dialog)"),
+                n => label.to_mut().push_str(&format!(" (+{n} dialogs)")),
+            }
+        }
+        if self.monitor_labels == MonitorLabels::Annotate
+            && self.spans_monitors()
+            && let Some(monitor) = self.monitor_of(task)
+        {
+            label.to_mut().push_str(&format!(" ({})", monitor.name));
+        }
+        label
+    }
+    /// the alias of the task, its title, or the first non empty `title_fallback`
+    pub fn title<'a>(&self, task: &'a Task) -> Cow<'a, str> {
+        if let Some(alias) = self.alias(task) {
+            return Cow::Owned(alias.to_string());
+        }
+        if !task.title.trim().is_empty() {
+            return Cow::Borrowed(&task.title);
+        }
+        for fallback in &self.title_fallback {
+            let title = match fallback {
+                TitleFallback::IconName => task.icon_name.as_deref().unwrap_or_default(),
+                TitleFallback::Class => &task.class.1,
+                TitleFallback::Instance => &task.class.0,
+                TitleFallback::Text(text) => return Cow::Owned(text.to_string()),
+            };
+            if !title.trim().is_empty() {
+                return Cow::Borrowed(title);
+            }
+        }
+        Cow::Borrowed(&task.title)
+    }
+    /// `title_format` with the fields of the task, the unknown ones are left as they are
+    pub fn format_title<'a>(&self, task: &'a Task) -> Cow<'a, str> {
+        if self.title_format == "{title}" {
+            return self.title(task);
+        }
+        let mut out = String::new();
+        let mut rest = self.title_format.as_str();
+        while let Some(start) = rest.find('{')
+            && let Some(len) = rest[start..].find('}')
+        {
+            out.push_str(&rest[..start]);
+            match &rest[start + 1..start + len] {
+                "title" => out.push_str(&self.title(task)),
+                "class" => out.push_str(&task.class.1),
+                "instance" => out.push_str(&task.class.0),
+                "role" => out.push_str(task.role.as_deref().unwrap_or_default()),
+                "visible_name" => out.push_str(task.visible_name.as_deref().unwrap_or_default()),
+                _ => out.push_str(&rest[start..=start + len]),
+            }
+            rest = &rest[start + len + 1..];
+        }
+        out.push_str(rest);
+        Cow::Owned(out.trim().to_string())
+    }
+    /// the alias of the window, or the one of its class and instance, or the config one
+    pub fn alias<'a>(&'a self, task: &'a Task) -> Option<&'a str> {
+        if let Some(alias) = &task.alias {
+            return Some(alias);
+        }
+        if let Some(alias) = self.saved_aliases.get(&task.class) {
+            return Some(alias);
+        }
+        self.aliases
+            .iter()
+            .find(|(class, _)| class_matches(&task.class, std::slice::from_ref(class)))
+            .map(|(_, alias)| alias.as_str())
+    }
+    pub fn set_aliases(&mut self, aliases: Vec<(String, String)>) {
+        self.aliases = aliases;
+    }
+    pub fn set_saved_aliases(&mut self, aliases: HashMap<(String, String), String>) {
+        self.saved_aliases = aliases;
+    }
+    pub fn set_title_fallback(&mut self, fallback: Vec<TitleFallback>) {
+        self.title_fallback = fallback;
+    }
+    pub fn set_title_format(&mut self, format: String) {
+        self.title_format = format;
+    }
+    pub fn update_role(&mut self, wid: Window, role: Option<String>) {
+        if let Some(task) = self.tasks.iter_mut().find(|task| task.wid == wid) {
+            task.role = role;
+        }
+    }
+    pub fn update_alias(&mut self, wid: Window, alias: Option<String>) {
+        if let Some(task) = self.tasks.iter_mut().find(|task| task.wid == wid) {
+            task.alias = alias;
+        }
+    }
+    pub fn update_visible_name(&mut self, wid: Window, visible_name: Option<String>) {
+        if let Some(task) = self.tasks.iter_mut().find(|task| task.wid == wid) {
+            task.visible_name = visible_name;
+        }
+    }
+    pub fn set_override_redirect_classes(&mut self, classes: Vec<String>) {
+        self.override_redirect_classes = classes;
+    }
+    pub fn set_desktop(&mut self, desktop: Option<u32>) {
+        self.desktop = desktop;
+        self.fix_selection();
+    }
+    pub fn update_desktop(&mut self, wid: Window, desktop: Option<u32>) {
+        if let Some(task) = self.tasks.iter_mut().find(|task| task.wid == wid) {
+            task.desktop = desktop;
+        }
+        self.fix_selection();
+    }
+    /// the nearest visible task from `from`, stepping towards older or newer tasks and wrapping
+    pub fn step(&self, from: usize, older: bool) -> Option<usize> {
+        let len = self.tasks.len();
+        (1..=len)
+            .map(|i| {
+                if older {
+                    (from + len - i) % len
+                } else {
+                    (from + i) % len
+                }
+            })
+            .find(|idx| self.is_visible(&self.tasks[*idx]))
+    }
+    pub fn fix_selection(&mut self) {
+        // a hidden focused task can stay selected, cycling starts from it
+        if let Some(sel) = self.selected
+            && !self.is_visible(&self.tasks[sel])
+            && !self.is_current(&self.tasks[sel])
+        {
+            self.selected = self.step(sel, true);
+        }
+    }
+    pub fn contains(&self, wid: Window) -> bool {
+        self.tasks.iter().any(|task| task.wid == wid)
+    }
+    pub fn update_title(&mut self, wid: Window, title: String) {
+        if let Some(task) = self.tasks.iter_mut().find(|task| task.wid == wid) {
+            task.title = title;
+        }
+    }
+    /// returns whether the class changed
+    pub fn update_class(&mut self, wid: Window, class: (String, String)) -> bool {
+        match self.tasks.iter_mut().find(|task| task.wid == wid) {
+            Some(task) if task.class != class => {
+                task.class = class;
+                true
+            }
+            _ => false,
+        }
+    }
+    pub fn update_icon_name(&mut self, wid: Window, icon_name: Option<String>) {
+        if let Some(task) = self.tasks.iter_mut().find(|task| task.wid == wid) {
+            task.icon_name = icon_name;
+        }
+    }
+    /// returns the windows that were untracked and the ones that started being tracked
+    pub fn diff_update(
+        &mut self,
+        wids: Vec<Window>,
+        conn: &Conn,
+        atoms: &Atoms,
+    ) -> (Vec<Window>, Vec<Window>) {
+        let mut old_wids = Vec::with_capacity(self.tasks.len());
+        self.tasks
+            .iter()
+            .filter(|task| !task.is_virtual() && !wids.contains(&task.wid))
+            .for_each(|task| old_wids.push(task.wid));
+        old_wids.iter().for_each(|wid| self.close(*wid));
+
+        let mut mask = EventMask::PROPERTY_CHANGE;
+        if self.tracks_geometry() {
+            mask |= EventMask::STRUCTURE_NOTIFY;
+        }
+        let propmask = &ChangeWindowAttributesAux::new().event_mask(mask);
+        let mut new_wids = Vec::with_capacity(wids.len());
+        wids.into_iter()
+            .filter(|wid| !self.contains(*wid))
+            .for_each(|wid| new_wids.push(wid));
+        let mut added = Vec::with_capacity(new_wids.len());
+        let classes = &self.override_redirect_classes;
+        let new_tasks: Vec<_> = new_wids
+            .into_iter()
+            .filter_map(|wid| window_to_task(conn, atoms, wid, classes, self.history > 0))
+            .collect();
+        new_tasks.into_iter().for_each(|task| {
+            let _ = conn.change_window_attributes(task.wid, propmask);
+            added.push(task.wid);
+            self.track(task);
+        });
+        (old_wids, added)
+    }
+    pub fn set_history(&mut self, history: usize) {
+        self.history = history;
+    }
+    /// untracks the window, keeping it in the history when it can be reopened
+    pub fn close(&mut self, wid: Window) {
+        let Some(task) = self.untrack(wid) else {
+            return;
+        };
+        if self.history == 0 {
+            return;
+        }
+        let Some(reopen) = reopen_command(&task) else {
+            return;
+        };
+        let task = Task {
+            wid: VIRTUAL_WID | task.wid,
+            desktop: None,
+            parent: None,
+            geometry: None,
+            pingable: false,
+            ping: None,
+            responsive: true,
+            activate: Some(reopen),
+            closed: Some(Instant::now()),
+            ..task
+        };
+        self.untrack(task.wid);
+        // the history sits at the bottom, the most recently closed right below the windows
+        let idx = self.tasks.iter().take_while(|t| t.closed.is_some()).count();
+        self.tasks.insert(idx, task);
+        self.selected = self
+            .selected
+            .map(|sel| if sel >= idx { sel + 1 } else { sel });
+        let closed = || self.tasks.iter().filter(|t| t.closed.is_some());
+        if closed().count() > self.history
+            && let Some(oldest) = closed().min_by_key(|t| t.closed).map(|t| t.wid)
+        {
+            self.untrack(oldest);
+        }
+    }
+    /// replaces the provided tasks, the ones still provided keep their position
+    pub fn update_virtual(&mut self, provided: Vec<Task>) -> (Vec<Window>, Vec<Window>) {
+        let removed: Vec<_> = self
+            .tasks
+            .iter()
+            .filter(|task| task.is_virtual() && task.closed.is_none() && !provided.contains(task))
+            .map(|task| task.wid)
+            .collect();
+        removed.iter().for_each(|wid| {
+            self.untrack(*wid);
+        });
+        let mut added = Vec::new();
+        for task in provided {
+            if let Some(old) = self.tasks.iter_mut().find(|old| **old == task) {
+                old.title = task.title;
+                old.class = task.class;
+                old.activate = task.activate;
+            } else {
+                added.push(task.wid);
+                self.track(task);
+            }
+        }
+        (removed, added)
+    }
+    pub fn track(&mut self, task: Task) {
+        if self.tasks.contains(&task) {
+            return;
+        }
+        if self.frozen {
+            // append at the bottom of the list so the rendered order doesn't shift
+            self.tasks.insert(0, task);
+            self.selected = self.selected.map(|sel| sel + 1);
+        } else {
+            self.tasks.push(task);
+            self.apply_order();
+        }
+    }
+    pub fn untrack(&mut self, wid: Window) -> Option<Task> {
+        let idx = self.tasks.iter().position(|task| task.wid == wid)?;
+        let task = self.tasks.remove(idx);
+        if let Some(sel) = self.selected {
+            if let Some(last) = self.tasks.len().checked_sub(1) {
+                let sel = if idx < sel { sel - 1 } else { sel };
+                self.selected = Some(sel.min(last));
+                self.fix_selection();
+            } else {
+                self.selected = None;
+            }
+        }
+        Some(task)
+    }
+    pub fn select_newer(&mut self) {
+        if !self.is_empty() {
+            if let Some(sel) = self.selected {
+                self.selected = self.step(sel, false);
+            } else {
+                self.select_end();
+            }
+        }
+    }
+    pub fn select_older(&mut self) {
+        if !self.is_empty() {
+            if let Some(sel) = self.selected {
+                self.selected = self.step(sel, true);
+            } else {
+                self.select_end();
+            }
+        }
+    }
+    pub fn select_by_wid(&mut self, wid: Window) {
+        if let Some(idx) = self.tasks.iter().position(|task| task.wid == wid)
+            && self.is_visible(&self.tasks[idx])
+        {
+            self.selected = Some(idx);
+        }
+    }
+    pub fn select_end(&mut self) {
+        if !self.is_empty() {
+            self.selected = self
+                .tasks
+                .iter()
+                .rposition(|task| self.is_visible(task) || self.is_current(task));
+        }
+    }
+    pub fn focus_by_index(&mut self, idx: usize) {
+        if let Some(task) = self.tasks.get(idx) {
+            self.focused = Some(task.wid);
+        }
+        if self.sort != Sort::Recent {
+            // the order doesn't depend on the focus
+            if idx < self.tasks.len() {
+                self.selected = Some(idx);
+            }
+            return;
+        }
+        if idx < self.tasks.len() {
+            let task = self.tasks.remove(idx);
+            self.tasks.push(task);
+            self.select_end();
+        }
+    }
+    pub fn focus_by_selection(&mut self) {
+        if let Some(sel) = self.selected {
+            self.focus_by_index(sel);
+        }
+    }
+    pub fn focus_by_wid(&mut self, wid: Window) {
+        if self.frozen {
+            self.pending_focus = Some(wid);
+            return;
+        }
+        let Some(idx) = self.tasks.iter().position(|task| task.wid == wid) else {
+            return;
+        };
+        let collapsed = self.is_collapsed(&self.tasks[idx]);
+        let parent = self.tasks[idx].parent;
+        self.focus_by_index(idx);
+        // a focused dialog brings its parent entry to the top
+        if collapsed
+            && let Some(parent) = parent
+            && let Some(idx) = self.tasks.iter().position(|task| task.wid == parent)
+        {
+            self.focus_by_index(idx);
+        }
+    }
+    pub fn unfocus(&mut self) {
+        if !self.frozen {
+            self.selected = None;
+            self.focused = None;
+        }
+    }
+}
+
+/// scores `text` against `pattern` the way skim and fzf do: the chars of the pattern appear in
+/// order, those at the start of a word or right after the previous one score higher and the
+/// gaps between them lower the score. the case is ignored unless the pattern has an uppercase
+/// char. returns the score of the best match and the byte offsets of its chars
+pub fn fuzzy_match(pattern: &str, text: &str) -> Option<(i64, Vec<usize>)> {
+    const MATCH: i64 = 16;
+    const BOUNDARY: i64 = 8;
+    const CONSECUTIVE: i64 = 8;
+    const GAP_START: i64 = -3;
+    const GAP_EXTENSION: i64 = -1;
+    let smart_case = pattern.chars().any(char::is_uppercase);
+    let fold = |c: char| match smart_case {
+        true => c,
+        false => c.to_lowercase().next().unwrap_or(c),
+    };
+    let pattern: Vec<_> = pattern.chars().map(fold).collect();
+    let text: Vec<_> = text.char_indices().collect();
+    let (m, n) = (pattern.len(), text.len());
+    if m == 0 || m > n {
+        return (m == 0).then(|| (0, vec![]));
+    }
+    let bonus = |j: usize| match j.checked_sub(1).map(|prev| text[prev].1) {
+        None => BOUNDARY,
+        Some(prev) if !prev.is_alphanumeric() => BOUNDARY,
+        Some(prev) if prev.is_lowercase() && text[j].1.is_uppercase() => BOUNDARY,
+        Some(_) => 0,
+    };
+    // the best score with the i-th char of the pattern matched at the j-th char of the text, and
+    // where the previous char of the pattern was matched
+    let mut scores = vec![vec![None; n]; m];
+    let mut from = vec![vec![0; n]; m];
+    for i in 0..m {
+        // the best previous match with a gap up to j
+        let mut gap: Option<(i64, usize)> = None;
+        for j in i..n {
+            if i > 0 && j >= 2 {
+                gap = gap.map(|(score, k)| (score + GAP_EXTENSION, k));
+                if let Some(score) = scores[i - 1][j - 2]
+                    && gap.is_none_or(|(best, _)| score + GAP_START > best)
+                {
+                    gap = Some((score + GAP_START, j - 2));
+                }
+            }
+            if fold(text[j].1) != pattern[i] {
+                continue;
+            }
+            let previous = match i {
+                0 => Some((0, 0)),
+                _ => {
+                    let consecutive =
+                        scores[i - 1][j - 1].map(|score| (score + CONSECUTIVE, j - 1));
+                    consecutive.into_iter().chain(gap).max()
+                }
+            };
+            if let Some((score, k)) = previous {
+                scores[i][j] = Some(score + MATCH + bonus(j));
+                from[i][j] = k;
+            }
+        }
+    }
+    let (score, mut j) = (0..n).filter_map(|j| Some((scores[m - 1][j]?, j))).max()?;
+    let mut matched = vec![text[j].0; m];
+    for i in (1..m).rev() {
+        j = from[i][j];
+        matched[i - 1] = text[j].0;
+    }
+    Some((score, matched))
+}
+
+/// drops the escape sequences and control characters, collapsing the whitespace
+pub fn sanitize_title(title: &str) -> String {
+    let mut out = String::with_capacity(title.len());
+    let mut chars = title.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c == '\x1b' {
+            // a csi sequence ends with its final byte, other escapes with the next char
+            if chars.next_if_eq(&'[').is_some() {
+                while chars
+                    .next()
+                    .is_some_and(|c| !('\x40'..='\x7e').contains(&c))
+                {}
+            } else {
+                chars.next();
+            }
+        } else if c.is_control() || c.is_whitespace() {
+            out.push(' ');
+        } else {
+            out.push(c);
+        }
+    }
+    out.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+pub fn window_to_task(
+    conn: &Conn,
+    atoms: &Atoms,
+    wid: Window,
+    override_redirect_classes: &[String],
+    // the command line is only needed to reopen the window, see `closed_history`
+    with_command: bool,
+) -> Option<Task> {
+    let attr = conn.get_window_attributes(wid).ok()?.reply().ok()?;
+    let class = get_window_class(conn, atoms, wid).ok()?;
+    if attr.override_redirect && !class_matches(&class, override_redirect_classes) {
+        return None;
+    }
+    let title = get_window_title(conn, atoms, wid).ok()?;
+    Some(Task {
+        wid,
+        title,
+        icon_name: get_window_icon_name(conn, atoms, wid).ok().flatten(),
+        role: get_window_role(conn, atoms, wid).ok().flatten(),
+        visible_name: get_window_visible_name(conn, atoms, wid).ok().flatten(),
+        alias: get_window_alias(conn, atoms, wid).ok().flatten(),
+        class,
+        created: Instant::now(),
+        desktop: get_window_desktop(conn, atoms, wid).ok().flatten(),
+        parent: get_window_parent(conn, atoms, wid).ok().flatten(),
+        geometry: get_window_geometry(conn, atoms, wid).ok(),
+        pingable: get_window_protocols(conn, atoms, wid)
+            .is_ok_and(|protocols| protocols.contains(&atoms._NET_WM_PING)),
+        ping: None,
+        responsive: true,
+        activate: None,
+        host: None,
+        command: with_command
+            .then(|| get_window_command(conn, atoms, wid))
+            .flatten(),
+        closed: None,
+    })
+}
+/// the command line of the process of the window, unless it runs on another host
+pub fn get_window_command(conn: &Conn, atoms: &Atoms, wid: Window) -> Option<String> {
+    let machine = get_window_client_machine(conn, wid).ok().flatten();
+    if machine.is_some_and(|machine| Some(machine.as_str()) != local_hostname()) {
+        return None;
+    }
+    get_window_pid(conn, atoms, wid)
+        .ok()
+        .flatten()
+        .and_then(get_process_command)
+}
+pub fn local_hostname() -> Option<&'static str> {
+    static HOSTNAME: OnceLock<Option<String>> = OnceLock::new();
+    HOSTNAME
+        .get_or_init(|| {
+            let name = read_to_string("/proc/sys/kernel/hostname").ok()?;
+            Some(name.trim().to_string())
+        })
+        .as_deref()
+}
+/// the command line of a local process, quoted for `sh -c`
+pub fn get_process_command(pid: u32) -> Option<String> {
+    let cmdline = std::fs::read(format!("/proc/{pid}/cmdline")).ok()?;
+    let args: Vec<_> = cmdline
+        .split(|b| *b == 0)
+        .filter(|arg| !arg.is_empty())
+        .map(|arg| shell_quote(&String::from_utf8_lossy(arg)))
+        .collect();
+    (!args.is_empty()).then(|| args.join(" "))
+}
+/// how to start the application again, preferring its desktop entry
+pub fn reopen_command(task: &Task) -> Option<String> {
+    match_desktop_entry(desktop_entries(), &task.class)
+        .and_then(|entry| entry.exec.as_deref())
+        .map(strip_field_codes)
+        .filter(|exec| !exec.is_empty())
+        .or_else(|| task.command.clone())
+}
+/// drops the `%f`, `%U`, ... placeholders of an `Exec` key, nothing is passed to them
+pub fn strip_field_codes(exec: &str) -> String {
+    exec.split_whitespace()
+        .filter(|arg| !(arg.len() == 2 && arg.starts_with('%') && arg != &"%%"))
+        .map(|arg| arg.replace("%%", "%"))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+/// runs the providers, each prints a task per line: `<id>\t<class>\t<title>\t<command>`
+pub fn get_provided_tasks(providers: &[String]) -> Vec<Task> {
+    let mut tasks = Vec::new();
+    for provider in providers {
+        let stdout = match run_provider(provider, PROVIDER_TIMEOUT) {
+            Ok(stdout) => stdout,
+            Err(e) => {
+                eprintln!("[WARNING] provider `{provider}` failed: {e}");
+                continue;
+            }
+        };
+        for line in stdout.lines().filter(|line| !line.trim().is_empty()) {
+            match parse_provided_task(provider, line) {
+                Some(task) if !tasks.contains(&task) => tasks.push(task),
+                Some(_) => {}
+                None => eprintln!("[WARNING] invalid line from provider `{provider}`: `{line}`"),
+            }
+        }
+    }
+    tasks
+}
+/// the output of the command, which is killed along with the processes it started past
+/// `timeout`
+pub fn run_provider(command: &str, timeout: Duration) -> Result<String> {
+    let mut child = std::process::Command::new("sh")
+        .arg("-c")
+        .arg(command)
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::inherit())
+        // a group of its own, that the processes left in the background belong to
+        .process_group(0)
+        .spawn()?;
+    let group = Pid::from_child(&child);
+    let timed_out = || {
+        let _ = kill_process_group(group, Signal::KILL);
+        Err(format!("timed out after {timeout:?}").into())
+    };
+    let mut stdout = child.stdout.take().ok_or("no stdout")?;
+    // read meanwhile, a full pipe would block the command
+    let (sender, output) = mpsc::channel();
+    std::thread::spawn(move || {
+        let mut out = vec![];
+        let _ = sender.send(std::io::Read::read_to_end(&mut stdout, &mut out).map(|_| out));
+    });
+    let deadline = Instant::now() + timeout;
+    let status = loop {
+        if let Some(status) = child.try_wait()? {
+            break status;
+        }
+        if Instant::now() >= deadline {
+            let result = timed_out();
+            let _ = child.wait();
+            return result;
+        }
+        std::thread::sleep(PROVIDER_POLL);
+    };
+    // the pipe stays open while a background process holds it, past the exit of the command
+    let Ok(out) = output.recv_timeout(deadline.saturating_duration_since(Instant::now())) else {
+        return timed_out();
+    };
+    if !status.success() {
+        return Err(status.to_string().into());
+    }
+    Ok(String::from_utf8_lossy(&out?).into_owned())
+}
+/// runs the command with `sh -c` and reaps it once it exits, the daemon outlives it
+pub fn spawn_shell(command: &str) -> Result<()> {
+    let mut child = std::process::Command::new("sh")
+        .arg("-c")
+        .arg(command)
+        .spawn()?;
+    std::thread::spawn(move || child.wait());
+    Ok(())
+}
+/// what the virtual tasks are queried with, gathered from the x server on the event loop
+pub struct VirtualQuery {
+    pub providers: Vec<String>,
+    // the active window and its pid, when tmux is enabled
+    pub terminal: Option<(Window, u32)>,
+}
+impl VirtualQuery {
+    pub fn new(conn: &Conn, screen: &Screen, atoms: &Atoms, conf: &Config) -> Self {
+        let terminal = conf
+            .tmux
+            .then(|| {
+                let wid = get_active_window(conn, screen, atoms).ok()??;
+                Some((wid, get_window_pid(conn, atoms, wid).ok()??))
+            })
+            .flatten();
+        Self {
+            providers: conf.providers.clone(),
+            terminal,
+        }
+    }
+    /// runs the providers and asks tmux, it blocks up to `PROVIDER_TIMEOUT` per command
+    pub fn run(&self) -> Vec<Task> {
+        let mut tasks = get_provided_tasks(&self.providers);
+        if let Some((wid, pid)) = self.terminal {
+            tasks.extend(get_tmux_tasks(wid, pid));
+        }
+        tasks
+    }
+}
+/// queries the virtual tasks off the event loop, so that a slow provider doesn't hold up a
+/// switch, the list shows the results of the last query meanwhile
+#[derive(Default)]
+pub struct VirtualRefresh {
+    results: Arc<Mutex<Option<Vec<Task>>>>,
+    running: Arc<AtomicBool>,
+}
+impl VirtualRefresh {
+    pub fn new() -> Self {
+        Self {
+            results: Arc::new(Mutex::new(None)),
+            running: Arc::new(AtomicBool::new(false)),
+        }
+    }
+    /// starts a query, unless one is still running
+    pub fn start(&self, query: VirtualQuery) {
+        if self.running.swap(true, Ordering::SeqCst) {
+            return;
+        }
+        let (results, running) = (self.results.clone(), self.running.clone());
+        std::thread::spawn(move || {
+            let tasks = query.run();
+            *results.lock().unwrap() = Some(tasks);
+            running.store(false, Ordering::SeqCst);
+        });
+    }
+    pub fn is_running(&self) -> bool {
+        self.running.load(Ordering::SeqCst)
+    }
+    /// the results of the last query that finished, once
+    pub fn take(&self) -> Option<Vec<Task>> {
+        self.results.lock().unwrap().take()
+    }
+}
+pub fn parse_provided_task(provider: &str, line: &str) -> Option<Task> {
+    let mut fields = line.splitn(4, '\t');
+    let id = fields.next()?;
+    let class = fields.next()?.trim();
+    let title = fields.next()?;
+    let command = fields.next()?.trim();
+    if class.is_empty() || command.is_empty() {
+        return None;
+    }
+    Some(virtual_task(
+        provider,
+        id,
+        class,
+        title,
+        command.to_string(),
+    ))
+}
+pub fn virtual_task(provider: &str, id: &str, class: &str, title: &str, activate: String) -> Task {
+    // the id stays the same across runs, so the task keeps its place in the list
+    let mut hasher = std::hash::DefaultHasher::new();
+    std::hash::Hash::hash(&(provider, id), &mut hasher);
+    let hash = std::hash::Hasher::finish(&hasher) as Window;
+    Task {
+        wid: VIRTUAL_WID | hash,
+        title: sanitize_title(title),
+        icon_name: None,
+        role: None,
+        visible_name: None,
+        alias: None,
+        class: (class.to_string(), class.to_string()),
+        created: Instant::now(),
+        desktop: None,
+        parent: None,
+        geometry: None,
+        pingable: false,
+        ping: None,
+        responsive: true,
+        activate: Some(activate),
+        host: None,
+        command: None,
+        closed: None,
+    }
+}
+/// the windows of the tmux server, when the active window is a terminal attached to it
+pub fn get_tmux_tasks(terminal: Window, terminal_pid: u32) -> Vec<Task> {
+    let tmux = |args: &[&str]| -> Option<String> {
+        let output = std::process::Command::new("tmux")
+            .args(args)
+            .output()
+            .ok()?;
+        output
+            .status
+            .success()
+            .then(|| String::from_utf8_lossy(&output.stdout).into_owned())
+    };
+    // the client running inside the terminal descends from its process
+    let Some(clients) = tmux(&["list-clients", "-F", "#{client_pid}\t#{client_name}"]) else {
+        return vec![];
+    };
+    let Some(client) = clients.lines().find_map(|line| {
+        let (pid, name) = line.split_once('\t')?;
+        let pid = pid.parse().ok()?;
+        is_descendant(pid, terminal_pid).then_some(name)
+    }) else {
+        return vec![];
+    };
+    let format = "#{window_id}\t#{session_name}:#{window_index}\t#{window_name}";
+    let Some(windows) = tmux(&["list-windows", "-a", "-F", format]) else {
+        return vec![];
+    };
+    windows
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.splitn(3, '\t');
+            let (id, target, name) = (fields.next()?, fields.next()?, fields.next()?);
+            let activate = format!(
+                "tmux switch-client -c {} -t {}",
+                shell_quote(client),
+                shell_quote(target)
+            );
+            let mut task = virtual_task("tmux", id, "tmux", &format!("{target} {name}"), activate);
+            task.host = Some(terminal);
+            Some(task)
+        })
+        .collect()
+}
+/// whether `ancestor` is `pid` or one of its parent processes
+pub fn is_descendant(mut pid: u32, ancestor: u32) -> bool {
+    while pid > 1 {
+        if pid == ancestor {
+            return true;
+        }
+        // the parent is the second field after the name, which is parenthesized
+        let Some(ppid) = read_to_string(format!("/proc/{pid}/stat"))
+            .ok()
+            .and_then(|stat| {
+                let (_, fields) = stat.rsplit_once(')')?;
+                fields.split_whitespace().nth(1)?.parse().ok()
+            })
+        else {
+            return false;
+        };
+        pid = ppid;
+    }
+    false
+}
+pub fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "'\\''"))
+}
+pub fn class_matches((instance, class): &(String, String), classes: &[String]) -> bool {
+    classes
+        .iter()
+        .any(|c| c.eq_ignore_ascii_case(instance) || c.eq_ignore_ascii_case(class))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+    use std::time::Duration;
+    use std::time::Instant;
+
+    use x11rb::protocol::xproto::Screen;
+    use x11rb::protocol::xproto::Window;
+
+    use super::ACTIONS;
+    use super::Action;
+    use super::Filter;
+    use super::Geometry;
+    use super::Monitor;
+    use super::MonitorLabels;
+    use super::Row;
+    use super::Sort;
+    use super::Task;
+    use super::TaskList;
+    use super::VIRTUAL_WID;
+    use super::VirtualQuery;
+    use super::VirtualRefresh;
+    use super::build_palette;
+    use super::fuzzy_match;
+    use super::get_provided_tasks;
+    use super::is_descendant;
+    use super::palette_action;
+    use super::parse_provided_task;
+    use super::run_provider;
+    use super::sanitize_title;
+    use super::shell_quote;
+    use super::strip_field_codes;
+    use super::virtual_task;
+    use crate::APP_NAME;
+    use crate::PROVIDER_POLL;
+    use crate::cli::load_aliases;
+    use crate::cli::store_alias;
+    use crate::config::Anchor;
+    use crate::config::Config;
+    use crate::config::ListLayout;
+    use crate::config::MonitorChoice;
+    use crate::config::State;
+    use crate::config::str_to_aliases;
+    use crate::config::str_to_monitor;
+    use crate::config::str_to_title_fallback;
+    use crate::config::str_to_title_format;
+    use crate::mock::DPI;
+    use crate::mock::parse;
+    use crate::render::Area;
+    use crate::render::compute_window_geometry;
+    use crate::x11::find_modal_dialog;
+
+    /// a window of class `term` titled after its id
+    fn window_task(wid: Window, geometry: Option<Geometry>) -> Task {
+        Task {
+            wid,
+            title: wid.to_string(),
+            icon_name: None,
+            role: None,
+            visible_name: None,
+            alias: None,
+            class: ("term".to_string(), "term".to_string()),
+            created: Instant::now(),
+            desktop: None,
+            parent: None,
+            geometry,
+            pingable: false,
+            ping: None,
+            responsive: true,
+            activate: None,
+            host: None,
+            command: None,
+            closed: None,
+        }
+    }
+
+    #[test]
+    fn framed_geometry() {
+        let client = Geometry {
+            x: 100,
+            y: 80,
+            w: 400,
+            h: 300,
+        };
+        let frame = client.framed([2, 2, 24, 2]);
+        assert_eq!((frame.x, frame.y, frame.w, frame.h), (98, 56, 404, 326));
+        assert_eq!(frame.center(), (300, 219));
+    }
+
+    #[test]
+    fn sanitized_titles() {
+        assert_eq!(sanitize_title("  vim\tmain.rs\n"), "vim main.rs");
+        assert_eq!(
+            sanitize_title("\x1b[1;31mbuild\x1b[0m failed\x07"),
+            "build failed"
+        );
+        assert_eq!(sanitize_title("a\x1b]b  c"), "ab c");
+        assert_eq!(sanitize_title("\u{1b}"), "");
+        assert_eq!(sanitize_title("résumé — draft"), "résumé — draft");
+    }
+
+    #[test]
+    fn fuzzy_matching() {
+        assert_eq!(fuzzy_match("", "any"), Some((0, vec![])));
+        assert_eq!(fuzzy_match("xyz", "firefox"), None);
+        assert_eq!(fuzzy_match("fox", "fo"), None);
+        // in order only
+        assert_eq!(fuzzy_match("of", "fo"), None);
+        let (_, matched) = fuzzy_match("ff", "firefox").unwrap();
+        assert_eq!(matched, [0, 4]);
+        // the chars are placed at the starts of the words
+        let (_, matched) = fuzzy_match("mr", "vim main.rs").unwrap();
+        assert_eq!(matched, [4, 9]);
+        let (_, matched) = fuzzy_match("gc", "goCode").unwrap();
+        assert_eq!(matched, [0, 2]);
+        // consecutive chars beat scattered ones
+        let score = |pattern, text| fuzzy_match(pattern, text).unwrap().0;
+        assert!(score("term", "terminal") > score("term", "the rust manual"));
+        assert!(score("ma", "vim main.rs") > score("ma", "vim lemma"));
+        // smart case
+        assert!(fuzzy_match("firefox", "Firefox").is_some());
+        assert!(fuzzy_match("Firefox", "firefox").is_none());
+        // the offsets are in bytes
+        let (_, matched) = fuzzy_match("sd", "résumé draft").unwrap();
+        assert_eq!(matched, [3, 9]);
+    }
+
+    #[test]
+    fn queried_tasks() {
+        let mut tasks = TaskList::new();
+        for (wid, title, class) in [
+            (1, "vim main.rs", "term"),
+            (2, "Mozilla Firefox", "firefox"),
+            (3, "notes.md", "editor"),
+        ] {
+            let class = (class.to_string(), class.to_string());
+            let title = title.to_string();
+            tasks.track(Task {
+                title,
+                class,
+                ..window_task(wid, None)
+            });
+        }
+        tasks.focus_by_wid(1);
+        tasks.set_query(Some(String::new()));
+        assert_eq!(tasks.len(), 3);
+        assert_eq!(tasks.selected().map(|task| task.wid), Some(1));
+
+        // the best match is selected
+        tasks.set_query(Some("ff".to_string()));
+        assert_eq!(tasks.len(), 1);
+        assert_eq!(tasks.selected().map(|task| task.wid), Some(2));
+        assert_eq!(tasks.query_matches(tasks.selected().unwrap()), [8, 12]);
+        // the class matches too, with nothing to highlight in the label
+        tasks.set_query(Some("editor".to_string()));
+        assert_eq!(tasks.selected().map(|task| task.wid), Some(3));
+        assert!(tasks.query_matches(tasks.selected().unwrap()).is_empty());
+        tasks.set_query(Some("qq".to_string()));
+        assert!(tasks.is_empty());
+        assert!(tasks.selected().is_none());
+
+        tasks.set_query(None);
+        assert_eq!(tasks.len(), 3);
+    }
+
+    #[test]
+    fn current_monitor_filter() {
+        let monitor = |name: &str, x| Monitor {
+            name: name.to_string(),
+            geometry: Geometry {
+                x,
+                y: 0,
+                w: 1920,
+                h: 1080,
+            },
+            primary: false,
+        };
+        let mut tasks = TaskList::new();
+        for (wid, x) in [(1, 100), (2, 2000), (3, 300)] {
+            let geometry = Geometry {
+                x,
+                y: 100,
+                w: 400,
+                h: 300,
+            };
+            tasks.track(window_task(wid, Some(geometry)));
+        }
+        let wids: Vec<_> = tasks.tasks.iter().map(|task| task.wid).collect();
+        tasks.set_monitors(
+            vec![monitor("DP-1", 0), monitor("DP-2", 1920)],
+            MonitorLabels::Off,
+        );
+        assert!(tasks.tracks_geometry());
+        tasks.focus_by_wid(wids[0]);
+        assert_eq!(tasks.len(), 3);
+        tasks.toggle_monitor();
+        assert_eq!(tasks.filter, Filter::CurrentMonitor);
+        assert_eq!(tasks.len(), 2);
+        tasks.focus_by_wid(wids[1]);
+        assert_eq!(tasks.len(), 1);
+        tasks.toggle_monitor();
+        assert_eq!(tasks.len(), 3);
+    }
+
+    #[test]
+    fn grouped_by_class() {
+        let mut tasks = TaskList::new();
+        for (wid, class) in [(1, "term"), (2, "web"), (3, "term")] {
+            let class = (class.to_string(), class.to_string());
+            tasks.track(Task {
+                class,
+                ..window_task(wid, None)
+            });
+        }
+        tasks.set_group_by_class(true);
+        assert_eq!(tasks.rows(), 5);
+        let (rows, _) = tasks.rows_descending();
+        let headers: Vec<_> = rows
+            .iter()
+            .filter_map(|row| match row {
+                Row::Header(name) => Some(name.as_str()),
+                Row::Task(_) => None,
+            })
+            .collect();
+        assert_eq!(headers.len(), 2);
+        let counts: Vec<_> = tasks.tasks.iter().map(|t| tasks.group_len(t)).collect();
+        assert_eq!(counts, [2, 1, 2]);
+        tasks.set_group_by_class(false);
+        assert_eq!(tasks.rows(), 3);
+    }
+
+    #[test]
+    fn placed_on_the_chosen_monitor() {
+        let screen = Screen {
+            width_in_pixels: 3840,
+            height_in_pixels: 1080,
+            ..Default::default()
+        };
+        let mut tasks = TaskList::new();
+        for (wid, x) in [(1, 100), (2, 2000)] {
+            let geometry = Geometry {
+                x,
+                y: 100,
+                w: 400,
+                h: 300,
+            };
+            tasks.track(window_task(wid, Some(geometry)));
+        }
+        let wids: Vec<_> = tasks.tasks.iter().map(|task| task.wid).collect();
+        tasks.focus_by_wid(wids[1]);
+        let focused = MonitorChoice::Focused;
+        assert_eq!(
+            tasks.output(&screen, &focused, None),
+            Area::of_screen(&screen)
+        );
+
+        let monitor = |name: &str, x, primary| Monitor {
+            name: name.to_string(),
+            geometry: Geometry {
+                x,
+                y: 0,
+                w: 1920,
+                h: 1080,
+            },
+            primary,
+        };
+        let monitors = vec![monitor("DP-1", 0, true), monitor("DP-2", 1920, false)];
+        tasks.set_monitors(monitors, MonitorLabels::Off);
+        let output = tasks.output(&screen, &focused, None);
+        assert_eq!(output, Area::new(1920.0, 0.0, 1920.0, 1080.0));
+        let (conf, _) = parse(
+            "location: 5\nwindow_size: fixed\nwidth: 50%\nheight: 50%",
+            DPI,
+        );
+        let g = compute_window_geometry(&conf, output, None, 2).unwrap();
+        assert_eq!(g, Area::new(2400.0, 270.0, 960.0, 540.0));
+        tasks.focus_by_wid(wids[0]);
+        assert_eq!(tasks.output(&screen, &focused, None).x, 0.0);
+
+        tasks.focus_by_wid(wids[1]);
+        let output = |value: &str, pointer| {
+            let choice = str_to_monitor(value).unwrap();
+            tasks.output(&screen, &choice, pointer).x
+        };
+        assert_eq!(output("pointer", Some((10, 10))), 0.0);
+        assert_eq!(output("pointer", None), 1920.0);
+        assert_eq!(output("primary", None), 0.0);
+        assert_eq!(output("DP-1", None), 0.0);
+        // an output that isn't connected
+        assert_eq!(output("HDMI-1", None), 1920.0);
+        assert!(str_to_monitor(" ").is_err());
+    }
+
+    #[test]
+    fn providers_are_killed_with_their_children() {
+        let timeout = Duration::from_millis(300);
+        assert_eq!(run_provider("echo a", timeout).unwrap(), "a\n");
+        assert!(run_provider("echo a; exit 1", timeout).is_err());
+        let start = Instant::now();
+        assert!(run_provider("sleep 10", timeout).is_err());
+        assert!(start.elapsed() < Duration::from_secs(5));
+
+        // the command exits at once, the process it leaves behind holds the output open
+        let dir = std::env::temp_dir().join(format!("{APP_NAME}-provider-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("pid");
+        let command = format!("echo a; sleep 10 & echo $! > {}", path.display());
+        let start = Instant::now();
+        assert!(run_provider(&command, timeout).is_err());
+        assert!(start.elapsed() < Duration::from_secs(5));
+        let pid = std::fs::read_to_string(&path).unwrap();
+        let stat = format!("/proc/{}/stat", pid.trim());
+        let is_alive = || std::fs::read_to_string(&stat).is_ok_and(|stat| !stat.contains(") Z "));
+        while is_alive() && start.elapsed() < Duration::from_secs(5) {
+            std::thread::sleep(PROVIDER_POLL);
+        }
+        assert!(!is_alive());
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn provided_tasks() {
+        let task = parse_provided_task("tabs", "7\tfirefox\tNews\tfocus-tab 7").unwrap();
+        assert!(task.is_virtual() && task.wid & VIRTUAL_WID != 0);
+        assert_eq!(task.class.1, "firefox");
+        assert_eq!(task.title, "News");
+        assert_eq!(task.activate.as_deref(), Some("focus-tab 7"));
+        // the same id from the same provider is the same task
+        let again = parse_provided_task("tabs", "7\tfirefox\tRenamed\tfocus-tab 7").unwrap();
+        assert_eq!(task, again);
+        assert_ne!(
+            task,
+            parse_provided_task("tmux", "7\ttmux\tNews\tx").unwrap()
+        );
+        assert!(parse_provided_task("tabs", "7\tfirefox\tNews").is_none());
+        // a dialog of a dialog is the one to focus
+        let windows = [(2, 1, true), (3, 2, true), (4, 1, false)];
+        assert_eq!(find_modal_dialog(1, &windows), Some(3));
+        assert_eq!(find_modal_dialog(4, &windows), None);
+        assert_eq!(find_modal_dialog(1, &[(2, 1, true), (1, 2, true)]), Some(2));
+
+        // nothing to check the focus of, unless a window hosts the task
+        assert_eq!(task.focus_target(), None);
+        let mut hosted = parse_provided_task("tabs", "8\tfirefox\tMail\tx").unwrap();
+        hosted.host = Some(0x400001);
+        assert_eq!(hosted.focus_target(), Some(0x400001));
+        assert!(parse_provided_task("tabs", "7\t\tNews\tx").is_none());
+        // a failing provider doesn't take the others down, and runs off the event loop
+        let providers = [
+            "exit 1".to_string(),
+            "printf '1\\tterm\\tA\\tx\\n'".to_string(),
+        ];
+        assert_eq!(get_provided_tasks(&providers).len(), 1);
+        let refresh = VirtualRefresh::new();
+        refresh.start(VirtualQuery {
+            providers: providers.to_vec(),
+            terminal: None,
+        });
+        while refresh.is_running() {
+            std::thread::sleep(PROVIDER_POLL);
+        }
+        assert_eq!(refresh.take().map(|tasks| tasks.len()), Some(1));
+        assert!(refresh.take().is_none());
+
+        let mut tasks = TaskList::new();
+        let wid = task.wid;
+        let (_, added) = tasks.update_virtual(vec![task]);
+        assert_eq!(added, [wid]);
+        let (removed, added) = tasks.update_virtual(vec![again]);
+        assert!(removed.is_empty() && added.is_empty());
+        assert_eq!(tasks.get_task_by_id(wid).unwrap().title, "Renamed");
+        tasks.update_icon_name(wid, Some("news".to_string()));
+        assert_eq!(tasks.label(tasks.get_task_by_id(wid).unwrap()), "Renamed");
+        tasks.update_title(wid, String::new());
+        assert_eq!(tasks.label(tasks.get_task_by_id(wid).unwrap()), "news");
+        tasks.update_icon_name(wid, None);
+        let class = ("firefox".to_string(), "Firefox".to_string());
+        assert!(tasks.update_class(wid, class.clone()));
+        assert!(!tasks.update_class(wid, class));
+        assert_eq!(tasks.label(tasks.get_task_by_id(wid).unwrap()), "Firefox");
+        tasks.set_title_fallback(str_to_title_fallback("icon_name, no title").unwrap());
+        assert_eq!(tasks.label(tasks.get_task_by_id(wid).unwrap()), "no title");
+        tasks.update_role(wid, Some("browser".to_string()));
+        let format = "{class}: {title} ({role}) {visible_name}";
+        tasks.set_title_format(str_to_title_format(format).unwrap());
+        let label = tasks.label(tasks.get_task_by_id(wid).unwrap()).into_owned();
+        assert_eq!(label, "Firefox: no title (browser)");
+        assert!(str_to_title_format("{title} {name}").is_err());
+        assert!(str_to_title_format("{title").is_err());
+        tasks.set_aliases(str_to_aliases("term=shell, FIREFOX = web").unwrap());
+        let label = tasks.label(tasks.get_task_by_id(wid).unwrap()).into_owned();
+        assert_eq!(label, "Firefox: web (browser)");
+        let mut state = State {
+            values: BTreeMap::new(),
+            path: None,
+        };
+        let class = ("firefox".to_string(), "Firefox".to_string());
+        store_alias(&mut state, &class, " news ");
+        tasks.set_saved_aliases(load_aliases(&state));
+        assert_eq!(tasks.title(tasks.get_task_by_id(wid).unwrap()), "news");
+        // the alias of the window wins over the one of its class
+        tasks.update_alias(wid, Some("mail".to_string()));
+        assert_eq!(tasks.title(tasks.get_task_by_id(wid).unwrap()), "mail");
+        tasks.update_alias(wid, None);
+        assert_eq!(tasks.title(tasks.get_task_by_id(wid).unwrap()), "news");
+        store_alias(&mut state, &class, "");
+        assert!(state.values.is_empty());
+        let class = ("web/app: 100%".to_string(), "Web App".to_string());
+        store_alias(&mut state, &class, "app");
+        assert_eq!(
+            load_aliases(&state).get(&class).map(String::as_str),
+            Some("app")
+        );
+        assert!(
+            state
+                .values
+                .keys()
+                .all(|key| !key.contains(':') && key.matches('/').count() == 1)
+        );
+        assert!(str_to_aliases("firefox").is_err());
+        assert!(str_to_aliases("=web").is_err());
+        let (removed, _) = tasks.update_virtual(vec![]);
+        assert_eq!(removed.len(), 1);
+        assert!(tasks.is_empty());
+
+        assert_eq!(shell_quote("it's"), r#"'it'\''s'"#);
+        let pid = std::process::id();
+        assert!(is_descendant(pid, std::os::unix::process::parent_id()));
+        assert!(!is_descendant(std::os::unix::process::parent_id(), pid));
+    }
+
+    #[test]
+    fn closed_history() {
+        let window = |wid: Window, command: Option<&str>| Task {
+            wid,
+            activate: None,
+            command: command.map(String::from),
+            ..virtual_task("", "", "goto-test", "", String::new())
+        };
+        let mut tasks = TaskList::new();
+        tasks.set_history(1);
+        tasks.track(window(1, Some("app --one")));
+        tasks.track(window(2, None));
+        tasks.track(window(3, Some("app --three")));
+        tasks.close(1);
+        let closed = tasks.get_task_by_id(VIRTUAL_WID | 1).unwrap();
+        assert!(closed.closed.is_some() && closed.is_virtual());
+        assert_eq!(closed.activate.as_deref(), Some("app --one"));
+        // nothing to reopen it with
+        tasks.close(2);
+        assert!(tasks.get_task_by_id(VIRTUAL_WID | 2).is_none());
+        // the oldest entry makes room
+        tasks.close(3);
+        let wids: Vec<_> = tasks.list_ascending().0.map(|task| task.wid).collect();
+        assert_eq!(wids, [VIRTUAL_WID | 3]);
+
+        assert_eq!(
+            strip_field_codes("firefox %u --name=50%% -- %F"),
+            "firefox --name=50% --"
+        );
+        assert_eq!(
+            strip_field_codes("env FOO=1 /usr/bin/code --new-window %F"),
+            "env FOO=1 /usr/bin/code --new-window"
+        );
+    }
+
+    #[test]
+    fn palette_actions() {
+        let mut themes = [Config::defaults(DPI), Config::defaults(DPI)];
+        let mut tasks = TaskList::new();
+        let mut palette = build_palette(&themes[0], &tasks);
+        assert_eq!(palette_action(&palette), Some(ACTIONS[0]));
+        palette.select_older();
+        assert_eq!(palette_action(&palette), Some(Action::SwitchLayout));
+
+        Action::SwitchLayout.apply(&mut themes, &mut tasks);
+        assert!(
+            themes
+                .iter()
+                .all(|conf| matches!(conf.layout, ListLayout::Columns))
+        );
+        Action::ChangeSort.apply(&mut themes, &mut tasks);
+        assert_eq!(tasks.sort, Sort::Stacking);
+        Action::ToggleScope.apply(&mut themes, &mut tasks);
+        assert_eq!(tasks.filter, Filter::CurrentDesktop);
+        let settings: Vec<_> = ACTIONS
+            .iter()
+            .map(|action| action.setting(&themes[0], &tasks))
+            .map(|(key, val)| format!("{key}: {val}"))
+            .collect();
+        let mut restored = Config::defaults(DPI);
+        assert!(restored.parse(&settings.join("\n"), DPI).is_empty());
+        assert_eq!(restored.sort, Sort::Stacking);
+        assert_eq!(restored.filter, Filter::CurrentDesktop);
+        let palette = build_palette(&themes[0], &tasks);
+        let labels: Vec<_> = palette
+            .list_descending()
+            .0
+            .map(|t| t.title.clone())
+            .collect();
+        assert_eq!(
+            labels,
+            [
+                "toggle icons (true)",
+                "switch layout (columns)",
+                "change sort (stacking)",
+                "toggle scope (current desktop)",
+                "move list (location 5)",
+            ]
+        );
+        Action::MoveList.apply(&mut themes, &mut tasks);
+        assert_eq!(themes[1].anchor, Anchor::RIGHT);
+        themes[0].anchor = Anchor::BOTTOM_RIGHT;
+        Action::MoveList.apply(&mut themes, &mut tasks);
+        assert_eq!(themes[0].anchor, Anchor::TOP_LEFT);
+    }
+}
@@ -0,0 +1,1403 @@
+use std::collections::HashMap;
+use std::time::Duration;
+use std::time::Instant;
+
+use rustix::event::PollFd;
+use rustix::event::PollFlags;
+use rustix::event::Timespec;
+use rustix::io::Errno;
+use x11rb::atom_manager;
+use x11rb::connection::Connection;
+use x11rb::connection::RequestConnection;
+use x11rb::properties::WmHints;
+use x11rb::properties::WmSizeHints;
+use x11rb::properties::WmSizeHintsSpecification;
+use x11rb::protocol::composite;
+use x11rb::protocol::composite::ConnectionExt as _;
+use x11rb::protocol::damage;
+use x11rb::protocol::damage::ConnectionExt as _;
+use x11rb::protocol::damage::Damage;
+use x11rb::protocol::randr::ConnectionExt as _;
+use x11rb::protocol::render::ConnectionExt as _;
+use x11rb::protocol::render::PictType;
+use x11rb::protocol::xinput;
+use x11rb::protocol::xinput::DeviceId;
+use x11rb::protocol::xinput::XIEventMask;
+use x11rb::protocol::xproto::Atom;
+use x11rb::protocol::xproto::AtomEnum;
+use x11rb::protocol::xproto::CLIENT_MESSAGE_EVENT;
+use x11rb::protocol::xproto::ChangeWindowAttributesAux;
+use x11rb::protocol::xproto::ClientMessageData;
+use x11rb::protocol::xproto::ClientMessageEvent;
+use x11rb::protocol::xproto::ColormapAlloc;
+use x11rb::protocol::xproto::ConfigureWindowAux;
+use x11rb::protocol::xproto::ConnectionExt as _;
+use x11rb::protocol::xproto::CreateGCAux;
+use x11rb::protocol::xproto::CreateWindowAux;
+use x11rb::protocol::xproto::Cursor;
+use x11rb::protocol::xproto::Drawable;
+use x11rb::protocol::xproto::EventMask;
+use x11rb::protocol::xproto::Gcontext;
+use x11rb::protocol::xproto::Grab;
+use x11rb::protocol::xproto::ImageFormat;
+use x11rb::protocol::xproto::ImageOrder;
+use x11rb::protocol::xproto::InputFocus;
+use x11rb::protocol::xproto::MapState;
+use x11rb::protocol::xproto::ModMask;
+use x11rb::protocol::xproto::PropMode;
+use x11rb::protocol::xproto::Screen;
+use x11rb::protocol::xproto::StackMode;
+use x11rb::protocol::xproto::Visualid;
+use x11rb::protocol::xproto::Window;
+use x11rb::protocol::xproto::WindowClass;
+use x11rb::resource_manager::Database;
+use x11rb::wrapper::ConnectionExt as _;
+
+use crate::ACTIVATION_TIMEOUT;
+use crate::APP_NAME;
+use crate::Atoms;
+use crate::Conn;
+use crate::Result;
+use crate::config::ColorScheme;
+use crate::config::Config;
+use crate::config::INCH_TO_MM;
+use crate::config::Stacking;
+use crate::render::Area;
+use crate::render::Frame;
+use crate::render::PointerOrigin;
+use crate::tasks::Geometry;
+use crate::tasks::ICONIC_STATE;
+use crate::tasks::Monitor;
+use crate::tasks::Sort;
+use crate::tasks::Task;
+use crate::tasks::class_matches;
+use crate::tasks::sanitize_title;
+use crate::tasks::spawn_shell;
+
+/// a value of the xsettings manager
+#[derive(Debug, Clone, PartialEq)]
+pub enum XSetting {
+    Int(i32),
+    String(String),
+    Color([u16; 4]),
+}
+atom_manager! {
+    pub AtomCollection: AtomCollectionCookie {
+        ATOM,
+        WM_PROTOCOLS,
+        WM_DELETE_WINDOW,
+        WM_TAKE_FOCUS,
+        UTF8_STRING,
+        WM_NAME,
+        WM_ICON_NAME,
+        WM_CLASS,
+        CARDINAL,
+        STRING,
+        WINDOW,
+        WM_TRANSIENT_FOR,
+        WM_STATE,
+        WM_WINDOW_ROLE,
+
+        _NET_WM_PID,
+        _NET_WM_STATE,
+        _NET_WM_STATE_ABOVE,
+        _NET_WM_STATE_MODAL,
+        _NET_WM_STATE_HIDDEN,
+        _NET_WM_NAME,
+        _NET_WM_ICON,
+        _NET_WM_ICON_NAME,
+        _NET_WM_VISIBLE_NAME,
+        _NET_WM_WINDOW_OPACITY,
+        _NET_ACTIVE_WINDOW,
+        _NET_RESTACK_WINDOW,
+        _NET_CLIENT_LIST,
+        _NET_CLIENT_LIST_STACKING,
+        _NET_WM_DESKTOP,
+        _NET_CURRENT_DESKTOP,
+        _NET_FRAME_EXTENTS,
+        _NET_WORKAREA,
+        _NET_DESKTOP_NAMES,
+        _NET_WM_STATE_SKIP_TASKBAR,
+        _NET_WM_STATE_SKIP_PAGER,
+        _NET_WM_WINDOW_TYPE,
+        _NET_WM_WINDOW_TYPE_DIALOG,
+
+        _NET_WM_PING,
+
+        MANAGER,
+        _XSETTINGS_SETTINGS,
+        RESOURCE_MANAGER,
+
+        _GOTO_SELECTION,
+        _GOTO_ALIAS,
+        _GOTO_SUSPENDED,
+    }
+}
+/// releases everything goto holds on the server, also when unwinding from a panic
+pub struct Teardown<'a> {
+    pub conn: &'a Conn,
+    pub root: Window,
+    pub window: Window,
+    pub selection: Atom,
+    pub suspended: Atom,
+}
+impl Drop for Teardown<'_> {
+    fn drop(&mut self) {
+        let _ = self.conn.ungrab_key(Grab::ANY, self.root, ModMask::ANY);
+        let _ = self.conn.ungrab_keyboard(x11rb::CURRENT_TIME);
+        let _ = xinput::ConnectionExt::xinput_xi_select_events(
+            self.conn,
+            self.root,
+            &[xinput::EventMask {
+                deviceid: DeviceId::from(0u16),
+                mask: vec![XIEventMask::from(0u32)],
+            }],
+        );
+        let _ = self.conn.destroy_window(self.window);
+        let _ = self.conn.delete_property(self.root, self.selection);
+        let _ = self.conn.delete_property(self.root, self.suspended);
+        let _ = self.conn.flush();
+    }
+}
+/// the timers of the event loop, each one is either armed once or not at all
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Timer {
+    /// expires the pings and shows the tooltip while the list is mapped
+    Tick,
+    /// wakes up the loop now and then, which compares the clocks to notice a resume from suspend
+    Clock,
+    /// checks that the wm focused the window goto activated
+    Activation,
+    /// draws the changes held back by `redraw_interval`
+    Redraw,
+    /// sends the next frame of `enter_animation`
+    Enter,
+    /// captures the windows damaged since the last time, see `thumbnails`
+    Thumbnails,
+    /// hides the notice shown when the grabs are resumed
+    Notice,
+    /// looks for the results of the providers, while they run
+    Providers,
+}
+/// waits on the x connection and the timers, so that nothing has to wake up the loop from a
+/// thread. other file descriptors (sockets, inotify) can be polled alongside the connection
+#[derive(Default)]
+pub struct Reactor {
+    pub deadlines: Vec<(Timer, Instant)>,
+}
+impl Reactor {
+    pub fn new() -> Self {
+        Self { deadlines: vec![] }
+    }
+    /// arms the timer, rearming it if it's already armed
+    pub fn set_timer(&mut self, timer: Timer, after: Duration) {
+        self.cancel_timer(timer);
+        self.deadlines.push((timer, Instant::now() + after));
+    }
+    pub fn cancel_timer(&mut self, timer: Timer) {
+        self.deadlines.retain(|(t, _)| *t != timer);
+    }
+    /// returns the expired timers, waiting for the first one or for the connection to be
+    /// readable if `block` is set
+    pub fn wait(&mut self, conn: &Conn, block: bool) -> Result<Vec<Timer>> {
+        if block {
+            let now = Instant::now();
+            let timeout = self
+                .deadlines
+                .iter()
+                .map(|(_, deadline)| deadline.saturating_duration_since(now))
+                .min()
+                .map(Timespec::try_from)
+                .transpose()?;
+            let mut fds = [PollFd::new(conn.stream(), PollFlags::IN)];
+            match rustix::event::poll(&mut fds, timeout.as_ref()) {
+                Ok(_) | Err(Errno::INTR) => {}
+                Err(e) => return Err(e.into()),
+            }
+        }
+        let now = Instant::now();
+        let (expired, pending) = self
+            .deadlines
+            .drain(..)
+            .partition(|(_, deadline)| *deadline <= now);
+        self.deadlines = pending;
+        Ok(expired.into_iter().map(|(timer, _)| timer).collect())
+    }
+}
+pub fn create_window(
+    conn: &Conn,
+    screen: &Screen,
+    atoms: &Atoms,
+    geometry: Area,
+    depth: u8,
+    visual: Visualid,
+    stacking: Stacking,
+) -> Result<Window> {
+    let window = conn.generate_id()?;
+    let colormap = conn.generate_id()?;
+    conn.create_colormap(ColormapAlloc::NONE, colormap, screen.root, visual)?;
+    let win_aux = CreateWindowAux::new()
+        .event_mask(EventMask::EXPOSURE | EventMask::KEY_PRESS | EventMask::KEY_RELEASE)
+        .colormap(colormap)
+        .override_redirect(u32::from(stacking == Stacking::Above));
+    conn.create_window(
+        depth,
+        window,
+        screen.root,
+        geometry.x as i16,
+        geometry.y as i16,
+        geometry.w as u16,
+        geometry.h as u16,
+        0,
+        WindowClass::INPUT_OUTPUT,
+        visual,
+        &win_aux,
+    )?;
+    conn.change_property8(
+        PropMode::REPLACE,
+        window,
+        atoms.WM_NAME,
+        atoms.STRING,
+        APP_NAME.as_bytes(),
+    )?;
+    conn.change_property8(
+        PropMode::REPLACE,
+        window,
+        atoms._NET_WM_NAME,
+        atoms.UTF8_STRING,
+        APP_NAME.as_bytes(),
+    )?;
+    conn.change_property8(
+        PropMode::REPLACE,
+        window,
+        atoms.WM_CLASS,
+        atoms.STRING,
+        APP_NAME.as_bytes(),
+    )?;
+    // with `stacking: normal` the wm manages the window and picks its layer
+    let state = match stacking {
+        Stacking::Above => atoms._NET_WM_STATE_ABOVE,
+        Stacking::Normal => atoms._NET_WM_STATE_SKIP_PAGER,
+    };
+    conn.change_property32(
+        PropMode::REPLACE,
+        window,
+        atoms._NET_WM_STATE,
+        atoms.ATOM,
+        &[atoms._NET_WM_STATE_SKIP_TASKBAR, state],
+    )?;
+    if stacking == Stacking::Normal {
+        // the wm places a managed window, unless told it was placed on purpose
+        let mut hints = WmSizeHints::new();
+        hints.position = Some((
+            WmSizeHintsSpecification::UserSpecified,
+            geometry.x as i32,
+            geometry.y as i32,
+        ));
+        hints.size = Some((
+            WmSizeHintsSpecification::UserSpecified,
+            geometry.w as i32,
+            geometry.h as i32,
+        ));
+        hints.set_normal_hints(conn, window)?;
+    }
+    conn.change_property32(
+        PropMode::REPLACE,
+        window,
+        atoms._NET_WM_WINDOW_TYPE,
+        atoms.ATOM,
+        &[atoms._NET_WM_WINDOW_TYPE_DIALOG],
+    )?;
+
+    Ok(window)
+}
+/// publishes the selected task on the root window as `<wid>\t<title>`, for bars to follow, it's
+/// deleted when there is none
+pub fn request_selection_publish(
+    conn: &Conn,
+    screen: &Screen,
+    atoms: &Atoms,
+    selection: Option<&(Window, String)>,
+) -> Result<()> {
+    match selection {
+        Some((wid, title)) => conn.change_property8(
+            PropMode::REPLACE,
+            screen.root,
+            atoms._GOTO_SELECTION,
+            atoms.UTF8_STRING,
+            format!("{wid:#x}\t{title}").as_bytes(),
+        )?,
+        None => conn.delete_property(screen.root, atoms._GOTO_SELECTION)?,
+    };
+    Ok(())
+}
+/// whether the daemon is suspended, as `1` or `0` on the root window while it runs, writing it
+/// suspends or resumes the daemon
+pub fn request_suspended_publish(
+    conn: &Conn,
+    screen: &Screen,
+    atoms: &Atoms,
+    suspended: bool,
+) -> Result<()> {
+    conn.change_property32(
+        PropMode::REPLACE,
+        screen.root,
+        atoms._GOTO_SUSPENDED,
+        AtomEnum::CARDINAL,
+        &[suspended as u32],
+    )?;
+    Ok(())
+}
+/// none when no daemon runs on the display
+pub fn get_suspended(conn: &Conn, screen: &Screen, atoms: &Atoms) -> Result<Option<bool>> {
+    let reply = conn
+        .get_property(
+            false,
+            screen.root,
+            atoms._GOTO_SUSPENDED,
+            AtomEnum::CARDINAL,
+            0,
+            1,
+        )?
+        .reply()?;
+    Ok(reply.value32().and_then(|mut it| it.next()).map(|v| v != 0))
+}
+pub fn send_frame(conn: &Conn, wid: Window, gc: Gcontext, frame: &Frame, depth: u8) -> Result<()> {
+    let format = ImageFormat::Z_PIXMAP;
+    let w = frame.width() as u16;
+    let h = frame.height() as u16;
+    conn.put_image(format, wid, gc, w, h, 0, 0, 0, depth, frame.buf_u8())?;
+    Ok(())
+}
+pub fn request_window_close(conn: &Conn, atoms: &Atoms, wid: Window) -> Result<()> {
+    let ev = ClientMessageEvent {
+        response_type: CLIENT_MESSAGE_EVENT,
+        format: 32,
+        sequence: 0,
+        window: wid,
+        type_: atoms.WM_PROTOCOLS,
+        data: ClientMessageData::from([atoms.WM_DELETE_WINDOW, x11rb::CURRENT_TIME, 0, 0, 0]),
+    };
+    conn.send_event(false, wid, EventMask::NO_EVENT, ev)?;
+    Ok(())
+}
+pub fn request_window_ping(conn: &Conn, atoms: &Atoms, wid: Window) -> Result<()> {
+    let ev = ClientMessageEvent {
+        response_type: CLIENT_MESSAGE_EVENT,
+        format: 32,
+        sequence: 0,
+        window: wid,
+        type_: atoms.WM_PROTOCOLS,
+        data: ClientMessageData::from([atoms._NET_WM_PING, x11rb::CURRENT_TIME, wid, 0, 0]),
+    };
+    conn.send_event(false, wid, EventMask::NO_EVENT, ev)?;
+    Ok(())
+}
+pub fn request_window_focus(
+    conn: &Conn,
+    screen: &Screen,
+    atoms: &Atoms,
+    wid: Window,
+) -> Result<()> {
+    conn.send_event(
+        false,
+        screen.root,
+        EventMask::SUBSTRUCTURE_REDIRECT | EventMask::SUBSTRUCTURE_NOTIFY,
+        ClientMessageEvent {
+            response_type: CLIENT_MESSAGE_EVENT,
+            format: 32,
+            sequence: 0,
+            window: wid,
+            type_: atoms._NET_ACTIVE_WINDOW,
+            data: ClientMessageData::from([1, x11rb::CURRENT_TIME, 0, 0, 0]),
+        },
+    )?;
+
+    // some clients (e.g. java apps) only react to the icccm focus models
+    let attr = conn.get_window_attributes(wid)?.reply()?;
+    if attr.map_state != MapState::VIEWABLE {
+        return Ok(());
+    }
+    let input = WmHints::get(conn, wid)?
+        .reply()?
+        .and_then(|hints| hints.input)
+        .unwrap_or(true);
+    let take_focus = get_window_protocols(conn, atoms, wid)?.contains(&atoms.WM_TAKE_FOCUS);
+    if input {
+        conn.set_input_focus(InputFocus::PARENT, wid, x11rb::CURRENT_TIME)?;
+    }
+    if take_focus {
+        let ev = ClientMessageEvent {
+            response_type: CLIENT_MESSAGE_EVENT,
+            format: 32,
+            sequence: 0,
+            window: wid,
+            type_: atoms.WM_PROTOCOLS,
+            data: ClientMessageData::from([atoms.WM_TAKE_FOCUS, x11rb::CURRENT_TIME, 0, 0, 0]),
+        };
+        conn.send_event(false, wid, EventMask::NO_EVENT, ev)?;
+    }
+    Ok(())
+}
+/// restores and raises a minimized window, for the stacking wms that only give it the focus
+pub fn request_window_unminimize(
+    conn: &Conn,
+    screen: &Screen,
+    atoms: &Atoms,
+    wid: Window,
+) -> Result<()> {
+    let hidden = get_window_states(conn, atoms, wid)?.contains(&atoms._NET_WM_STATE_HIDDEN);
+    let iconic = get_window_wm_state(conn, atoms, wid)? == Some(ICONIC_STATE);
+    if !hidden && !iconic {
+        return Ok(());
+    }
+    // icccm: mapping an iconic window asks the wm to bring it back to the normal state
+    conn.map_window(wid)?;
+    let mask = EventMask::SUBSTRUCTURE_REDIRECT | EventMask::SUBSTRUCTURE_NOTIFY;
+    let message = |type_, data: [u32; 5]| ClientMessageEvent {
+        response_type: CLIENT_MESSAGE_EVENT,
+        format: 32,
+        sequence: 0,
+        window: wid,
+        type_,
+        data: ClientMessageData::from(data),
+    };
+    // _NET_WM_STATE_REMOVE, sent as a pager
+    let data = [0, atoms._NET_WM_STATE_HIDDEN, 0, 2, 0];
+    conn.send_event(false, screen.root, mask, message(atoms._NET_WM_STATE, data))?;
+    // a restack above all the siblings
+    let data = [2, x11rb::NONE, u32::from(StackMode::ABOVE), 0, 0];
+    conn.send_event(
+        false,
+        screen.root,
+        mask,
+        message(atoms._NET_RESTACK_WINDOW, data),
+    )?;
+    Ok(())
+}
+/// gives the focus back to a window, or to the root window or none as it was
+pub fn restore_focus(conn: &Conn, screen: &Screen, atoms: &Atoms, focus: Window) -> Result<()> {
+    let pointer_root = u32::from(InputFocus::POINTER_ROOT);
+    if focus == x11rb::NONE || focus == pointer_root || focus == screen.root {
+        conn.set_input_focus(InputFocus::POINTER_ROOT, focus, x11rb::CURRENT_TIME)?;
+    } else {
+        // through the wm, and WM_TAKE_FOCUS for the clients that want it
+        request_window_focus(conn, screen, atoms, focus)?;
+    }
+    conn.flush()?;
+    Ok(())
+}
+/// the pager source indication gets past the focus stealing prevention of most wms, raising
+/// and focusing the window directly covers the ones that ignore the request altogether
+pub fn request_window_focus_fallback(
+    conn: &Conn,
+    screen: &Screen,
+    atoms: &Atoms,
+    wid: Window,
+) -> Result<()> {
+    conn.send_event(
+        false,
+        screen.root,
+        EventMask::SUBSTRUCTURE_REDIRECT | EventMask::SUBSTRUCTURE_NOTIFY,
+        ClientMessageEvent {
+            response_type: CLIENT_MESSAGE_EVENT,
+            format: 32,
+            sequence: 0,
+            window: wid,
+            type_: atoms._NET_ACTIVE_WINDOW,
+            data: ClientMessageData::from([2, x11rb::CURRENT_TIME, 0, 0, 0]),
+        },
+    )?;
+    let aux = ConfigureWindowAux::new().stack_mode(StackMode::ABOVE);
+    conn.configure_window(wid, &aux)?;
+    conn.set_input_focus(InputFocus::PARENT, wid, x11rb::CURRENT_TIME)?;
+    conn.flush()?;
+    Ok(())
+}
+/// returns whether the activation of `wid` is settled, either because the wm focused it or
+/// because the fallback was already tried, otherwise it tries the fallback
+pub fn confirm_activation(
+    conn: &Conn,
+    screen: &Screen,
+    atoms: &Atoms,
+    wid: Window,
+    retried: bool,
+) -> Result<bool> {
+    if get_active_window(conn, screen, atoms)? == Some(wid) {
+        return Ok(true);
+    }
+    if retried {
+        eprintln!("[WARNING] the window manager didn't activate {wid:#x}, even when forced");
+        return Ok(true);
+    }
+    eprintln!(
+        "[WARNING] the window manager didn't activate {wid:#x} within {ACTIVATION_TIMEOUT:?}, forcing it"
+    );
+    request_window_focus_fallback(conn, screen, atoms, wid)?;
+    Ok(false)
+}
+/// blocking version of the `Timer::Activation` check, for the invocations that exit right away
+pub fn wait_for_activation(conn: &Conn, screen: &Screen, atoms: &Atoms, wid: Window) -> Result<()> {
+    for retried in [false, true] {
+        let deadline = Instant::now() + ACTIVATION_TIMEOUT;
+        while Instant::now() < deadline {
+            if get_active_window(conn, screen, atoms)? == Some(wid) {
+                return Ok(());
+            }
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        if confirm_activation(conn, screen, atoms, wid, retried)? {
+            return Ok(());
+        }
+    }
+    Ok(())
+}
+pub fn request_pointer_warp(conn: &Conn, wid: Window) -> Result<()> {
+    // the compositor owns the pointer, xwayland ignores warps outside of pointer locks
+    if is_xwayland(conn) {
+        return Ok(());
+    }
+    // under focus-follows-mouse the wm would give the focus back to the window under the pointer
+    let geom = conn.get_geometry(wid)?.reply()?;
+    let pointer = conn.query_pointer(wid)?.reply()?;
+    let (px, py) = (pointer.win_x, pointer.win_y);
+    if pointer.same_screen
+        && (0..geom.width as i16).contains(&px)
+        && (0..geom.height as i16).contains(&py)
+    {
+        return Ok(());
+    }
+    let (x, y) = ((geom.width / 2) as i16, (geom.height / 2) as i16);
+    conn.warp_pointer(x11rb::NONE, wid, 0, 0, 0, 0, x, y)?;
+    Ok(())
+}
+pub fn request_window_move(conn: &Conn, wid: Window, area: Area) -> Result<()> {
+    conn.configure_window(
+        wid,
+        &ConfigureWindowAux::new()
+            .x(area.x as i32)
+            .y(area.y as i32)
+            .width(area.w as u32)
+            .height(area.h as u32),
+    )?;
+    Ok(())
+}
+/// the opacity of the list, for the compositor, from 0 to 1
+pub fn request_window_opacity(conn: &Conn, atoms: &Atoms, wid: Window, opacity: f32) -> Result<()> {
+    if opacity >= 1.0 {
+        conn.delete_property(wid, atoms._NET_WM_WINDOW_OPACITY)?;
+    } else {
+        let value = (opacity.max(0.0) as f64 * u32::MAX as f64) as u32;
+        conn.change_property32(
+            PropMode::REPLACE,
+            wid,
+            atoms._NET_WM_WINDOW_OPACITY,
+            atoms.CARDINAL,
+            &[value],
+        )?;
+    }
+    Ok(())
+}
+pub fn load_cursor(conn: &Conn, screen_num: usize, db: &Database, name: &str) -> Result<Cursor> {
+    let handle = x11rb::cursor::Handle::new(conn, screen_num, db)?.reply()?;
+    Ok(handle.load_cursor(conn, name)?)
+}
+pub fn create_graphic_context(conn: &Conn, window: Window) -> Result<u32> {
+    let gc = conn.generate_id()?;
+    conn.create_gc(gc, window, &CreateGCAux::new())?;
+    Ok(gc)
+}
+pub fn choose_visual(conn: &Conn, screen_num: usize) -> Result<(u8, Visualid)> {
+    let depth = 32;
+    let screen = &conn.setup().roots[screen_num];
+    if has_render(conn) {
+        let formats = conn.render_query_pict_formats()?.reply()?;
+        let format = formats
+            .formats
+            .iter()
+            .filter(|info| (info.type_, info.depth) == (PictType::DIRECT, depth))
+            .filter(|info| {
+                let d = info.direct;
+                (d.red_mask, d.green_mask, d.blue_mask, d.alpha_mask) == (0xff, 0xff, 0xff, 0xff)
+            })
+            .find(|info| {
+                let d = info.direct;
+                (d.red_shift, d.green_shift, d.blue_shift, d.alpha_shift)
+                    == (16, 8, 0, depth.into())
+            });
+        if let Some(format) = format
+            && let Some(visual) = formats.screens[screen_num]
+                .depths
+                .iter()
+                .flat_map(|d| &d.visuals)
+                .find(|v| v.format == format.id)
+        {
+            return Ok((format.depth, visual.visual));
+        }
+    }
+    Ok((screen.root_depth, screen.root_visual))
+}
+/// vnc servers and some nested ones don't implement it
+pub fn has_render(conn: &Conn) -> bool {
+    conn.extension_information(x11rb::protocol::render::X11_EXTENSION_NAME)
+        .is_ok_and(|info| info.is_some())
+}
+/// composite 0.2, for the pixmaps of the redirected windows
+pub fn has_composite(conn: &Conn) -> bool {
+    conn.extension_information(composite::X11_EXTENSION_NAME)
+        .is_ok_and(|info| info.is_some())
+        && conn
+            .composite_query_version(0, 4)
+            .ok()
+            .and_then(|cookie| cookie.reply().ok())
+            .is_some_and(|version| (version.major_version, version.minor_version) >= (0, 2))
+}
+/// damage 1.1, to be told when the contents of the windows change
+pub fn has_damage(conn: &Conn) -> bool {
+    conn.extension_information(damage::X11_EXTENSION_NAME)
+        .is_ok_and(|info| info.is_some())
+        && conn
+            .damage_query_version(1, 1)
+            .ok()
+            .and_then(|cookie| cookie.reply().ok())
+            .is_some()
+}
+/// reports the first change to the contents of the window, until `request_damage_subtract`
+pub fn request_damage_watch(conn: &Conn, wid: Window) -> Result<Damage> {
+    let damage = conn.generate_id()?;
+    conn.damage_create(damage, wid, damage::ReportLevel::NON_EMPTY)?;
+    Ok(damage)
+}
+/// forgets the reported changes, the next one is reported again
+pub fn request_damage_subtract(conn: &Conn, damage: Damage) -> Result<()> {
+    conn.damage_subtract(damage, x11rb::NONE, x11rb::NONE)?;
+    Ok(())
+}
+/// keeps the contents of the window offscreen, while the server still draws it as usual, so that
+/// they can be read when it's covered
+pub fn request_window_redirect(conn: &Conn, wid: Window) -> Result<()> {
+    conn.composite_redirect_window(wid, composite::Redirect::AUTOMATIC)?;
+    Ok(())
+}
+/// the contents of a redirected window, scaled down to fit a `size` square
+pub fn get_window_thumbnail(conn: &Conn, wid: Window, size: u32) -> Result<Frame> {
+    let pixmap = conn.generate_id()?;
+    conn.composite_name_window_pixmap(wid, pixmap)?.check()?;
+    let frame = get_drawable_frame(conn, pixmap, size);
+    conn.free_pixmap(pixmap)?;
+    frame
+}
+/// what the window shows on the screen, scaled down to fit a `size` square, it fails while the
+/// window isn't mapped
+pub fn get_window_snapshot(conn: &Conn, wid: Window, size: u32) -> Result<Frame> {
+    get_drawable_frame(conn, wid, size)
+}
+fn get_drawable_frame(conn: &Conn, drawable: Drawable, size: u32) -> Result<Frame> {
+    let g = conn.get_geometry(drawable)?.reply()?;
+    let (w, h) = (g.width as u32, g.height as u32);
+    let image = conn
+        .get_image(ImageFormat::Z_PIXMAP, drawable, 0, 0, g.width, g.height, !0)?
+        .reply()?;
+    if image.depth < 24 || image.data.len() < w as usize * h as usize * 4 {
+        return Err(format!("unsupported window depth: {}", image.depth).into());
+    }
+    let msb = conn.setup().image_byte_order == ImageOrder::MSB_FIRST;
+    let mut frame = Frame::new(w, h);
+    for (px, bgrx) in frame
+        .buf_u32_mut()
+        .iter_mut()
+        .zip(image.data.chunks_exact(4))
+    {
+        let (b, g, r) = if msb {
+            (bgrx[3], bgrx[2], bgrx[1])
+        } else {
+            (bgrx[0], bgrx[1], bgrx[2])
+        };
+        // the alpha of argb windows is dropped, they are shown over nothing
+        *px = u32::from_ne_bytes([b, g, r, 255]);
+    }
+    let factor = size as f32 / w.max(h) as f32;
+    Ok(if factor < 1.0 {
+        frame.scale_area(factor)
+    } else {
+        frame
+    })
+}
+/// the x server is xwayland, a compatibility layer of a wayland compositor
+pub fn is_xwayland(conn: &Conn) -> bool {
+    conn.extension_information("XWAYLAND")
+        .is_ok_and(|info| info.is_some())
+}
+pub fn get_active_window(conn: &Conn, screen: &Screen, atoms: &Atoms) -> Result<Option<Window>> {
+    let prop = conn
+        .get_property(
+            false,
+            screen.root,
+            atoms._NET_ACTIVE_WINDOW,
+            atoms.WINDOW,
+            0,
+            u32::MAX,
+        )?
+        .reply()?;
+
+    Ok(prop.value32().and_then(|mut val| match val.next() {
+        None => None,
+        Some(0) => None,
+        Some(wid) => Some(wid),
+    }))
+}
+/// in root coordinates
+pub fn get_pointer_position(conn: &Conn, screen: &Screen) -> Result<(i32, i32)> {
+    let pointer = conn.query_pointer(screen.root)?.reply()?;
+    Ok((pointer.root_x as i32, pointer.root_y as i32))
+}
+pub fn get_pointer_origin(conn: &Conn, screen: &Screen, atoms: &Atoms) -> Result<PointerOrigin> {
+    let pointer = conn.query_pointer(screen.root)?.reply()?;
+    Ok(PointerOrigin {
+        x: pointer.root_x as f32,
+        y: pointer.root_y as f32,
+        work_area: get_work_area(conn, screen, atoms)
+            .ok()
+            .flatten()
+            .unwrap_or(Area::of_screen(screen)),
+    })
+}
+/// the `_NET_WORKAREA` of the current desktop, the screen without the panels
+pub fn get_work_area(conn: &Conn, screen: &Screen, atoms: &Atoms) -> Result<Option<Area>> {
+    let desktop = get_current_desktop(conn, screen, atoms)?.unwrap_or(0) as usize;
+    let reply = conn
+        .get_property(
+            false,
+            screen.root,
+            atoms._NET_WORKAREA,
+            atoms.CARDINAL,
+            0,
+            u32::MAX,
+        )?
+        .reply()?;
+    let areas: Vec<u32> = reply.value32().map(Iterator::collect).unwrap_or_default();
+    Ok(areas.chunks_exact(4).nth(desktop).map(|area| {
+        Area::new(
+            area[0] as f32,
+            area[1] as f32,
+            area[2] as f32,
+            area[3] as f32,
+        )
+    }))
+}
+pub fn get_current_desktop(conn: &Conn, screen: &Screen, atoms: &Atoms) -> Result<Option<u32>> {
+    let reply = conn
+        .get_property(
+            false,
+            screen.root,
+            atoms._NET_CURRENT_DESKTOP,
+            atoms.CARDINAL,
+            0,
+            1,
+        )?
+        .reply()?;
+    Ok(reply.value32().and_then(|mut val| val.next()))
+}
+/// the client area and the decorations around it
+pub fn get_window_geometry(conn: &Conn, atoms: &Atoms, wid: Window) -> Result<Geometry> {
+    let geom = conn.get_geometry(wid)?.reply()?;
+    // the geometry is relative to the parent, which is the wm frame for reparented windows
+    let pos = conn.translate_coordinates(wid, geom.root, 0, 0)?.reply()?;
+    let geometry = Geometry {
+        x: pos.dst_x as i32,
+        y: pos.dst_y as i32,
+        w: geom.width as u32,
+        h: geom.height as u32,
+    };
+    let extents = conn
+        .get_property(false, wid, atoms._NET_FRAME_EXTENTS, atoms.CARDINAL, 0, 4)?
+        .reply()?;
+    let extents: Vec<u32> = extents.value32().map(Iterator::collect).unwrap_or_default();
+    Ok(match extents.try_into() {
+        Ok(extents) => geometry.framed(extents),
+        Err(_) => geometry,
+    })
+}
+/// the client with a `WM_STATE` in the subtree of a frame, as icccm looks for it
+pub fn get_frame_client(conn: &Conn, atoms: &Atoms, frame: Window) -> Result<Option<Window>> {
+    if get_window_wm_state(conn, atoms, frame)?.is_some() {
+        return Ok(Some(frame));
+    }
+    for child in conn.query_tree(frame)?.reply()?.children {
+        if let Some(client) = get_frame_client(conn, atoms, child)? {
+            return Ok(Some(client));
+        }
+    }
+    Ok(None)
+}
+pub fn get_monitors(conn: &Conn, screen: &Screen) -> Result<Vec<Monitor>> {
+    let mut monitors = vec![];
+    for info in conn
+        .randr_get_monitors(screen.root, true)?
+        .reply()?
+        .monitors
+    {
+        let name = conn.get_atom_name(info.name)?.reply()?.name;
+        monitors.push(Monitor {
+            name: String::from_utf8_lossy(&name).into_owned(),
+            geometry: Geometry {
+                x: info.x as i32,
+                y: info.y as i32,
+                w: info.width as u32,
+                h: info.height as u32,
+            },
+            primary: info.primary,
+        });
+    }
+    Ok(monitors)
+}
+/// selects the changes of the xsettings manager, if there's one
+pub fn watch_xsettings(conn: &Conn, selection: Atom) -> Result<Option<Window>> {
+    let owner = conn.get_selection_owner(selection)?.reply()?.owner;
+    if owner == x11rb::NONE {
+        return Ok(None);
+    }
+    let mask = EventMask::PROPERTY_CHANGE | EventMask::STRUCTURE_NOTIFY;
+    conn.change_window_attributes(owner, &ChangeWindowAttributesAux::new().event_mask(mask))?;
+    Ok(Some(owner))
+}
+pub fn get_xsettings(
+    conn: &Conn,
+    atoms: &Atoms,
+    owner: Window,
+) -> Result<HashMap<String, XSetting>> {
+    let reply = conn
+        .get_property(
+            false,
+            owner,
+            atoms._XSETTINGS_SETTINGS,
+            atoms._XSETTINGS_SETTINGS,
+            0,
+            u32::MAX,
+        )?
+        .reply()?;
+    parse_xsettings(&reply.value)
+}
+/// decodes the `_XSETTINGS_SETTINGS` property, as laid out in the xsettings specification
+pub fn parse_xsettings(bytes: &[u8]) -> Result<HashMap<String, XSetting>> {
+    let mut settings = HashMap::new();
+    let big_endian = *bytes.first().ok_or("empty xsettings")? == 1;
+    let mut pos = 0;
+    let mut take = |len: usize| -> Result<&[u8]> {
+        let field = bytes.get(pos..pos + len).ok_or("truncated xsettings")?;
+        pos += len.next_multiple_of(4).min(bytes.len() - pos);
+        Ok(field)
+    };
+    let card16 = |b: &[u8]| {
+        let b = [b[0], b[1]];
+        if big_endian {
+            u16::from_be_bytes(b)
+        } else {
+            u16::from_le_bytes(b)
+        }
+    };
+    let card32 = |b: &[u8]| {
+        let b = [b[0], b[1], b[2], b[3]];
+        if big_endian {
+            u32::from_be_bytes(b)
+        } else {
+            u32::from_le_bytes(b)
+        }
+    };
+    take(4)?; // byte order and padding
+    take(4)?; // serial
+    let count = card32(take(4)?);
+    for _ in 0..count {
+        let header = take(4)?;
+        let (kind, name_len) = (header[0], card16(&header[2..]));
+        let name = String::from_utf8_lossy(take(name_len as usize)?).into_owned();
+        take(4)?; // last change serial
+        let value = match kind {
+            0 => XSetting::Int(card32(take(4)?) as i32),
+            1 => {
+                let len = card32(take(4)?);
+                XSetting::String(String::from_utf8_lossy(take(len as usize)?).into_owned())
+            }
+            2 => {
+                let color = take(8)?;
+                XSetting::Color([0, 2, 4, 6].map(|i| card16(&color[i..])))
+            }
+            _ => return Err(format!("unknown xsetting type `{kind}`").into()),
+        };
+        settings.insert(name, value);
+    }
+    Ok(settings)
+}
+/// gtk only publishes the theme in use, dark variants are told apart by name
+pub fn color_scheme(settings: &HashMap<String, XSetting>) -> ColorScheme {
+    if settings.get("Gtk/ApplicationPreferDarkTheme") == Some(&XSetting::Int(1)) {
+        return ColorScheme::Dark;
+    }
+    match settings.get("Net/ThemeName") {
+        Some(XSetting::String(name)) if !name.to_lowercase().contains("dark") => ColorScheme::Light,
+        _ => ColorScheme::Dark,
+    }
+}
+pub fn get_desktop_names(conn: &Conn, screen: &Screen, atoms: &Atoms) -> Result<Vec<String>> {
+    let reply = conn
+        .get_property(
+            false,
+            screen.root,
+            atoms._NET_DESKTOP_NAMES,
+            atoms.UTF8_STRING,
+            0,
+            u32::MAX,
+        )?
+        .reply()?;
+    // the names are null terminated
+    let names = String::from_utf8_lossy(&reply.value);
+    let names = names.strip_suffix('\0').unwrap_or(&names);
+    if names.is_empty() {
+        return Ok(vec![]);
+    }
+    Ok(names.split('\0').map(str::to_string).collect())
+}
+pub fn get_window_desktop(conn: &Conn, atoms: &Atoms, wid: Window) -> Result<Option<u32>> {
+    let reply = conn
+        .get_property(false, wid, atoms._NET_WM_DESKTOP, atoms.CARDINAL, 0, 1)?
+        .reply()?;
+    Ok(reply.value32().and_then(|mut val| val.next()))
+}
+/// how the windows are listed, decided at startup by what the wm supports
+pub struct WindowSource {
+    // bare wms might not support ewmh, in that case the window tree is tracked instead
+    pub use_tree: bool,
+    // the children of the root window are listed in stacking order
+    pub stacking_tree: bool,
+    // override redirect windows are never listed by the wm. kept apart from the config, which is
+    // reloaded when the desktop changes its scaling
+    pub override_redirect_classes: Vec<String>,
+}
+impl WindowSource {
+    pub fn new(conn: &Conn, screen: &Screen, atoms: &Atoms, conf: &Config) -> Self {
+        let use_tree = get_windows(conn, screen, atoms).is_err();
+        let stacking_tree = conf.sort == Sort::Stacking
+            && (use_tree || get_windows_stacking(conn, screen, atoms).is_err());
+        Self {
+            use_tree,
+            stacking_tree,
+            override_redirect_classes: conf.override_redirect_classes.clone(),
+        }
+    }
+    pub fn tracks_override_redirect(&self) -> bool {
+        !self.override_redirect_classes.is_empty()
+    }
+    pub fn list(&self, conn: &Conn, screen: &Screen, atoms: &Atoms) -> Result<Vec<Window>> {
+        let mut wids = if self.use_tree {
+            get_windows_from_tree(conn, screen, atoms)?
+        } else {
+            get_windows(conn, screen, atoms)?
+        };
+        if self.tracks_override_redirect() {
+            let classes = &self.override_redirect_classes;
+            wids.extend(get_override_redirect_windows(conn, screen, atoms, classes)?);
+        }
+        Ok(wids)
+    }
+    pub fn list_stacking(
+        &self,
+        conn: &Conn,
+        screen: &Screen,
+        atoms: &Atoms,
+    ) -> Result<Vec<Window>> {
+        if self.stacking_tree {
+            get_windows_from_tree(conn, screen, atoms)
+        } else {
+            get_windows_stacking(conn, screen, atoms)
+        }
+    }
+}
+pub fn get_windows(conn: &Conn, screen: &Screen, atoms: &Atoms) -> Result<Vec<Window>> {
+    let net_client_list = conn.intern_atom(false, b"_NET_CLIENT_LIST")?.reply()?.atom;
+    let prop = conn
+        .get_property(
+            false,
+            screen.root,
+            net_client_list,
+            atoms.WINDOW,
+            0,
+            u32::MAX,
+        )?
+        .reply()?;
+    let windows = prop
+        .value32()
+        .ok_or("failed to extract windows")?
+        .collect::<Vec<_>>();
+    Ok(windows)
+}
+pub fn get_windows_stacking(conn: &Conn, screen: &Screen, atoms: &Atoms) -> Result<Vec<Window>> {
+    let prop = conn
+        .get_property(
+            false,
+            screen.root,
+            atoms._NET_CLIENT_LIST_STACKING,
+            atoms.WINDOW,
+            0,
+            u32::MAX,
+        )?
+        .reply()?;
+    let windows = prop
+        .value32()
+        .ok_or("failed to extract windows")?
+        .collect::<Vec<_>>();
+    Ok(windows)
+}
+pub fn get_windows_from_tree(conn: &Conn, screen: &Screen, atoms: &Atoms) -> Result<Vec<Window>> {
+    let has_wm_state = |wid: Window| -> Result<bool> {
+        let reply = conn
+            .get_property(false, wid, atoms.WM_STATE, AtomEnum::ANY, 0, 0)?
+            .reply()?;
+        Ok(reply.type_ != x11rb::NONE)
+    };
+    let mut windows = vec![];
+    for child in conn.query_tree(screen.root)?.reply()?.children {
+        let Ok(attr) = conn.get_window_attributes(child)?.reply() else {
+            continue;
+        };
+        if attr.override_redirect || attr.map_state != MapState::VIEWABLE {
+            continue;
+        }
+        if has_wm_state(child).unwrap_or(false) {
+            windows.push(child);
+            continue;
+        }
+        // reparenting wms set WM_STATE on the client window inside the frame
+        let Ok(tree) = conn.query_tree(child)?.reply() else {
+            continue;
+        };
+        for grandchild in tree.children {
+            if has_wm_state(grandchild).unwrap_or(false) {
+                windows.push(grandchild);
+                break;
+            }
+        }
+    }
+    Ok(windows)
+}
+pub fn get_override_redirect_windows(
+    conn: &Conn,
+    screen: &Screen,
+    atoms: &Atoms,
+    classes: &[String],
+) -> Result<Vec<Window>> {
+    let mut windows = vec![];
+    for child in conn.query_tree(screen.root)?.reply()?.children {
+        let Ok(attr) = conn.get_window_attributes(child)?.reply() else {
+            continue;
+        };
+        if !attr.override_redirect || attr.map_state != MapState::VIEWABLE {
+            continue;
+        }
+        if let Ok(class) = get_window_class(conn, atoms, child)
+            && class_matches(&class, classes)
+        {
+            windows.push(child);
+        }
+    }
+    Ok(windows)
+}
+pub fn get_window_title(conn: &Conn, atoms: &Atoms, wid: Window) -> Result<String> {
+    let bytes: Result<Vec<u8>> = conn
+        .get_property(
+            false,
+            wid,
+            atoms._NET_WM_NAME,
+            atoms.UTF8_STRING,
+            0,
+            u32::MAX,
+        )
+        .map_err(Into::into)
+        .and_then(|prop| prop.reply().map(|v| v.value).map_err(Into::into));
+    if let Ok(bytes) = bytes {
+        return Ok(sanitize_title(&String::from_utf8(bytes)?));
+    }
+    let bytes = conn
+        .get_property(false, wid, atoms.WM_NAME, atoms.UTF8_STRING, 0, u32::MAX)?
+        .reply()?
+        .value;
+    Ok(sanitize_title(&String::from_utf8(bytes)?))
+}
+/// `_NET_WM_ICON_NAME`, falling back to the `WM_ICON_NAME` of older clients
+pub fn get_window_icon_name(conn: &Conn, atoms: &Atoms, wid: Window) -> Result<Option<String>> {
+    let bytes = conn
+        .get_property(
+            false,
+            wid,
+            atoms._NET_WM_ICON_NAME,
+            atoms.UTF8_STRING,
+            0,
+            u32::MAX,
+        )?
+        .reply()?
+        .value;
+    if !bytes.is_empty() {
+        return Ok(Some(sanitize_title(&String::from_utf8(bytes)?)));
+    }
+    let bytes = conn
+        .get_property(false, wid, atoms.WM_ICON_NAME, AtomEnum::ANY, 0, u32::MAX)?
+        .reply()?
+        .value;
+    Ok((!bytes.is_empty()).then(|| sanitize_title(&String::from_utf8_lossy(&bytes))))
+}
+/// `WM_WINDOW_ROLE`, set by applications with several kinds of windows, like gimp
+pub fn get_window_role(conn: &Conn, atoms: &Atoms, wid: Window) -> Result<Option<String>> {
+    let bytes = conn
+        .get_property(false, wid, atoms.WM_WINDOW_ROLE, AtomEnum::ANY, 0, u32::MAX)?
+        .reply()?
+        .value;
+    Ok((!bytes.is_empty()).then(|| sanitize_title(&String::from_utf8_lossy(&bytes))))
+}
+/// `_GOTO_ALIAS`, see `--alias`
+pub fn get_window_alias(conn: &Conn, atoms: &Atoms, wid: Window) -> Result<Option<String>> {
+    let bytes = conn
+        .get_property(
+            false,
+            wid,
+            atoms._GOTO_ALIAS,
+            atoms.UTF8_STRING,
+            0,
+            u32::MAX,
+        )?
+        .reply()?
+        .value;
+    Ok((!bytes.is_empty()).then(|| sanitize_title(&String::from_utf8_lossy(&bytes))))
+}
+/// `_NET_WM_VISIBLE_NAME`, the title as the wm shows it, like `term <2>` for duplicates
+pub fn get_window_visible_name(conn: &Conn, atoms: &Atoms, wid: Window) -> Result<Option<String>> {
+    let bytes = conn
+        .get_property(
+            false,
+            wid,
+            atoms._NET_WM_VISIBLE_NAME,
+            atoms.UTF8_STRING,
+            0,
+            u32::MAX,
+        )?
+        .reply()?
+        .value;
+    Ok((!bytes.is_empty()).then(|| sanitize_title(&String::from_utf8_lossy(&bytes))))
+}
+pub fn get_window_class(conn: &Conn, atoms: &Atoms, wid: Window) -> Result<(String, String)> {
+    let bytes = conn
+        .get_property(false, wid, atoms.WM_CLASS, atoms.STRING, 0, u32::MAX)?
+        .reply()?
+        .value;
+    let mut parts = bytes.split(|b| *b == 0);
+    let instance = parts
+        .next()
+        .and_then(|s| String::from_utf8(s.to_vec()).ok())
+        .unwrap_or_default();
+    let class = parts
+        .next()
+        .and_then(|s| String::from_utf8(s.to_vec()).ok())
+        .unwrap_or_default();
+    Ok((instance, class))
+}
+pub fn get_window_protocols(conn: &Conn, atoms: &Atoms, wid: Window) -> Result<Vec<Atom>> {
+    let reply = conn
+        .get_property(false, wid, atoms.WM_PROTOCOLS, atoms.ATOM, 0, u32::MAX)?
+        .reply()?;
+    Ok(reply.value32().map(Iterator::collect).unwrap_or_default())
+}
+pub fn get_window_states(conn: &Conn, atoms: &Atoms, wid: Window) -> Result<Vec<Atom>> {
+    let reply = conn
+        .get_property(false, wid, atoms._NET_WM_STATE, atoms.ATOM, 0, u32::MAX)?
+        .reply()?;
+    Ok(reply.value32().map(Iterator::collect).unwrap_or_default())
+}
+/// the icccm state of the client, `ICONIC_STATE` when minimized
+pub fn get_window_wm_state(conn: &Conn, atoms: &Atoms, wid: Window) -> Result<Option<u32>> {
+    let reply = conn
+        .get_property(false, wid, atoms.WM_STATE, atoms.WM_STATE, 0, 1)?
+        .reply()?;
+    Ok(reply.value32().and_then(|mut state| state.next()))
+}
+pub fn get_window_parent(conn: &Conn, atoms: &Atoms, wid: Window) -> Result<Option<Window>> {
+    let reply = conn
+        .get_property(false, wid, atoms.WM_TRANSIENT_FOR, atoms.WINDOW, 0, 1)?
+        .reply()?;
+    if reply.value_len == 0 {
+        Ok(None)
+    } else {
+        let window_id = u32::from_ne_bytes(reply.value[..4].try_into()?);
+        Ok(Some(window_id))
+    }
+}
+/// `WM_CLIENT_MACHINE`, the host the client runs on
+pub fn get_window_client_machine(conn: &Conn, wid: Window) -> Result<Option<String>> {
+    let bytes = conn
+        .get_property(
+            false,
+            wid,
+            AtomEnum::WM_CLIENT_MACHINE,
+            AtomEnum::ANY,
+            0,
+            u32::MAX,
+        )?
+        .reply()?
+        .value;
+    Ok((!bytes.is_empty()).then(|| String::from_utf8_lossy(&bytes).into_owned()))
+}
+pub fn get_window_pid(conn: &Conn, atoms: &Atoms, wid: Window) -> Result<Option<u32>> {
+    let reply = conn
+        .get_property::<_, u32>(false, wid, atoms._NET_WM_PID, atoms.CARDINAL, 0, 1)?
+        .reply()?;
+    let mut pids = reply.value32().ok_or_else(|| "no pid".to_string())?;
+    Ok(pids.next())
+}
+pub fn get_dpi(
+    db: &Database,
+    screen: &Screen,
+    xsettings: &HashMap<String, XSetting>,
+) -> Result<f32> {
+    // in 1024ths of a dot per inch, -1 when unset
+    if let Some(XSetting::Int(dpi)) = xsettings.get("Xft/DPI")
+        && *dpi > 0
+    {
+        return Ok(*dpi as f32 / 1024.0);
+    }
+    if let Ok(Some(dpi)) = db.get_value("Xft.dpi", "") {
+        return Ok(dpi);
+    }
+    let dpi_x = screen.width_in_pixels as f32 * INCH_TO_MM / screen.width_in_millimeters as f32;
+    let dpi_y = screen.height_in_pixels as f32 * INCH_TO_MM / screen.height_in_millimeters as f32;
+    let dpi = (dpi_x + dpi_y) / 2.0;
+    Ok(dpi)
+}
+/// focuses the window, or runs the activation command of a provided task, returns the window
+/// that should end up focused
+pub fn request_task_activation(
+    conn: &Conn,
+    screen: &Screen,
+    atoms: &Atoms,
+    conf: &Config,
+    task: &Task,
+) -> Result<Option<Window>> {
+    if let Some(host) = task.host {
+        request_window_focus(conn, screen, atoms, host)?;
+    }
+    if let Some(command) = &task.activate {
+        spawn_shell(command)?;
+        return Ok(task.focus_target());
+    }
+    request_window_activation(conn, screen, atoms, conf, task.wid).map(Some)
+}
+/// focuses the window, then the modal dialog blocking it if any, which some wms leave behind
+pub fn request_window_activation(
+    conn: &Conn,
+    screen: &Screen,
+    atoms: &Atoms,
+    conf: &Config,
+    wid: Window,
+) -> Result<Window> {
+    if conf.unminimize_on_focus {
+        request_window_unminimize(conn, screen, atoms, wid)?;
+    }
+    request_window_focus(conn, screen, atoms, wid)?;
+    let Some(dialog) = get_modal_dialog(conn, screen, atoms, wid) else {
+        return Ok(wid);
+    };
+    request_window_focus(conn, screen, atoms, dialog)?;
+    Ok(dialog)
+}
+/// the modal dialog blocking `wid`, the innermost one when a dialog opened another
+pub fn get_modal_dialog(
+    conn: &Conn,
+    screen: &Screen,
+    atoms: &Atoms,
+    wid: Window,
+) -> Option<Window> {
+    let windows: Vec<_> = get_windows(conn, screen, atoms)
+        .ok()?
+        .into_iter()
+        .filter_map(|w| {
+            let parent = get_window_parent(conn, atoms, w).ok().flatten()?;
+            let modal = get_window_states(conn, atoms, w)
+                .is_ok_and(|states| states.contains(&atoms._NET_WM_STATE_MODAL));
+            Some((w, parent, modal))
+        })
+        .collect();
+    find_modal_dialog(wid, &windows)
+}
+/// follows the modal transients of `wid`, given as `(window, transient for, is modal)`
+pub fn find_modal_dialog(wid: Window, windows: &[(Window, Window, bool)]) -> Option<Window> {
+    let mut dialog = None;
+    let mut target = wid;
+    // bounded, in case the transients form a loop
+    for _ in 0..windows.len() {
+        let Some(&(next, _, _)) = windows
+            .iter()
+            .find(|(w, parent, modal)| *modal && *parent == target && *w != wid)
+        else {
+            break;
+        };
+        dialog = Some(next);
+        target = next;
+    }
+    dialog
+}
+
+#[cfg(test)]
+mod tests {
+    use super::XSetting;
+    use super::color_scheme;
+    use super::parse_xsettings;
+    use crate::config::ColorScheme;
+
+    /// encodes settings the way a little endian xsettings manager does
+    fn xsettings(settings: &[(&str, XSetting)]) -> Vec<u8> {
+        fn padded(out: &mut Vec<u8>, bytes: &[u8]) {
+            out.extend(bytes);
+            out.resize(out.len().next_multiple_of(4), 0);
+        }
+        let mut out = vec![0, 0, 0, 0];
+        out.extend(7u32.to_le_bytes());
+        out.extend((settings.len() as u32).to_le_bytes());
+        for (name, value) in settings {
+            let kind = match value {
+                XSetting::Int(_) => 0,
+                XSetting::String(_) => 1,
+                XSetting::Color(_) => 2,
+            };
+            out.extend([kind, 0]);
+            out.extend((name.len() as u16).to_le_bytes());
+            padded(&mut out, name.as_bytes());
+            out.extend(0u32.to_le_bytes());
+            match value {
+                XSetting::Int(v) => out.extend(v.to_le_bytes()),
+                XSetting::String(v) => {
+                    out.extend((v.len() as u32).to_le_bytes());
+                    padded(&mut out, v.as_bytes());
+                }
+                XSetting::Color(v) => v.iter().for_each(|c| out.extend(c.to_le_bytes())),
+            }
+        }
+        out
+    }
+
+    #[test]
+    fn xsettings_values() {
+        let theme = XSetting::String("Adwaita-dark".to_string());
+        let bytes = xsettings(&[
+            ("Net/ThemeName", theme.clone()),
+            ("Xft/DPI", XSetting::Int(96 * 1024)),
+            ("Net/Color", XSetting::Color([1, 2, 3, 4])),
+        ]);
+        let settings = parse_xsettings(&bytes).unwrap();
+        assert_eq!(settings["Net/ThemeName"], theme);
+        assert_eq!(settings["Xft/DPI"], XSetting::Int(96 * 1024));
+        assert_eq!(settings["Net/Color"], XSetting::Color([1, 2, 3, 4]));
+        assert_eq!(color_scheme(&settings), ColorScheme::Dark);
+        assert!(parse_xsettings(&bytes[..bytes.len() - 2]).is_err());
+        assert!(parse_xsettings(&[]).is_err());
+
+        let light = xsettings(&[("Net/ThemeName", XSetting::String("Adwaita".to_string()))]);
+        let settings = parse_xsettings(&light).unwrap();
+        assert_eq!(color_scheme(&settings), ColorScheme::Light);
+    }
+}